@@ -26,11 +26,49 @@ enum Commands {
         #[arg(short, long)]
         pass: String,
     },
+    Verify2fa{
+        #[arg(short, long)]
+        code: String,
+    },
+    Enroll2fa,
+    Confirm2fa{
+        #[arg(short, long)]
+        code: String,
+    },
+    Disable2fa{
+        #[arg(short, long)]
+        code: String,
+    },
+    Refresh,
+    Logout,
+    VerifyEmail{
+        #[arg(short, long)]
+        token: String,
+    },
+    ResendVerification,
+    ResetPasswordRequest{
+        #[arg(short, long)]
+        email: String,
+    },
+    ResetPassword{
+        #[arg(short, long)]
+        token: String,
+        #[arg(short, long)]
+        pass: String,
+    },
     Create{
         #[arg(short, long)]
         title: String,
         #[arg(short, long)]
         content: String,
+        #[arg(short, long)]
+        status: Option<String>,
+        #[arg(long)]
+        publish_at: Option<String>,
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        #[arg(short, long)]
+        format: Option<String>,
     },
     Update{
         #[arg(short, long)]
@@ -39,6 +77,18 @@ enum Commands {
         title: Option<String>,
         #[arg(short, long)]
         content: Option<String>,
+        #[arg(short, long)]
+        status: Option<String>,
+        #[arg(long)]
+        publish_at: Option<String>,
+        #[arg(short, long)]
+        expected_version: Option<i64>,
+        #[arg(long = "tag")]
+        tags: Option<Vec<String>>,
+        #[arg(long, conflicts_with = "tags")]
+        clear_tags: bool,
+        #[arg(short, long)]
+        format: Option<String>,
     },
     Delete{
         #[arg(short, long)]
@@ -47,12 +97,152 @@ enum Commands {
     Get{
         #[arg(short, long)]
         id: i64,
+        #[arg(short, long)]
+        auth: bool,
+    },
+    GetBySlug{
+        #[arg(short, long)]
+        slug: String,
+        #[arg(short, long)]
+        auth: bool,
     },
     List{
+        #[arg(short, long, default_value_t = 0)]
+        offset: i64,
+        #[arg(short, long)]
+        limit: i64,
+        #[arg(short, long)]
+        auth: bool,
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(short, long)]
+        cursor: Option<String>,
+        #[arg(long)]
+        total: bool,
+    },
+    Tags,
+    Search{
+        #[arg(short, long)]
+        query: String,
+        #[arg(long)]
+        lang: Option<String>,
         #[arg(short, long)]
         offset: i64,
         #[arg(short, long)]
-        limit: i64
+        limit: i64,
+        #[arg(short, long)]
+        auth: bool,
+    },
+    Trash{
+        #[arg(short, long)]
+        offset: i64,
+        #[arg(short, long)]
+        limit: i64,
+    },
+    Untrash{
+        #[arg(short, long)]
+        id: i64,
+    },
+    Purge{
+        #[arg(short, long)]
+        id: i64,
+    },
+    Comments{
+        #[arg(short, long)]
+        id: i64,
+        #[arg(short, long)]
+        auth: bool,
+    },
+    Comment{
+        #[arg(short, long)]
+        id: i64,
+        #[arg(short, long)]
+        content: String,
+        #[arg(short, long)]
+        reply_to: Option<i64>,
+    },
+    CommentEdit{
+        #[arg(short, long)]
+        id: i64,
+        #[arg(short = 'm', long)]
+        comment_id: i64,
+        #[arg(short, long)]
+        content: String,
+    },
+    CommentDelete{
+        #[arg(short, long)]
+        id: i64,
+        #[arg(short = 'm', long)]
+        comment_id: i64,
+    },
+    Revisions{
+        #[arg(short, long)]
+        id: i64,
+    },
+    Revision{
+        #[arg(short, long)]
+        id: i64,
+        #[arg(short, long)]
+        revision: i32,
+    },
+    Diff{
+        #[arg(short, long)]
+        id: i64,
+        #[arg(short, long)]
+        from: i32,
+        #[arg(short, long)]
+        to: i32,
+    },
+    Restore{
+        #[arg(short, long)]
+        id: i64,
+        #[arg(short, long)]
+        revision: i32,
+        #[arg(short, long)]
+        expected_version: Option<i64>,
+    },
+    SetRole{
+        #[arg(short, long)]
+        user_id: i64,
+        #[arg(short, long)]
+        role: String,
+    },
+    TokenCreate{
+        #[arg(short, long)]
+        name: String,
+        #[arg(short, long, required = true)]
+        scope: Vec<String>,
+        #[arg(short, long)]
+        expires_in_days: Option<i64>,
+    },
+    Tokens,
+    TokenRevoke{
+        #[arg(short, long)]
+        id: i64,
+    },
+    Sessions,
+    SessionRevoke{
+        #[arg(short, long)]
+        id: i64,
+    },
+    SessionRevokeAll,
+    ChangePassword{
+        #[arg(short, long)]
+        pass: String,
+        #[arg(short, long)]
+        new_pass: String,
+    },
+    ChangeEmail{
+        #[arg(short, long)]
+        email: String,
+        #[arg(short, long)]
+        pass: String,
+    },
+    DeleteAccount{
+        #[arg(short, long)]
+        pass: String,
+        #[arg(short, long)]
+        reassign_to: Option<i64>,
     }
 }
 
@@ -81,9 +271,33 @@ fn save_token(token: &str) -> Result<(), std::io::Error> {
 }
 
 fn read_token() -> Result<String, std::io::Error> {
+    if let Ok(token) = env::var("BLOG_TOKEN") {
+        return Ok(token);
+    }
     std::fs::read_to_string("token.txt")
 }
 
+fn save_refresh_token(token: &str) -> Result<(), std::io::Error> {
+    std::fs::write("refresh_token.txt", token)
+}
+
+fn read_refresh_token() -> Result<String, std::io::Error> {
+    std::fs::read_to_string("refresh_token.txt")
+}
+
+fn save_challenge(token: &str) -> Result<(), std::io::Error> {
+    std::fs::write("challenge.txt", token)
+}
+
+fn read_challenge() -> Result<String, std::io::Error> {
+    std::fs::read_to_string("challenge.txt")
+}
+
+fn remove_tokens() {
+    let _ = std::fs::remove_file("token.txt");
+    let _ = std::fs::remove_file("refresh_token.txt");
+}
+
 #[tokio::main]
 async fn main() {
     let http_server_addr = env::var("HTTP_SERVER_ADDR").unwrap_or(DEFAULT_HTTP_ADDR.to_string());
@@ -115,6 +329,7 @@ async fn main() {
             print_message(&res, "Regestration complete: token");
             let reg_user = res.unwrap_or_default();
             save_token(&reg_user.token).expect("Can't save token");
+            save_refresh_token(&reg_user.refresh_token).expect("Can't save refresh token");
         }
         Commands::Login { username, pass } => {
             let res =
@@ -129,32 +344,154 @@ async fn main() {
             };
             print_message(&res, "Login complete: token");
             let reg_user = res.unwrap_or_default();
+            if let Some(challenge_token) = reg_user.challenge_token {
+                println!("Two-factor code required, run verify2fa");
+                save_challenge(&challenge_token).expect("Can't save challenge token");
+            } else {
+                save_token(&reg_user.token).expect("Can't save token");
+                save_refresh_token(&reg_user.refresh_token).expect("Can't save refresh token");
+            }
+        }
+        Commands::Verify2fa { code } => {
+            let challenge_token = read_challenge().expect("Can't read challenge token");
+            let res =
+            if cli.grpc {
+                grpc_client.verify_two_factor(challenge_token, code).await
+            }else{
+                http_client.verify_two_factor(&challenge_token, &code).await
+            };
+            print_message(&res, "Login complete: token");
+            if let Ok(reg_user) = res {
+                save_token(&reg_user.token).expect("Can't save token");
+                save_refresh_token(&reg_user.refresh_token).expect("Can't save refresh token");
+                let _ = std::fs::remove_file("challenge.txt");
+            }
+        }
+        Commands::Enroll2fa => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.enroll_totp(&token).await
+            }else{
+                http_client.enroll_totp(&token).await
+            };
+            print_message(&res, "Add the secret to your authenticator app and run confirm2fa");
+        }
+        Commands::Confirm2fa { code } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.confirm_totp(&token, code).await
+            }else{
+                http_client.confirm_totp(&token, &code).await
+            };
+            print_message(&res, "Two-factor enabled, keep the recovery codes safe");
+        }
+        Commands::Disable2fa { code } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.disable_totp(&token, code).await
+            }else{
+                http_client.disable_totp(&token, &code).await
+            };
+            print_message(&res, "Two-factor disabled");
+        }
+        Commands::Refresh => {
+            let refresh_token = read_refresh_token().expect("Can't read refresh token");
+            let res =
+            if cli.grpc {
+                grpc_client.refresh(refresh_token).await
+            }else{
+                http_client.refresh(&refresh_token).await
+            };
+            print_message(&res, "Refresh complete: token");
+            let reg_user = res.unwrap_or_default();
             save_token(&reg_user.token).expect("Can't save token");
+            save_refresh_token(&reg_user.refresh_token).expect("Can't save refresh token");
         }
-        Commands::Create { title, content } => {
+        Commands::Logout => {
             let token = read_token().expect("Can't read token");
+            let refresh_token = read_refresh_token().ok();
             let res =
             if cli.grpc {
-                grpc_client.create_post(&token, title, content).await
+                grpc_client.logout(&token, refresh_token).await
+            }else{
+                http_client.logout(&token, refresh_token.as_deref()).await
+            };
+            print_message(&res, "Logout complete");
+            remove_tokens();
+        }
+        Commands::VerifyEmail { token } => {
+            let res =
+            if cli.grpc {
+                grpc_client.verify_email(token).await
+            }else{
+                http_client.verify_email(&token).await
+            };
+            print_message(&res, "Email verified");
+        }
+        Commands::ResendVerification => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.resend_verification(&token).await
+            }else{
+                http_client.resend_verification(&token).await
+            };
+            print_message(&res, "Verification mail sent");
+        }
+        Commands::ResetPasswordRequest { email } => {
+            let res =
+            if cli.grpc {
+                grpc_client.request_password_reset(email).await
+            }else{
+                http_client.request_password_reset(&email).await
+            };
+            print_message(&res, "Password reset requested, check your mail");
+        }
+        Commands::ResetPassword { token, pass } => {
+            let res =
+            if cli.grpc {
+                grpc_client.confirm_password_reset(token, pass).await
+            }else{
+                http_client.confirm_password_reset(&token, &pass).await
+            };
+            print_message(&res, "Password changed");
+        }
+        Commands::Create { title, content, status, publish_at, tags, format } => {
+            let token = read_token().expect("Can't read token");
+            let create_req = pod::NewPost{
+                title,
+                content,
+                status,
+                publish_at,
+                tags,
+                content_format: format,
+            };
+            let res =
+            if cli.grpc {
+                grpc_client.create_post(&token, create_req).await
             }else{
-                let create_req = pod::NewPost{
-                    title,
-                    content,
-                };
                 http_client.create_post(&token, create_req).await
             };
             print_message(&res, "Create post: ");
         }
-        Commands::Update { id, title, content } => {
+        Commands::Update { id, title, content, status, publish_at, expected_version, tags, clear_tags, format } => {
             let token = read_token().expect("Can't read token");
+            let update_req = pod::UpdatePost{
+                title,
+                content,
+                status,
+                publish_at,
+                tags: if clear_tags { Some(Vec::new()) } else { tags },
+                content_format: format,
+                expected_version,
+            };
             let res =
             if cli.grpc {
-                grpc_client.update_post(&token, id, title, content).await
+                grpc_client.update_post(&token, id, update_req).await
             }else{
-                let update_req = pod::UpdatePost{
-                    title,
-                    content,
-                };
                 http_client.update_post(&token, pod::PostId{id}, update_req).await
             };
             print_message(&res, "Update post: ");
@@ -169,23 +506,285 @@ async fn main() {
             };
             print_message(&res, "Delete post: ");
         }
-        Commands::Get { id} => {
+        Commands::Get { id, auth } => {
+            let token = auth.then(|| read_token().expect("Can't read token"));
             let res =
             if cli.grpc {
-                grpc_client.get_post(id).await
+                grpc_client.get_post(token.as_deref(), id).await
             }else{
-                http_client.get_post(pod::PostId{id}).await
+                http_client.get_post(token.as_deref(), pod::PostId{id}).await
             };
             print_message(&res, "Get post: ");
         }
-        Commands::List { offset, limit} => {
+        Commands::GetBySlug { slug, auth } => {
+            let token = auth.then(|| read_token().expect("Can't read token"));
+            let res =
+            if cli.grpc {
+                grpc_client.get_post_by_slug(token.as_deref(), &slug).await
+            }else{
+                http_client.get_post_by_slug(token.as_deref(), &slug).await
+            };
+            print_message(&res, "Post");
+        }
+        Commands::List { offset, limit, auth, tag, cursor, total } => {
+            let token = auth.then(|| read_token().expect("Can't read token"));
             let res =
             if cli.grpc {
-                grpc_client.get_posts(offset, limit).await
+                grpc_client.get_posts(token.as_deref(), offset, limit, tag.as_deref(), cursor.as_deref(), total).await
             }else{
-                http_client.get_posts(offset, limit).await
+                http_client.get_posts(token.as_deref(), offset, limit, tag.as_deref(), cursor.as_deref(), total).await
             };
             print_message(&res, "List posts: ");
         }
+        Commands::Tags => {
+            let res =
+            if cli.grpc {
+                grpc_client.list_tags().await
+            }else{
+                http_client.list_tags().await
+            };
+            print_message(&res, "Tags");
+        }
+        Commands::Search { query, lang, offset, limit, auth } => {
+            let token = auth.then(|| read_token().expect("Can't read token"));
+            let res =
+            if cli.grpc {
+                grpc_client.search_posts(token.as_deref(), &query, lang.as_deref(), offset, limit).await
+            }else{
+                http_client.search_posts(token.as_deref(), &query, lang.as_deref(), offset, limit).await
+            };
+            print_message(&res, "Found posts");
+        }
+        Commands::Trash { offset, limit } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.get_trash(&token, offset, limit).await
+            }else{
+                http_client.get_trash(&token, offset, limit).await
+            };
+            print_message(&res, "Trash");
+        }
+        Commands::Untrash { id } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.restore_post(&token, id).await
+            }else{
+                http_client.restore_post(&token, pod::PostId{id}).await
+            };
+            print_message(&res, "Post restored from trash");
+        }
+        Commands::Purge { id } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.purge_post(&token, id).await
+            }else{
+                http_client.purge_post(&token, pod::PostId{id}).await
+            };
+            print_message(&res, "Post purged");
+        }
+        Commands::Comments { id, auth } => {
+            let token = auth.then(|| read_token().expect("Can't read token"));
+            let res =
+            if cli.grpc {
+                grpc_client.list_comments(token.as_deref(), id).await
+            }else{
+                http_client.list_comments(token.as_deref(), pod::PostId{id}).await
+            };
+            print_message(&res, "Comments");
+        }
+        Commands::Comment { id, content, reply_to } => {
+            let token = read_token().expect("Can't read token");
+            let new_comment = pod::NewComment{content, parent_id: reply_to};
+            let res =
+            if cli.grpc {
+                grpc_client.add_comment(&token, id, new_comment).await
+            }else{
+                http_client.add_comment(&token, pod::PostId{id}, new_comment).await
+            };
+            print_message(&res, "Comment added");
+        }
+        Commands::CommentEdit { id, comment_id, content } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.update_comment(&token, id, comment_id, &content).await
+            }else{
+                http_client.update_comment(&token, pod::PostId{id}, comment_id, &content).await
+            };
+            print_message(&res, "Comment updated");
+        }
+        Commands::CommentDelete { id, comment_id } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.delete_comment(&token, id, comment_id).await
+            }else{
+                http_client.delete_comment(&token, pod::PostId{id}, comment_id).await
+            };
+            print_message(&res, "Comment deleted");
+        }
+        Commands::Revisions { id } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.list_revisions(&token, id).await
+            }else{
+                http_client.list_revisions(&token, pod::PostId{id}).await
+            };
+            print_message(&res, "Post revisions");
+        }
+        Commands::Revision { id, revision } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.get_revision(&token, id, revision).await
+            }else{
+                http_client.get_revision(&token, pod::PostId{id}, revision).await
+            };
+            print_message(&res, "Post revision");
+        }
+        Commands::Diff { id, from, to } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.diff_revisions(&token, id, from, to).await
+            }else{
+                http_client.diff_revisions(&token, pod::PostId{id}, from, to).await
+            };
+            print_message(&res, "Revision diff");
+        }
+        Commands::Restore { id, revision, expected_version } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.restore_revision(&token, id, revision, expected_version).await
+            }else{
+                http_client.restore_revision(&token, pod::PostId{id}, revision, expected_version).await
+            };
+            print_message(&res, "Post restored");
+        }
+        Commands::SetRole { user_id, role } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.set_user_role(&token, user_id, role).await
+            }else{
+                http_client.set_user_role(&token, user_id, &role).await
+            };
+            print_message(&res, "Set role: ");
+        }
+        Commands::TokenCreate { name, scope, expires_in_days } => {
+            let token = read_token().expect("Can't read token");
+            let create_req = pod::CreatePersonalTokenReq{
+                name,
+                scopes: scope,
+                expires_in_days,
+            };
+            let res =
+            if cli.grpc {
+                grpc_client.create_personal_token(&token, create_req).await
+            }else{
+                http_client.create_personal_token(&token, create_req).await
+            };
+            print_message(&res, "Personal access token created, it is shown only once");
+        }
+        Commands::Tokens => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.list_personal_tokens(&token).await
+            }else{
+                http_client.list_personal_tokens(&token).await
+            };
+            print_message(&res, "Personal access tokens: ");
+        }
+        Commands::TokenRevoke { id } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.revoke_personal_token(&token, id).await
+            }else{
+                http_client.revoke_personal_token(&token, id).await
+            };
+            print_message(&res, "Personal access token revoked");
+        }
+        Commands::Sessions => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.list_sessions(&token).await
+            }else{
+                http_client.list_sessions(&token).await
+            };
+            print_message(&res, "Active sessions: ");
+        }
+        Commands::SessionRevoke { id } => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.revoke_session(&token, id).await
+            }else{
+                http_client.revoke_session(&token, id).await
+            };
+            print_message(&res, "Session revoked");
+        }
+        Commands::SessionRevokeAll => {
+            let token = read_token().expect("Can't read token");
+            let res =
+            if cli.grpc {
+                grpc_client.revoke_all_sessions(&token).await
+            }else{
+                http_client.revoke_all_sessions(&token).await
+            };
+            print_message(&res, "All sessions revoked");
+        }
+        Commands::ChangePassword { pass, new_pass } => {
+            let token = read_token().expect("Can't read token");
+            let change_req = pod::ChangePasswordReq{
+                current_password: pass,
+                new_password: new_pass,
+            };
+            let res =
+            if cli.grpc {
+                grpc_client.change_password(&token, change_req).await
+            }else{
+                http_client.change_password(&token, change_req).await
+            };
+            print_message(&res, "Password changed");
+        }
+        Commands::ChangeEmail { email, pass } => {
+            let token = read_token().expect("Can't read token");
+            let change_req = pod::ChangeEmailReq{
+                email,
+                password: pass,
+            };
+            let res =
+            if cli.grpc {
+                grpc_client.change_email(&token, change_req).await
+            }else{
+                http_client.change_email(&token, change_req).await
+            };
+            print_message(&res, "Email changed, check the mail to confirm it");
+        }
+        Commands::DeleteAccount { pass, reassign_to } => {
+            let token = read_token().expect("Can't read token");
+            let delete_req = pod::DeleteAccountReq{
+                password: pass,
+                reassign_posts_to: reassign_to,
+            };
+            let res =
+            if cli.grpc {
+                grpc_client.delete_account(&token, delete_req).await
+            }else{
+                http_client.delete_account(&token, delete_req).await
+            };
+            print_message(&res, "Account deleted");
+            if res.is_ok() {
+                remove_tokens();
+            }
+        }
     }
 }
@@ -10,9 +10,21 @@ pub enum ClientError {
     /// Попытка выполнить неавторизованный запрос
     #[error("User unauthorized: {0}")]
     Unauthorized(String),
+    /// Недостаточно прав для выполнения запроса
+    #[error("Access denied: {0}")]
+    Forbidden(String),
+    /// Некорректные параметры запроса
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    /// Слишком много неудачных попыток, запрос нужно повторить позже
+    #[error("Too many attempts: {0}")]
+    TooManyAttempts(String),
     /// Запись не найдена
     #[error("Not found: {0}")]
     NotFound(String),
+    /// Пост изменился после версии, на основе которой сделана правка
+    #[error("Version conflict: {0}")]
+    VersionConflict(String),
     /// Неизвестная ошибка сервера
     #[error("Unknown server error: {0}")]
     UnknownServerErr(String),
@@ -31,6 +43,12 @@ impl From<reqwest::Error> for ClientError {
             StatusCode::CONFLICT => Self::AlreadyExists(format!("{err}")),
             StatusCode::UNAUTHORIZED => Self::Unauthorized(format!("{err}")),
             StatusCode::NOT_FOUND => Self::NotFound(format!("{err}")),
+            StatusCode::FORBIDDEN => Self::Forbidden(format!("{err}")),
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                Self::InvalidRequest(format!("{err}"))
+            }
+            StatusCode::TOO_MANY_REQUESTS => Self::TooManyAttempts(format!("{err}")),
+            StatusCode::PRECONDITION_FAILED => Self::VersionConflict(format!("{err}")),
             _ => Self::UnknownServerErr(format!("{err}")),
         }
     }
@@ -42,6 +60,10 @@ impl From<tonic::Status> for ClientError {
             tonic::Code::AlreadyExists => Self::AlreadyExists(format!("{err_status}")),
             tonic::Code::Unauthenticated => Self::Unauthorized(format!("{err_status}")),
             tonic::Code::NotFound => Self::NotFound(format!("{err_status}")),
+            tonic::Code::PermissionDenied => Self::Forbidden(format!("{err_status}")),
+            tonic::Code::InvalidArgument => Self::InvalidRequest(format!("{err_status}")),
+            tonic::Code::ResourceExhausted => Self::TooManyAttempts(format!("{err_status}")),
+            tonic::Code::FailedPrecondition => Self::VersionConflict(format!("{err_status}")),
             _ => Self::UnknownServerErr(format!("{err_status}")),
         }
     }
@@ -1,11 +1,27 @@
-mod proto;
+mod proto {
+    include!("proto.rs");
 
-use proto::blog_service_client::BlogServiceClient;
+    pub mod v2 {
+        include!("proto.v2.rs");
+    }
+}
+
+use proto::v2::blog_service_client::BlogServiceClient;
 use proto::*;
 
 use crate::error::ClientError;
 use crate::pod;
 
+impl From<RegisteredUser> for pod::RegisteredUser {
+    fn from(value: RegisteredUser) -> Self {
+        Self {
+            token: value.token,
+            refresh_token: value.refresh_token,
+            challenge_token: Some(value.challenge_token).filter(|token| !token.is_empty()),
+        }
+    }
+}
+
 impl From<PostInfo> for pod::PostInfo {
     fn from(value: PostInfo) -> Self {
         Self {
@@ -15,22 +31,127 @@ impl From<PostInfo> for pod::PostInfo {
             author_id: value.author_id,
             created_at: value.created_at,
             updated_at: value.updated_at,
+            status: value.status,
+            published_at: value.published_at,
+            version: value.version,
+            deleted_at: value.deleted_at,
+            tags: value.tags,
+            slug: value.slug,
+            content_format: value.content_format,
+            content_html: value.content_html,
+        }
+    }
+}
+
+impl From<PersonalToken> for pod::PersonalTokenInfo {
+    fn from(value: PersonalToken) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            scopes: value.scopes,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+            token: value.token,
         }
     }
 }
 
+impl From<Session> for pod::SessionInfo {
+    fn from(value: Session) -> Self {
+        Self {
+            id: value.id,
+            user_agent: value.user_agent,
+            ip_address: value.ip_address,
+            created_at: value.created_at,
+            last_seen_at: value.last_seen_at,
+            current: value.current,
+        }
+    }
+}
+
+impl From<PostRevision> for pod::RevisionInfo {
+    fn from(value: PostRevision) -> Self {
+        Self {
+            post_id: value.post_id,
+            revision: value.revision,
+            title: value.title,
+            content: value.content,
+            editor_id: value.editor_id,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<DiffChunk> for pod::DiffChunk {
+    fn from(value: DiffChunk) -> Self {
+        Self {
+            op: value.op,
+            text: value.text,
+        }
+    }
+}
+
+impl From<PostRevisionDiff> for pod::RevisionDiff {
+    fn from(value: PostRevisionDiff) -> Self {
+        Self {
+            post_id: value.post_id,
+            from: value.from,
+            to: value.to,
+            title: value.title.into_iter().map(pod::DiffChunk::from).collect(),
+            content: value.content.into_iter().map(pod::DiffChunk::from).collect(),
+        }
+    }
+}
+
+impl From<Comment> for pod::CommentInfo {
+    fn from(value: Comment) -> Self {
+        Self {
+            id: value.id,
+            post_id: value.post_id,
+            parent_id: value.parent_id,
+            author_id: value.author_id,
+            content: value.content,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            depth: value.depth,
+            deleted: value.deleted,
+        }
+    }
+}
+
+/// Запрос с токеном в метаданных `authorization: Bearer <token>`
+fn authorized<T>(token: &str, message: T) -> Result<tonic::Request<T>, ClientError> {
+    let value = format!("Bearer {token}")
+        .parse()
+        .map_err(|_| ClientError::InvalidRequest("invalid token".to_string()))?;
+    let mut request = tonic::Request::new(message);
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+/// Запрос с токеном в метаданных, если токен передан
+fn maybe_authorized<T>(token: Option<&str>, message: T) -> Result<tonic::Request<T>, ClientError> {
+    match token {
+        Some(token) => authorized(token, message),
+        None => Ok(tonic::Request::new(message)),
+    }
+}
+
 /// Клиент для взаимодействия с сервером по протоколу gRPC
 /// Пример:
-/// ```rust
+/// ```rust,no_run
 /// use blog_client::grpc_client::GrpcClient;
-/// use blog_client::error::ClientError;
-/// 
+///
 /// #[tokio::main]
 /// async fn main() {
-///     let mut grpc_client = match GrpcClient::connect("http://127.0.0.1:50051").await.unwrap();
-///     let reg_resp = grpc_client.register("NewName", "NewMail", "NewPass").await.unwrap();
+///     let mut grpc_client = GrpcClient::connect("http://127.0.0.1:50051").await.unwrap();
+///     let reg_resp = grpc_client
+///         .register("NewName".to_string(), "NewMail".to_string(), "NewPass".to_string())
+///         .await
+///         .unwrap();
 /// }
-/// ``` 
+/// ```
 pub struct GrpcClient {
     client: BlogServiceClient<tonic::transport::Channel>,
 }
@@ -64,10 +185,7 @@ impl GrpcClient {
             .await?
             .into_inner();
 
-        let reg_user = pod::RegisteredUser {
-            token: response.token,
-        };
-        Ok(reg_user)
+        Ok(response.into())
     }
 
     /// Вход зарегистрированного пользователя
@@ -82,27 +200,170 @@ impl GrpcClient {
             .await?
             .into_inner();
 
-        let reg_user = pod::RegisteredUser {
-            token: response.token,
-        };
-        Ok(reg_user)
+        Ok(response.into())
+    }
+
+    /// Обновление пары токенов по refresh-токену. Использованный refresh-токен становится недействительным
+    pub async fn refresh(&mut self, refresh_token: String) -> Result<pod::RegisteredUser, ClientError> {
+        let response = self
+            .client
+            .refresh(RefreshRequest { refresh_token })
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Второй шаг входа: обмен токена, полученного при входе, и одноразового кода на пару токенов
+    pub async fn verify_two_factor(
+        &mut self,
+        challenge_token: String,
+        code: String,
+    ) -> Result<pod::RegisteredUser, ClientError> {
+        let response = self
+            .client
+            .verify_two_factor(VerifyTwoFactorRequest {
+                challenge_token,
+                code,
+            })
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Начало подключения двухфакторной аутентификации (Использует токен, полученный при авторизации)
+    pub async fn enroll_totp(&mut self, token: &str) -> Result<pod::TotpEnrollment, ClientError> {
+        let response = self
+            .client
+            .enroll_totp(authorized(token, v2::EnrollTotpRequest {})?)
+            .await?
+            .into_inner();
+
+        Ok(pod::TotpEnrollment {
+            secret: response.secret,
+            otpauth_uri: response.otpauth_uri,
+        })
+    }
+
+    /// Подтверждение подключения двухфакторной аутентификации кодом из приложения
+    /// (Использует токен, полученный при авторизации)
+    pub async fn confirm_totp(
+        &mut self,
+        token: &str,
+        code: String,
+    ) -> Result<pod::RecoveryCodes, ClientError> {
+        let response = self
+            .client
+            .confirm_totp(authorized(token, v2::TotpCodeRequest { code })?)
+            .await?
+            .into_inner();
+
+        Ok(pod::RecoveryCodes {
+            recovery_codes: response.recovery_codes,
+        })
+    }
+
+    /// Отключение двухфакторной аутентификации (Использует токен, полученный при авторизации)
+    pub async fn disable_totp(&mut self, token: &str, code: String) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .disable_totp(authorized(token, v2::TotpCodeRequest { code })?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Выход пользователя. Токен доступа и переданный refresh-токен отзываются на сервере
+    pub async fn logout(
+        &mut self,
+        token: &str,
+        refresh_token: Option<String>,
+    ) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .logout(authorized(token, v2::LogoutRequest { refresh_token })?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Запрос сброса пароля. Токен сброса отправляется письмом на указанный email
+    pub async fn request_password_reset(&mut self, email: String) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .request_password_reset(PasswordResetRequest { email })
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Установка нового пароля по токену сброса из письма
+    pub async fn confirm_password_reset(
+        &mut self,
+        token: String,
+        new_password: String,
+    ) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .confirm_password_reset(PasswordResetConfirmRequest {
+                token,
+                new_password,
+            })
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Подтверждение email по токену из письма
+    pub async fn verify_email(&mut self, verification_token: String) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .verify_email(VerifyEmailRequest {
+                token: verification_token,
+            })
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Повторная отправка письма для подтверждения email (Использует токен, полученный при авторизации)
+    pub async fn resend_verification(&mut self, token: &str) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .resend_verification(authorized(token, v2::ResendVerificationRequest {})?)
+            .await?
+            .into_inner();
+
+        Ok(())
     }
 
     /// Создание нового поста (Использует токен, полученный при авторизации)
     pub async fn create_post(
         &mut self,
         token: &str,
-        title: String,
-        content: String,
+        new_post: pod::NewPost,
     ) -> Result<pod::PostInfo, ClientError> {
         let response = self
             .client
-            .create_post(CreatePostRequest {
-                reg_user: Some(RegisteredUser {
-                    token: token.to_string(),
-                }),
-                new_post: Some(NewPost { title, content }),
-            })
+            .create_post(authorized(
+                token,
+                v2::CreatePostRequest {
+                    new_post: Some(NewPost {
+                        title: new_post.title,
+                        content: new_post.content,
+                        status: new_post.status,
+                        publish_at: new_post.publish_at,
+                        tags: new_post.tags,
+                        content_format: new_post.content_format,
+                    }),
+                },
+            )?)
             .await?
             .into_inner();
 
@@ -114,18 +375,25 @@ impl GrpcClient {
         &mut self,
         token: &str,
         post_id: i64,
-        title: Option<String>,
-        content: Option<String>,
+        update_post: pod::UpdatePost,
     ) -> Result<pod::PostInfo, ClientError> {
         let response = self
             .client
-            .update_post(UpdatePostRequest {
-                reg_user: Some(RegisteredUser {
-                    token: token.to_string(),
-                }),
-                update_post: Some(UpdatePost { title, content }),
-                post_id: Some(PostId { id: post_id }),
-            })
+            .update_post(authorized(
+                token,
+                v2::UpdatePostRequest {
+                    update_post: Some(UpdatePost {
+                        title: update_post.title,
+                        content: update_post.content,
+                        status: update_post.status,
+                        publish_at: update_post.publish_at,
+                        expected_version: update_post.expected_version,
+                        tags: update_post.tags.map(|tags| Tags { tags }),
+                        content_format: update_post.content_format,
+                    }),
+                    post_id: Some(PostId { id: post_id }),
+                },
+            )?)
             .await?
             .into_inner();
 
@@ -136,51 +404,532 @@ impl GrpcClient {
     pub async fn delete_post(&mut self, token: &str, post_id: i64) -> Result<(), ClientError> {
         let _response = self
             .client
-            .delete_post(DeletePostRequest {
-                reg_user: Some(RegisteredUser {
-                    token: token.to_string(),
-                }),
-                post_id: Some(PostId { id: post_id }),
-            })
+            .delete_post(authorized(
+                token,
+                v2::DeletePostRequest {
+                    post_id: Some(PostId { id: post_id }),
+                },
+            )?)
             .await?
             .into_inner();
 
         Ok(())
     }
 
-    /// Получение поста
-    pub async fn get_post(&mut self, post_id: i64) -> Result<pod::PostInfo, ClientError> {
+    /// Получение поста. С токеном автору доступны его черновики и запланированные посты
+    pub async fn get_post(
+        &mut self,
+        token: Option<&str>,
+        post_id: i64,
+    ) -> Result<pod::PostInfo, ClientError> {
         let response = self
             .client
-            .get_post(PostId { id: post_id })
+            .get_post(maybe_authorized(token, PostId { id: post_id })?)
             .await?
             .into_inner();
 
         Ok(response.into())
     }
 
-    /// Получение списка постов
+    /// Получение поста по slug, в том числе по старому slug переименованного поста
+    pub async fn get_post_by_slug(
+        &mut self,
+        token: Option<&str>,
+        slug: &str,
+    ) -> Result<pod::PostInfo, ClientError> {
+        let request = PostSlug {
+            slug: slug.to_string(),
+        };
+        let response = self
+            .client
+            .get_post_by_slug(maybe_authorized(token, request)?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Получение списка постов. С токеном в список попадают черновики и запланированные
+    /// посты автора. С курсором из `next_cursor` или `prev_cursor` предыдущего ответа
+    /// страница строится от него, а `offset` не учитывается
     pub async fn get_posts(
         &mut self,
+        token: Option<&str>,
         offset: i64,
         limit: i64,
+        tag: Option<&str>,
+        cursor: Option<&str>,
+        with_total: bool,
     ) -> Result<pod::PostResp, ClientError> {
+        let request = GetPostsReq {
+            offset,
+            limit,
+            tag: tag.map(str::to_string),
+            cursor: cursor.map(str::to_string),
+            with_total,
+        };
         let response = self
             .client
-            .get_posts(GetPostsReq { offset, limit })
+            .get_posts(maybe_authorized(token, request)?)
             .await?
             .into_inner();
 
         let posts_info: Vec<pod::PostInfo> = response
             .posts_info
             .into_iter()
-            .map(|item| pod::PostInfo::from(item))
+            .map(pod::PostInfo::from)
             .collect();
 
         Ok(pod::PostResp {
+            offset: response.offset,
+            limit: response.limit,
+            posts: posts_info,
+            next_cursor: response.next_cursor,
+            prev_cursor: response.prev_cursor,
+            total: response.total,
+        })
+    }
+
+    /// Полнотекстовый поиск постов, самые релевантные первыми
+    pub async fn search_posts(
+        &mut self,
+        token: Option<&str>,
+        query: &str,
+        lang: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<pod::SearchResp, ClientError> {
+        let request = SearchPostsRequest {
+            query: query.to_string(),
+            lang: lang.map(str::to_string),
             offset,
             limit,
-            posts: posts_info,
+        };
+        let response = self
+            .client
+            .search_posts(maybe_authorized(token, request)?)
+            .await?
+            .into_inner();
+
+        Ok(pod::SearchResp {
+            offset: response.offset,
+            limit: response.limit,
+            hits: response
+                .hits
+                .into_iter()
+                .map(|hit| pod::SearchHit {
+                    post: hit.post.map(pod::PostInfo::from).unwrap_or_default(),
+                    rank: hit.rank,
+                    snippet: hit.snippet,
+                })
+                .collect(),
+        })
+    }
+
+    /// Список тегов с числом опубликованных постов, популярные первыми
+    pub async fn list_tags(&mut self) -> Result<Vec<pod::TagInfo>, ClientError> {
+        let response = self
+            .client
+            .list_tags(ListTagsRequest {})
+            .await?
+            .into_inner();
+
+        Ok(response
+            .tags
+            .into_iter()
+            .map(|tag| pod::TagInfo {
+                name: tag.name,
+                post_count: tag.post_count,
+            })
+            .collect())
+    }
+
+    /// Список удаленных постов пользователя в корзине (Использует токен, полученный при авторизации)
+    pub async fn get_trash(
+        &mut self,
+        token: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<pod::PostResp, ClientError> {
+        let response = self
+            .client
+            .list_trash(authorized(token, v2::ListTrashRequest { offset, limit })?)
+            .await?
+            .into_inner();
+
+        Ok(pod::PostResp {
+            offset: response.offset,
+            limit: response.limit,
+            posts: response
+                .posts_info
+                .into_iter()
+                .map(pod::PostInfo::from)
+                .collect(),
+            ..Default::default()
         })
     }
+
+    /// Восстановление поста из корзины (Использует токен, полученный при авторизации)
+    pub async fn restore_post(
+        &mut self,
+        token: &str,
+        post_id: i64,
+    ) -> Result<pod::PostInfo, ClientError> {
+        let response = self
+            .client
+            .restore_post(authorized(token, v2::RestorePostRequest { post_id })?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Окончательное удаление поста из корзины (Использует токен, полученный при авторизации)
+    pub async fn purge_post(&mut self, token: &str, post_id: i64) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .purge_post(authorized(token, v2::PurgePostRequest { post_id })?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Комментарии к посту в порядке веток: за каждым комментарием идут ответы на него
+    pub async fn list_comments(
+        &mut self,
+        token: Option<&str>,
+        post_id: i64,
+    ) -> Result<Vec<pod::CommentInfo>, ClientError> {
+        let response = self
+            .client
+            .list_comments(maybe_authorized(token, PostId { id: post_id })?)
+            .await?
+            .into_inner();
+
+        Ok(response.comments.into_iter().map(pod::CommentInfo::from).collect())
+    }
+
+    /// Добавление комментария или ответа на комментарий (Использует токен, полученный при авторизации)
+    pub async fn add_comment(
+        &mut self,
+        token: &str,
+        post_id: i64,
+        new_comment: pod::NewComment,
+    ) -> Result<pod::CommentInfo, ClientError> {
+        let response = self
+            .client
+            .add_comment(authorized(
+                token,
+                v2::AddCommentRequest {
+                    post_id,
+                    content: new_comment.content,
+                    parent_id: new_comment.parent_id,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Изменение своего комментария, доступно ограниченное время после публикации
+    /// (Использует токен, полученный при авторизации)
+    pub async fn update_comment(
+        &mut self,
+        token: &str,
+        post_id: i64,
+        comment_id: i64,
+        content: &str,
+    ) -> Result<pod::CommentInfo, ClientError> {
+        let response = self
+            .client
+            .update_comment(authorized(
+                token,
+                v2::UpdateCommentRequest {
+                    post_id,
+                    comment_id,
+                    content: content.to_string(),
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Удаление комментария автором комментария, автором поста или модератором
+    /// (Использует токен, полученный при авторизации)
+    pub async fn delete_comment(
+        &mut self,
+        token: &str,
+        post_id: i64,
+        comment_id: i64,
+    ) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .delete_comment(authorized(
+                token,
+                v2::DeleteCommentRequest {
+                    post_id,
+                    comment_id,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Список версий поста (Использует токен автора или модератора)
+    pub async fn list_revisions(
+        &mut self,
+        token: &str,
+        post_id: i64,
+    ) -> Result<Vec<pod::RevisionInfo>, ClientError> {
+        let response = self
+            .client
+            .list_post_revisions(authorized(token, v2::ListPostRevisionsRequest { post_id })?)
+            .await?
+            .into_inner();
+
+        Ok(response.revisions.into_iter().map(pod::RevisionInfo::from).collect())
+    }
+
+    /// Получение версии поста (Использует токен автора или модератора)
+    pub async fn get_revision(
+        &mut self,
+        token: &str,
+        post_id: i64,
+        revision: i32,
+    ) -> Result<pod::RevisionInfo, ClientError> {
+        let response = self
+            .client
+            .get_post_revision(authorized(
+                token,
+                v2::GetPostRevisionRequest { post_id, revision },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Пословное сравнение двух версий поста (Использует токен автора или модератора)
+    pub async fn diff_revisions(
+        &mut self,
+        token: &str,
+        post_id: i64,
+        from: i32,
+        to: i32,
+    ) -> Result<pod::RevisionDiff, ClientError> {
+        let response = self
+            .client
+            .diff_post_revisions(authorized(
+                token,
+                v2::DiffPostRevisionsRequest { post_id, from, to },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Восстановление поста из версии. Восстановленный текст сохраняется новой версией
+    /// (Использует токен, полученный при авторизации)
+    pub async fn restore_revision(
+        &mut self,
+        token: &str,
+        post_id: i64,
+        revision: i32,
+        expected_version: Option<i64>,
+    ) -> Result<pod::PostInfo, ClientError> {
+        let response = self
+            .client
+            .restore_post_revision(authorized(
+                token,
+                v2::RestorePostRevisionRequest {
+                    post_id,
+                    revision,
+                    expected_version,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Смена роли пользователя (доступно только администратору)
+    pub async fn set_user_role(
+        &mut self,
+        token: &str,
+        user_id: i64,
+        role: String,
+    ) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .set_user_role(authorized(
+                token,
+                v2::SetUserRoleRequest {
+                    user_id,
+                    role,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Создание персонального токена доступа (Использует токен, полученный при авторизации)
+    pub async fn create_personal_token(
+        &mut self,
+        token: &str,
+        create_req: pod::CreatePersonalTokenReq,
+    ) -> Result<pod::PersonalTokenInfo, ClientError> {
+        let response = self
+            .client
+            .create_personal_token(authorized(
+                token,
+                v2::CreatePersonalTokenRequest {
+                    name: create_req.name,
+                    scopes: create_req.scopes,
+                    expires_in_days: create_req.expires_in_days,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(response.into())
+    }
+
+    /// Список действующих персональных токенов доступа (Использует токен, полученный при авторизации)
+    pub async fn list_personal_tokens(
+        &mut self,
+        token: &str,
+    ) -> Result<Vec<pod::PersonalTokenInfo>, ClientError> {
+        let response = self
+            .client
+            .list_personal_tokens(authorized(token, v2::ListPersonalTokensRequest {})?)
+            .await?
+            .into_inner();
+
+        Ok(response.tokens.into_iter().map(pod::PersonalTokenInfo::from).collect())
+    }
+
+    /// Отзыв персонального токена доступа (Использует токен, полученный при авторизации)
+    pub async fn revoke_personal_token(&mut self, token: &str, token_id: i64) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .revoke_personal_token(authorized(
+                token,
+                v2::RevokePersonalTokenRequest { id: token_id },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Список активных сессий пользователя (Использует токен, полученный при авторизации)
+    pub async fn list_sessions(&mut self, token: &str) -> Result<Vec<pod::SessionInfo>, ClientError> {
+        let response = self
+            .client
+            .list_sessions(authorized(token, v2::ListSessionsRequest {})?)
+            .await?
+            .into_inner();
+
+        Ok(response.sessions.into_iter().map(pod::SessionInfo::from).collect())
+    }
+
+    /// Завершение сессии: её токены перестают приниматься сразу
+    /// (Использует токен, полученный при авторизации)
+    pub async fn revoke_session(&mut self, token: &str, session_id: i64) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .revoke_session(authorized(token, v2::RevokeSessionRequest { id: session_id })?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Завершение всех сессий пользователя, включая текущую
+    /// (Использует токен, полученный при авторизации)
+    pub async fn revoke_all_sessions(&mut self, token: &str) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .revoke_all_sessions(authorized(token, v2::RevokeAllSessionsRequest {})?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Смена пароля. Требует текущий пароль, refresh-токены всех сессий отзываются
+    /// (Использует токен, полученный при авторизации)
+    pub async fn change_password(
+        &mut self,
+        token: &str,
+        change_req: pod::ChangePasswordReq,
+    ) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .change_password(authorized(
+                token,
+                v2::ChangePasswordRequest {
+                    current_password: change_req.current_password,
+                    new_password: change_req.new_password,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Смена email. На новый адрес отправляется письмо для подтверждения
+    /// (Использует токен, полученный при авторизации)
+    pub async fn change_email(
+        &mut self,
+        token: &str,
+        change_req: pod::ChangeEmailReq,
+    ) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .change_email(authorized(
+                token,
+                v2::ChangeEmailRequest {
+                    email: change_req.email,
+                    password: change_req.password,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
+
+    /// Удаление учетной записи вместе с постами или с передачей постов другому пользователю
+    /// (Использует токен, полученный при авторизации)
+    pub async fn delete_account(
+        &mut self,
+        token: &str,
+        delete_req: pod::DeleteAccountReq,
+    ) -> Result<(), ClientError> {
+        let _response = self
+            .client
+            .delete_account(authorized(
+                token,
+                v2::DeleteAccountRequest {
+                    password: delete_req.password,
+                    reassign_posts_to: delete_req.reassign_posts_to,
+                },
+            )?)
+            .await?
+            .into_inner();
+
+        Ok(())
+    }
 }
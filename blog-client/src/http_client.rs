@@ -5,22 +5,21 @@ use crate::pod::*;
 
 /// Клиент для взаимодействия с сервером по протоколу http
 /// Пример:
-/// ```rust
-/// use blog_client::grpc_client::HttpClient;
-/// use blog_client::error::ClientError;
+/// ```rust,no_run
+/// use blog_client::http_client::HttpClient;
 /// use blog_client::pod;
-/// 
+///
 /// #[tokio::main]
 /// async fn main() {
 ///     let http_client = HttpClient::new("http://127.0.0.1:3000");
-///     let reg_req = pod::RegisterUserReq{
-///         username,
-///         email,
-///         password: pass,
+///     let reg_req = pod::RegisterUserReq {
+///         username: "NewName".to_string(),
+///         email: "NewMail".to_string(),
+///         password: "NewPass".to_string(),
 ///     };
 ///     let reg_resp = http_client.register(reg_req).await.unwrap();
 /// }
-/// ``` 
+/// ```
 pub struct HttpClient {
     client: Client,
     addr: String,
@@ -65,6 +64,182 @@ impl HttpClient {
         Ok(reg_user)
     }
 
+    /// Обновление пары токенов по refresh-токену. Использованный refresh-токен становится недействительным
+    pub async fn refresh(&self, refresh_token: &str) -> Result<RegisteredUser, ClientError> {
+        let url = format!("{}/auth/refresh", self.addr);
+        let req = RefreshTokenReq {
+            refresh_token: refresh_token.to_string(),
+        };
+        let resp = self
+            .client
+            .post(url)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let reg_user = resp.json::<RegisteredUser>().await?;
+        Ok(reg_user)
+    }
+
+    /// Второй шаг входа: обмен токена, полученного при входе, и одноразового кода на пару токенов
+    pub async fn verify_two_factor(
+        &self,
+        challenge_token: &str,
+        code: &str,
+    ) -> Result<RegisteredUser, ClientError> {
+        let url = format!("{}/auth/2fa/verify", self.addr);
+        let req = VerifyTwoFactorReq {
+            challenge_token: challenge_token.to_string(),
+            code: code.to_string(),
+        };
+        let resp = self
+            .client
+            .post(url)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let reg_user = resp.json::<RegisteredUser>().await?;
+        Ok(reg_user)
+    }
+
+    /// Начало подключения двухфакторной аутентификации (Использует токен, полученный при авторизации)
+    pub async fn enroll_totp(&self, token: &str) -> Result<TotpEnrollment, ClientError> {
+        let url = format!("{}/auth/2fa/enroll", self.addr);
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let enrollment = resp.json::<TotpEnrollment>().await?;
+        Ok(enrollment)
+    }
+
+    /// Подтверждение подключения двухфакторной аутентификации кодом из приложения
+    /// (Использует токен, полученный при авторизации)
+    pub async fn confirm_totp(&self, token: &str, code: &str) -> Result<RecoveryCodes, ClientError> {
+        let url = format!("{}/auth/2fa/confirm", self.addr);
+        let req = TotpCodeReq {
+            code: code.to_string(),
+        };
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(token)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let recovery_codes = resp.json::<RecoveryCodes>().await?;
+        Ok(recovery_codes)
+    }
+
+    /// Отключение двухфакторной аутентификации (Использует токен, полученный при авторизации)
+    pub async fn disable_totp(&self, token: &str, code: &str) -> Result<(), ClientError> {
+        let url = format!("{}/auth/2fa/disable", self.addr);
+        let req = TotpCodeReq {
+            code: code.to_string(),
+        };
+        self.client
+            .post(url)
+            .bearer_auth(token)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Выход пользователя. Токен доступа и переданный refresh-токен отзываются на сервере
+    pub async fn logout(&self, token: &str, refresh_token: Option<&str>) -> Result<(), ClientError> {
+        let url = format!("{}/auth/logout", self.addr);
+        let req = LogoutReq {
+            refresh_token: refresh_token.map(str::to_string),
+        };
+        self.client
+            .post(url)
+            .bearer_auth(token)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Запрос сброса пароля. Токен сброса отправляется письмом на указанный email
+    pub async fn request_password_reset(&self, email: &str) -> Result<(), ClientError> {
+        let url = format!("{}/auth/password-reset/request", self.addr);
+        let req = PasswordResetReq {
+            email: email.to_string(),
+        };
+        self.client
+            .post(url)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Установка нового пароля по токену сброса из письма
+    pub async fn confirm_password_reset(
+        &self,
+        token: &str,
+        new_password: &str,
+    ) -> Result<(), ClientError> {
+        let url = format!("{}/auth/password-reset/confirm", self.addr);
+        let req = PasswordResetConfirmReq {
+            token: token.to_string(),
+            new_password: new_password.to_string(),
+        };
+        self.client
+            .post(url)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Подтверждение email по токену из письма
+    pub async fn verify_email(&self, verification_token: &str) -> Result<(), ClientError> {
+        let url = format!("{}/auth/verify-email", self.addr);
+        let req = VerifyEmailReq {
+            token: verification_token.to_string(),
+        };
+        self.client
+            .get(url)
+            .query(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Повторная отправка письма для подтверждения email (Использует токен, полученный при авторизации)
+    pub async fn resend_verification(&self, token: &str) -> Result<(), ClientError> {
+        let url = format!("{}/auth/verify-email/resend", self.addr);
+        self.client
+            .post(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
     /// Создание нового поста (Использует токен, полученный при авторизации)
     pub async fn create_post(
         &self,
@@ -93,14 +268,11 @@ impl HttpClient {
         update_post: UpdatePost,
     ) -> Result<PostInfo, ClientError> {
         let url = format!("{}/posts/{}", self.addr, post_id.id);
-        let resp = self
-            .client
-            .put(url)
-            .bearer_auth(token)
-            .json(&update_post)
-            .send()
-            .await?
-            .error_for_status()?;
+        let mut req = self.client.put(url).bearer_auth(token);
+        if let Some(version) = update_post.expected_version {
+            req = req.header(reqwest::header::IF_MATCH, format!("\"{version}\""));
+        }
+        let resp = req.json(&update_post).send().await?.error_for_status()?;
 
         let post_info = resp.json::<PostInfo>().await?;
         Ok(post_info)
@@ -119,31 +291,488 @@ impl HttpClient {
         Ok(())
     }
 
-    /// Получение поста
-    pub async fn get_post(&self, post_id: PostId) -> Result<PostInfo, ClientError> {
+    /// Получение поста. С токеном автору доступны его черновики и запланированные посты
+    pub async fn get_post(
+        &self,
+        token: Option<&str>,
+        post_id: PostId,
+    ) -> Result<PostInfo, ClientError> {
         let url = format!("{}/posts/{}", self.addr, post_id.id);
-        let resp = self.client.get(url).send().await?.error_for_status()?;
+        let mut req = self.client.get(url);
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let resp = req.send().await?.error_for_status()?;
+
+        let post_info = resp.json::<PostInfo>().await?;
+        Ok(post_info)
+    }
+
+    /// Получение поста по slug, в том числе по старому slug переименованного поста
+    pub async fn get_post_by_slug(
+        &self,
+        token: Option<&str>,
+        slug: &str,
+    ) -> Result<PostInfo, ClientError> {
+        let url = format!("{}/posts/by-slug/{}", self.addr, slug);
+        let mut req = self.client.get(url);
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let resp = req.send().await?.error_for_status()?;
 
         let post_info = resp.json::<PostInfo>().await?;
         Ok(post_info)
     }
 
-    /// Получение списка постов
-    pub async fn get_posts(&self, offset: i64, limit: i64) -> Result<PostResp, ClientError> {
+    /// Получение списка постов. С токеном в список попадают черновики и запланированные
+    /// посты автора. С курсором из `next_cursor` или `prev_cursor` предыдущего ответа
+    /// страница строится от него, а `offset` не учитывается
+    pub async fn get_posts(
+        &self,
+        token: Option<&str>,
+        offset: i64,
+        limit: i64,
+        tag: Option<&str>,
+        cursor: Option<&str>,
+        with_total: bool,
+    ) -> Result<PostResp, ClientError> {
         let query = GetPostsReq {
             offset: Some(offset),
             limit: Some(limit),
+            tag: tag.map(str::to_string),
+            cursor: cursor.map(str::to_string),
+            with_total,
         };
         let url = format!("{}/posts", self.addr);
+        let mut req = self.client.get(url).query(&query);
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let resp = req.send().await?.error_for_status()?;
+
+        let post_info = resp.json::<PostResp>().await?;
+        Ok(post_info)
+    }
+
+    /// Полнотекстовый поиск постов, самые релевантные первыми
+    pub async fn search_posts(
+        &self,
+        token: Option<&str>,
+        q: &str,
+        lang: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<SearchResp, ClientError> {
+        let query = SearchPostsReq {
+            q: q.to_string(),
+            lang: lang.map(str::to_string),
+            offset: Some(offset),
+            limit: Some(limit),
+        };
+        let url = format!("{}/posts/search", self.addr);
+        let mut req = self.client.get(url).query(&query);
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let resp = req.send().await?.error_for_status()?;
+
+        let found = resp.json::<SearchResp>().await?;
+        Ok(found)
+    }
+
+    /// Список тегов с числом опубликованных постов, популярные первыми
+    pub async fn list_tags(&self) -> Result<Vec<TagInfo>, ClientError> {
+        let url = format!("{}/tags", self.addr);
+        let resp = self.client.get(url).send().await?.error_for_status()?;
+
+        let tags = resp.json::<Vec<TagInfo>>().await?;
+        Ok(tags)
+    }
+
+    /// Список удаленных постов пользователя в корзине (Использует токен, полученный при авторизации)
+    pub async fn get_trash(
+        &self,
+        token: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<PostResp, ClientError> {
+        let query = GetPostsReq {
+            offset: Some(offset),
+            limit: Some(limit),
+            ..Default::default()
+        };
+        let url = format!("{}/posts/trash", self.addr);
         let resp = self
             .client
             .get(url)
+            .bearer_auth(token)
             .query(&query)
             .send()
             .await?
             .error_for_status()?;
 
-        let post_info = resp.json::<PostResp>().await?;
+        let posts = resp.json::<PostResp>().await?;
+        Ok(posts)
+    }
+
+    /// Восстановление поста из корзины (Использует токен, полученный при авторизации)
+    pub async fn restore_post(&self, token: &str, post_id: PostId) -> Result<PostInfo, ClientError> {
+        let url = format!("{}/posts/trash/{}/restore", self.addr, post_id.id);
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let post_info = resp.json::<PostInfo>().await?;
+        Ok(post_info)
+    }
+
+    /// Окончательное удаление поста из корзины (Использует токен, полученный при авторизации)
+    pub async fn purge_post(&self, token: &str, post_id: PostId) -> Result<(), ClientError> {
+        let url = format!("{}/posts/trash/{}", self.addr, post_id.id);
+        self.client
+            .delete(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Комментарии к посту в порядке веток: за каждым комментарием идут ответы на него
+    pub async fn list_comments(
+        &self,
+        token: Option<&str>,
+        post_id: PostId,
+    ) -> Result<Vec<CommentInfo>, ClientError> {
+        let url = format!("{}/posts/{}/comments", self.addr, post_id.id);
+        let mut req = self.client.get(url);
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let resp = req.send().await?.error_for_status()?;
+
+        let comments = resp.json::<Vec<CommentInfo>>().await?;
+        Ok(comments)
+    }
+
+    /// Добавление комментария или ответа на комментарий (Использует токен, полученный при авторизации)
+    pub async fn add_comment(
+        &self,
+        token: &str,
+        post_id: PostId,
+        new_comment: NewComment,
+    ) -> Result<CommentInfo, ClientError> {
+        let url = format!("{}/posts/{}/comments", self.addr, post_id.id);
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(token)
+            .json(&new_comment)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let comment = resp.json::<CommentInfo>().await?;
+        Ok(comment)
+    }
+
+    /// Изменение своего комментария, доступно ограниченное время после публикации
+    /// (Использует токен, полученный при авторизации)
+    pub async fn update_comment(
+        &self,
+        token: &str,
+        post_id: PostId,
+        comment_id: i64,
+        content: &str,
+    ) -> Result<CommentInfo, ClientError> {
+        let url = format!("{}/posts/{}/comments/{}", self.addr, post_id.id, comment_id);
+        let resp = self
+            .client
+            .put(url)
+            .bearer_auth(token)
+            .json(&UpdateComment {
+                content: content.to_string(),
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let comment = resp.json::<CommentInfo>().await?;
+        Ok(comment)
+    }
+
+    /// Удаление комментария автором комментария, автором поста или модератором
+    /// (Использует токен, полученный при авторизации)
+    pub async fn delete_comment(
+        &self,
+        token: &str,
+        post_id: PostId,
+        comment_id: i64,
+    ) -> Result<(), ClientError> {
+        let url = format!("{}/posts/{}/comments/{}", self.addr, post_id.id, comment_id);
+        self.client
+            .delete(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Список версий поста (Использует токен автора или модератора)
+    pub async fn list_revisions(
+        &self,
+        token: &str,
+        post_id: PostId,
+    ) -> Result<Vec<RevisionInfo>, ClientError> {
+        let url = format!("{}/posts/{}/revisions", self.addr, post_id.id);
+        let resp = self
+            .client
+            .get(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let revisions = resp.json::<Vec<RevisionInfo>>().await?;
+        Ok(revisions)
+    }
+
+    /// Получение версии поста (Использует токен автора или модератора)
+    pub async fn get_revision(
+        &self,
+        token: &str,
+        post_id: PostId,
+        revision: i32,
+    ) -> Result<RevisionInfo, ClientError> {
+        let url = format!("{}/posts/{}/revisions/{}", self.addr, post_id.id, revision);
+        let resp = self
+            .client
+            .get(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let revision = resp.json::<RevisionInfo>().await?;
+        Ok(revision)
+    }
+
+    /// Пословное сравнение двух версий поста (Использует токен автора или модератора)
+    pub async fn diff_revisions(
+        &self,
+        token: &str,
+        post_id: PostId,
+        from: i32,
+        to: i32,
+    ) -> Result<RevisionDiff, ClientError> {
+        let url = format!("{}/posts/{}/revisions/diff", self.addr, post_id.id);
+        let resp = self
+            .client
+            .get(url)
+            .bearer_auth(token)
+            .query(&[("from", from), ("to", to)])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let diff = resp.json::<RevisionDiff>().await?;
+        Ok(diff)
+    }
+
+    /// Восстановление поста из версии. Восстановленный текст сохраняется новой версией
+    /// (Использует токен, полученный при авторизации)
+    pub async fn restore_revision(
+        &self,
+        token: &str,
+        post_id: PostId,
+        revision: i32,
+        expected_version: Option<i64>,
+    ) -> Result<PostInfo, ClientError> {
+        let url = format!("{}/posts/{}/restore/{}", self.addr, post_id.id, revision);
+        let mut req = self.client.post(url).bearer_auth(token);
+        if let Some(version) = expected_version {
+            req = req.header(reqwest::header::IF_MATCH, format!("\"{version}\""));
+        }
+        let resp = req.send().await?.error_for_status()?;
+
+        let post_info = resp.json::<PostInfo>().await?;
         Ok(post_info)
     }
+
+    /// Смена роли пользователя (доступно только администратору)
+    pub async fn set_user_role(
+        &self,
+        token: &str,
+        user_id: i64,
+        role: &str,
+    ) -> Result<(), ClientError> {
+        let url = format!("{}/users/{}/role", self.addr, user_id);
+        let req = SetUserRoleReq {
+            role: role.to_string(),
+        };
+        self.client
+            .put(url)
+            .bearer_auth(token)
+            .json(&req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Создание персонального токена доступа (Использует токен, полученный при авторизации)
+    pub async fn create_personal_token(
+        &self,
+        token: &str,
+        create_req: CreatePersonalTokenReq,
+    ) -> Result<PersonalTokenInfo, ClientError> {
+        let url = format!("{}/users/me/tokens", self.addr);
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(token)
+            .json(&create_req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let token_info = resp.json::<PersonalTokenInfo>().await?;
+        Ok(token_info)
+    }
+
+    /// Список действующих персональных токенов доступа (Использует токен, полученный при авторизации)
+    pub async fn list_personal_tokens(
+        &self,
+        token: &str,
+    ) -> Result<Vec<PersonalTokenInfo>, ClientError> {
+        let url = format!("{}/users/me/tokens", self.addr);
+        let resp = self
+            .client
+            .get(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let tokens = resp.json::<Vec<PersonalTokenInfo>>().await?;
+        Ok(tokens)
+    }
+
+    /// Отзыв персонального токена доступа (Использует токен, полученный при авторизации)
+    pub async fn revoke_personal_token(&self, token: &str, token_id: i64) -> Result<(), ClientError> {
+        let url = format!("{}/users/me/tokens/{}", self.addr, token_id);
+        self.client
+            .delete(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Список активных сессий пользователя (Использует токен, полученный при авторизации)
+    pub async fn list_sessions(&self, token: &str) -> Result<Vec<SessionInfo>, ClientError> {
+        let url = format!("{}/users/me/sessions", self.addr);
+        let resp = self
+            .client
+            .get(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let sessions = resp.json::<Vec<SessionInfo>>().await?;
+        Ok(sessions)
+    }
+
+    /// Завершение сессии: её токены перестают приниматься сразу
+    /// (Использует токен, полученный при авторизации)
+    pub async fn revoke_session(&self, token: &str, session_id: i64) -> Result<(), ClientError> {
+        let url = format!("{}/users/me/sessions/{}", self.addr, session_id);
+        self.client
+            .delete(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Завершение всех сессий пользователя, включая текущую
+    /// (Использует токен, полученный при авторизации)
+    pub async fn revoke_all_sessions(&self, token: &str) -> Result<(), ClientError> {
+        let url = format!("{}/users/me/sessions", self.addr);
+        self.client
+            .delete(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Смена пароля. Требует текущий пароль, refresh-токены всех сессий отзываются
+    /// (Использует токен, полученный при авторизации)
+    pub async fn change_password(
+        &self,
+        token: &str,
+        change_req: ChangePasswordReq,
+    ) -> Result<(), ClientError> {
+        let url = format!("{}/users/me/password", self.addr);
+        self.client
+            .put(url)
+            .bearer_auth(token)
+            .json(&change_req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Смена email. На новый адрес отправляется письмо для подтверждения
+    /// (Использует токен, полученный при авторизации)
+    pub async fn change_email(&self, token: &str, change_req: ChangeEmailReq) -> Result<(), ClientError> {
+        let url = format!("{}/users/me/email", self.addr);
+        self.client
+            .put(url)
+            .bearer_auth(token)
+            .json(&change_req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Удаление учетной записи вместе с постами или с передачей постов другому пользователю
+    /// (Использует токен, полученный при авторизации)
+    pub async fn delete_account(
+        &self,
+        token: &str,
+        delete_req: DeleteAccountReq,
+    ) -> Result<(), ClientError> {
+        let url = format!("{}/users/me", self.addr);
+        self.client
+            .delete(url)
+            .bearer_auth(token)
+            .json(&delete_req)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
 }
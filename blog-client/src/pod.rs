@@ -20,11 +20,171 @@ pub struct LoginUserReq {
     pub password: String,
 }
 
+/// pod запроса сброса пароля
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct PasswordResetReq {
+    /// email учетной записи, на который будет отправлен токен сброса
+    pub email: String,
+}
+
+/// pod подтверждения сброса пароля
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct PasswordResetConfirmReq {
+    /// Токен сброса из письма
+    pub token: String,
+    /// Новый пароль
+    pub new_password: String,
+}
+
+/// pod подтверждения email
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct VerifyEmailReq {
+    /// Токен подтверждения из письма
+    pub token: String,
+}
+
+/// pod запроса смены роли пользователя
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SetUserRoleReq {
+    /// Новая роль: user, moderator или admin
+    pub role: String,
+}
+
+/// pod запроса создания персонального токена доступа
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct CreatePersonalTokenReq {
+    /// Название токена, например имя CI-задачи
+    pub name: String,
+    /// Права токена: posts:read, posts:write, comments:write
+    pub scopes: Vec<String>,
+    /// Срок действия в днях. Без него токен бессрочный
+    pub expires_in_days: Option<i64>,
+}
+
+/// pod запроса смены пароля
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ChangePasswordReq {
+    /// Текущий пароль
+    pub current_password: String,
+    /// Новый пароль
+    pub new_password: String,
+}
+
+/// pod запроса смены email
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ChangeEmailReq {
+    /// Новый email, его нужно будет подтвердить заново
+    pub email: String,
+    /// Текущий пароль
+    pub password: String,
+}
+
+/// pod запроса удаления учетной записи
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct DeleteAccountReq {
+    /// Текущий пароль
+    pub password: String,
+    /// id пользователя, которому передаются посты (только для модераторов). Без него посты
+    /// удаляются
+    pub reassign_posts_to: Option<i64>,
+}
+
+/// Информация о персональном токене доступа
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct PersonalTokenInfo {
+    /// id токена
+    pub id: i64,
+    /// Название токена
+    pub name: String,
+    /// Права токена
+    pub scopes: Vec<String>,
+    /// Дата создания UTC rfc 3339
+    pub created_at: String,
+    /// Дата истечения UTC rfc 3339
+    pub expires_at: Option<String>,
+    /// Дата последнего использования UTC rfc 3339
+    pub last_used_at: Option<String>,
+    /// Сам токен. Возвращается только при создании
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Информация об активной сессии (входе с устройства)
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SessionInfo {
+    /// id сессии
+    pub id: i64,
+    /// User-Agent клиента, с которого выполнен вход
+    pub user_agent: Option<String>,
+    /// IP-адрес клиента, с которого выполнен вход
+    pub ip_address: Option<String>,
+    /// Дата входа UTC rfc 3339
+    pub created_at: String,
+    /// Дата последней активности UTC rfc 3339
+    pub last_seen_at: String,
+    /// Сессия, из которой выполнен запрос
+    pub current: bool,
+}
+
+/// pod запроса обновления токена
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RefreshTokenReq {
+    /// Refresh-токен, полученный при регистрации, входе или предыдущем обновлении
+    pub refresh_token: String,
+}
+
+/// pod запроса выхода
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct LogoutReq {
+    /// Refresh-токен, который нужно отозвать вместе с токеном доступа
+    pub refresh_token: Option<String>,
+}
+
 /// Ответ на регистрацию или вход пользователя
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct RegisteredUser {
-    /// Токен для авторизации
+    /// Короткоживущий токен для авторизации
+    #[serde(default)]
     pub token: String,
+    /// Одноразовый токен для получения новой пары токенов
+    #[serde(default)]
+    pub refresh_token: String,
+    /// Токен второго шага входа. Присутствует вместо пары токенов, если у пользователя
+    /// включена двухфакторная аутентификация
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge_token: Option<String>,
+}
+
+/// pod запроса с кодом из приложения-аутентификатора или кодом восстановления
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct TotpCodeReq {
+    /// Одноразовый код
+    pub code: String,
+}
+
+/// pod второго шага входа
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct VerifyTwoFactorReq {
+    /// Токен, полученный при входе
+    pub challenge_token: String,
+    /// Код из приложения-аутентификатора или код восстановления
+    pub code: String,
+}
+
+/// Данные для подключения приложения-аутентификатора
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct TotpEnrollment {
+    /// Секрет в base32 для ручного ввода
+    pub secret: String,
+    /// Ссылка otpauth:// для QR-кода
+    pub otpauth_uri: String,
+}
+
+/// Коды восстановления, выдаются один раз при включении двухфакторной аутентификации
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RecoveryCodes {
+    /// Список одноразовых кодов
+    pub recovery_codes: Vec<String>,
 }
 
 /// pod Создания нового поста
@@ -34,6 +194,15 @@ pub struct NewPost {
     pub title: String,
     /// Содержимое
     pub content: String,
+    /// Статус: draft, published или scheduled. Без него пост публикуется сразу
+    pub status: Option<String>,
+    /// Время публикации запланированного поста UTC rfc 3339
+    pub publish_at: Option<String>,
+    /// Теги поста
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Формат содержимого: plain или markdown. По умолчанию plain
+    pub content_format: Option<String>,
 }
 
 /// pod обновления поста
@@ -43,6 +212,18 @@ pub struct UpdatePost {
     pub title: Option<String>,
     /// Новый контент
     pub content: Option<String>,
+    /// Новый статус: draft, published или scheduled
+    pub status: Option<String>,
+    /// Время публикации запланированного поста UTC rfc 3339
+    pub publish_at: Option<String>,
+    /// Новый набор тегов, заменяет прежний. Пустой список удаляет все теги
+    pub tags: Option<Vec<String>>,
+    /// Новый формат содержимого: plain или markdown
+    pub content_format: Option<String>,
+    /// Версия поста, на основе которой сделана правка. Если пост успел измениться,
+    /// сервер отклонит обновление. По http передается в заголовке `If-Match`
+    #[serde(skip)]
+    pub expected_version: Option<i64>,
 }
 
 /// pod Id поста
@@ -59,6 +240,14 @@ pub struct GetPostsReq {
     pub offset: Option<i64>,
     /// Количество постов в странице
     pub limit: Option<i64>,
+    /// Только посты с этим тегом
+    pub tag: Option<String>,
+    /// Курсор страницы (`next_cursor` или `prev_cursor` из предыдущего ответа).
+    /// Если задан, номер страницы не учитывается
+    pub cursor: Option<String>,
+    /// Подсчитать общее число постов в ленте
+    #[serde(default)]
+    pub with_total: bool,
 }
 
 /// pod Информация о посте
@@ -68,14 +257,163 @@ pub struct PostInfo {
     pub id: i64,
     /// Заголовок поста
     pub title: String,
+    /// Человекочитаемый идентификатор поста для ссылок
+    #[serde(default)]
+    pub slug: String,
     /// Содержимое поста
     pub content: String,
+    /// Формат содержимого: plain или markdown
+    #[serde(default)]
+    pub content_format: String,
+    /// Содержимое поста в виде очищенного HTML, безопасного для вставки в страницу
+    #[serde(default)]
+    pub content_html: String,
     /// id автора
     pub author_id: i64,
     /// Дата создание поста UTC rfc 3339
     pub created_at: String,
     /// Дата обновления поста UTC rfc 3339
     pub updated_at: String,
+    /// Статус: draft, published или scheduled
+    #[serde(default)]
+    pub status: String,
+    /// Дата публикации (для запланированного поста - планируемая) UTC rfc 3339
+    #[serde(default)]
+    pub published_at: Option<String>,
+    /// Версия поста, увеличивается при каждом сохранении
+    #[serde(default)]
+    pub version: i64,
+    /// Дата удаления поста в корзину UTC rfc 3339
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    /// Теги поста
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Тег и число опубликованных постов с ним
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct TagInfo {
+    /// Название тега
+    pub name: String,
+    /// Количество опубликованных постов
+    pub post_count: i64,
+}
+
+/// pod Запрос полнотекстового поиска постов
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SearchPostsReq {
+    /// Поисковый запрос
+    pub q: String,
+    /// Язык поиска (english, russian, simple ...), по умолчанию язык сервера
+    pub lang: Option<String>,
+    /// Номер страницы
+    pub offset: Option<i64>,
+    /// Количество результатов в странице
+    pub limit: Option<i64>,
+}
+
+/// Найденный пост
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SearchHit {
+    /// Пост
+    pub post: PostInfo,
+    /// Релевантность
+    pub rank: f32,
+    /// Фрагменты текста с совпадениями, выделенными тегом `<mark>`
+    pub snippet: String,
+}
+
+/// pod ответ на запрос поиска постов
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SearchResp {
+    /// Номер страницы
+    pub offset: i64,
+    /// Количество результатов в странице
+    pub limit: i64,
+    /// Найденные посты, самые релевантные первыми
+    pub hits: Vec<SearchHit>,
+}
+
+/// pod нового комментария или ответа на комментарий
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct NewComment {
+    /// Текст комментария
+    pub content: String,
+    /// id комментария, на который дается ответ. Без него комментарий верхнего уровня
+    pub parent_id: Option<i64>,
+}
+
+/// pod изменения комментария
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct UpdateComment {
+    /// Новый текст комментария
+    pub content: String,
+}
+
+/// Комментарий к посту
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct CommentInfo {
+    /// id комментария
+    pub id: i64,
+    /// id поста
+    pub post_id: i64,
+    /// id комментария, на который это ответ
+    pub parent_id: Option<i64>,
+    /// id автора. Пусто, если аккаунт автора удален
+    pub author_id: Option<i64>,
+    /// Текст комментария. Пустой у удаленного комментария, оставленного ради ответов
+    pub content: String,
+    /// Дата создания UTC rfc 3339
+    pub created_at: String,
+    /// Дата изменения UTC rfc 3339
+    pub updated_at: String,
+    /// Уровень вложенности в ветке, 0 у комментариев верхнего уровня
+    pub depth: i64,
+    /// Комментарий удален
+    pub deleted: bool,
+}
+
+/// Сохраненная версия поста
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RevisionInfo {
+    /// id поста
+    pub post_id: i64,
+    /// Номер версии, начиная с 1
+    pub revision: i32,
+    /// Заголовок
+    pub title: String,
+    /// Содержимое. Не возвращается в списке версий
+    #[serde(default)]
+    pub content: Option<String>,
+    /// id пользователя, сохранившего версию
+    pub editor_id: Option<i64>,
+    /// Дата сохранения UTC rfc 3339
+    pub created_at: String,
+}
+
+/// Фрагмент сравнения версий
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct DiffChunk {
+    /// Операция: equal, insert или delete
+    pub op: String,
+    /// Текст фрагмента
+    pub text: String,
+}
+
+/// Пословное сравнение двух версий поста
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RevisionDiff {
+    /// id поста
+    pub post_id: i64,
+    /// Исходная версия
+    pub from: i32,
+    /// Конечная версия
+    pub to: i32,
+    /// Изменения заголовка
+    pub title: Vec<DiffChunk>,
+    /// Изменения содержимого
+    pub content: Vec<DiffChunk>,
 }
 
 /// pod ответ на запрос списка постов
@@ -87,4 +425,13 @@ pub struct PostResp {
     pub limit: i64,
     /// Список постов
     pub posts: Vec<PostInfo>,
+    /// Курсор следующей страницы с более старыми постами. Нет на последней странице
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+    /// Курсор предыдущей страницы с более новыми постами. Нет на первой странице
+    #[serde(default)]
+    pub prev_cursor: Option<String>,
+    /// Общее число постов в ленте, если запрошено
+    #[serde(default)]
+    pub total: Option<i64>,
 }
@@ -0,0 +1,2 @@
+-- Revocations have to outlive a deleted account until the revoked tokens expire.
+ALTER TABLE revoked_tokens DROP CONSTRAINT IF EXISTS revoked_tokens_user_id_fkey;
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS sessions(
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+    family_id VARCHAR UNIQUE NOT NULL,
+    user_agent VARCHAR,
+    ip_address VARCHAR,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS sessions_user_id_idx ON sessions (user_id);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS post_revisions(
+    id BIGSERIAL PRIMARY KEY,
+    post_id BIGINT REFERENCES posts (id) ON DELETE CASCADE NOT NULL,
+    revision INTEGER NOT NULL,
+    title VARCHAR NOT NULL,
+    content TEXT NOT NULL,
+    editor_id BIGINT REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    UNIQUE (post_id, revision)
+);
+
+INSERT INTO post_revisions (post_id, revision, title, content, editor_id, created_at)
+SELECT id, 1, title, content, author_id, updated_at FROM posts
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,3 @@
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS posts_deleted_idx ON posts (author_id, deleted_at) WHERE deleted_at IS NOT NULL;
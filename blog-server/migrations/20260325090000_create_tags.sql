@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS tags(
+    id BIGSERIAL PRIMARY KEY,
+    name VARCHAR UNIQUE NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS post_tags(
+    post_id BIGINT REFERENCES posts (id) ON DELETE CASCADE NOT NULL,
+    tag_id BIGINT REFERENCES tags (id) ON DELETE CASCADE NOT NULL,
+    PRIMARY KEY (post_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS post_tags_tag_idx ON post_tags (tag_id);
@@ -0,0 +1,12 @@
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS slug VARCHAR;
+UPDATE posts SET slug = 'post-' || id WHERE slug IS NULL;
+ALTER TABLE posts ALTER COLUMN slug SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS posts_slug_idx ON posts (slug);
+
+-- Previous slugs of renamed posts, kept so old links still lead to the post.
+CREATE TABLE IF NOT EXISTS post_slugs(
+    slug VARCHAR PRIMARY KEY,
+    post_id BIGINT REFERENCES posts (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS post_slugs_post_idx ON post_slugs (post_id);
@@ -0,0 +1,3 @@
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS content_format VARCHAR NOT NULL DEFAULT 'plain';
+-- Rendered on save, posts saved before this migration are rendered on read.
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS content_html TEXT;
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS posts_feed_idx ON posts (updated_at DESC, id DESC) WHERE deleted_at IS NULL;
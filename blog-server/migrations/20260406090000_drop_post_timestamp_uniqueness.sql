@@ -0,0 +1,2 @@
+ALTER TABLE posts DROP CONSTRAINT IF EXISTS posts_created_at_key;
+ALTER TABLE posts DROP CONSTRAINT IF EXISTS posts_updated_at_key;
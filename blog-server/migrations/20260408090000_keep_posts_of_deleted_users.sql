@@ -0,0 +1,5 @@
+-- Posts of a deleted account stay in the trash until the retention period is over.
+ALTER TABLE posts ALTER COLUMN author_id DROP NOT NULL;
+ALTER TABLE posts DROP CONSTRAINT IF EXISTS posts_author_id_fkey;
+ALTER TABLE posts ADD CONSTRAINT posts_author_id_fkey
+    FOREIGN KEY (author_id) REFERENCES users (id) ON DELETE SET NULL;
@@ -0,0 +1,12 @@
+-- Posts with their tag names, sorted, as the repository reads them.
+CREATE OR REPLACE VIEW posts_with_tags AS
+SELECT posts.id, posts.title, posts.slug, posts.content, posts.content_format, posts.content_html,
+       posts.author_id, posts.created_at, posts.updated_at, posts.status, posts.published_at,
+       posts.version, posts.deleted_at, posts.search_language, posts.search_vector,
+       ARRAY(
+           SELECT tags.name FROM post_tags
+           JOIN tags ON tags.id = post_tags.tag_id
+           WHERE post_tags.post_id = posts.id
+           ORDER BY tags.name
+       ) AS tags
+FROM posts;
@@ -0,0 +1,173 @@
+use serde::Deserialize;
+use tracing::{error, info};
+
+use std::sync::Arc;
+
+use super::auth_service::{AuthService, LogoutReq};
+use super::blog_service::AuthUser;
+use super::email_verification_service::EmailVerificationService;
+use super::login_guard::LoginGuard;
+use super::policy::{self, Action};
+use super::session_service::{ClientInfo, SessionService};
+use super::validation::Validate;
+use crate::data::password_reset_repository::PasswordResetRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::error::AppError;
+use crate::domain::user::User;
+use crate::infrastructure::jwt::Claims;
+
+#[derive(Deserialize, Default)]
+pub struct ChangePasswordReq {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ChangeEmailReq {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct DeleteAccountReq {
+    pub password: String,
+    /// Id of the user that takes over the posts, only for moderators. Without it the
+    /// posts are deleted.
+    pub reassign_posts_to: Option<i64>,
+}
+
+pub struct AccountService {
+    user_repo: Arc<UserRepository>,
+    reset_repo: Arc<PasswordResetRepository>,
+    auth_service: Arc<AuthService>,
+    session_service: Arc<SessionService>,
+    verification_service: Arc<EmailVerificationService>,
+    login_guard: Arc<LoginGuard>,
+}
+
+impl AccountService {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        reset_repo: Arc<PasswordResetRepository>,
+        auth_service: Arc<AuthService>,
+        session_service: Arc<SessionService>,
+        verification_service: Arc<EmailVerificationService>,
+        login_guard: Arc<LoginGuard>,
+    ) -> Self {
+        Self {
+            user_repo,
+            reset_repo,
+            auth_service,
+            session_service,
+            verification_service,
+            login_guard,
+        }
+    }
+
+    /// Loads the account and checks the password the user confirmed the change with.
+    /// Failed checks count towards the login lockout, so a stolen access token can't be
+    /// used to guess the password.
+    async fn confirmed_user(
+        &self,
+        auth_user: &AuthUser,
+        password: &str,
+        client: &ClientInfo,
+    ) -> Result<User, AppError> {
+        policy::authorize(auth_user, Action::ManageAccount)?;
+        let user = self.user_repo.get_user_by_id(auth_user.id).await?;
+        self.login_guard.check(&user.username, client.ip).await?;
+        if user.verify_user(password).is_err() {
+            self.login_guard
+                .record_failure(&user.username, client.ip)
+                .await?;
+            return Err(AppError::Unauthorized("invalid credentials".to_string()));
+        }
+        self.login_guard.record_success(&user.username).await?;
+        Ok(user)
+    }
+
+    pub async fn change_password(
+        &self,
+        auth_user: AuthUser,
+        change_req: ChangePasswordReq,
+        client: ClientInfo,
+    ) -> Result<(), AppError> {
+        change_req.validate()?;
+        let mut user = self
+            .confirmed_user(&auth_user, &change_req.current_password, &client)
+            .await?;
+
+        user.set_password(&change_req.new_password)?;
+        self.user_repo.update_password_hash(&user).await?;
+
+        // Same as after a reset: sessions opened with the old password end.
+        self.reset_repo.invalidate_user_tokens(user.id).await?;
+        self.session_service.end_user_sessions(user.id).await?;
+
+        info!("Password changed for user: {}", user.id);
+        Ok(())
+    }
+
+    pub async fn change_email(
+        &self,
+        auth_user: AuthUser,
+        change_req: ChangeEmailReq,
+        client: ClientInfo,
+    ) -> Result<(), AppError> {
+        change_req.validate()?;
+        let mut user = self
+            .confirmed_user(&auth_user, &change_req.password, &client)
+            .await?;
+        if user.email == change_req.email {
+            return Err(AppError::InvalidRequest("email is the same".to_string()));
+        }
+
+        self.user_repo
+            .update_email(user.id, &change_req.email)
+            .await?;
+        user.email = change_req.email;
+        user.email_verified_at = None;
+
+        if let Err(e) = self.verification_service.restart_verification(&user).await {
+            error!("Can't send verification mail to user {}: {e}", user.id);
+        }
+
+        info!("Email changed for user: {}", user.id);
+        Ok(())
+    }
+
+    pub async fn delete_account(
+        &self,
+        claims: Claims,
+        delete_req: DeleteAccountReq,
+        client: ClientInfo,
+    ) -> Result<(), AppError> {
+        let auth_user = AuthUser::from(claims.clone());
+        let user = self
+            .confirmed_user(&auth_user, &delete_req.password, &client)
+            .await?;
+
+        if let Some(new_author_id) = delete_req.reassign_posts_to {
+            policy::authorize(&auth_user, Action::ReassignPosts { new_author_id })?;
+            if new_author_id == user.id {
+                return Err(AppError::InvalidRequest(
+                    "can't reassign posts to the deleted account".to_string(),
+                ));
+            }
+            self.user_repo.get_user_by_id(new_author_id).await?;
+        }
+
+        // Sessions are deleted with the account, their access tokens are rejected only if
+        // they are revoked first.
+        self.session_service.end_user_sessions(user.id).await?;
+        self.user_repo
+            .delete_user(user.id, delete_req.reassign_posts_to)
+            .await?;
+        self.auth_service
+            .logout(claims, LogoutReq::default())
+            .await?;
+
+        info!("Account deleted: {}", user.id);
+        Ok(())
+    }
+}
@@ -1,11 +1,26 @@
+use chrono::TimeDelta;
 use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
 
 use std::sync::Arc;
 
+use super::blog_service::AuthUser;
+use super::email_verification_service::EmailVerificationService;
+use super::login_guard::LoginGuard;
+use super::policy::{self, Action};
+use super::session_service::{ClientInfo, SessionService};
+use super::two_factor_service::{TwoFactorChallenge, TwoFactorService, VerifyTwoFactorReq};
+use super::validation::Validate;
+use crate::data::refresh_token_repository::RefreshTokenRepository;
+use crate::data::revoked_token_repository::RevokedTokenRepository;
 use crate::data::user_repository::UserRepository;
 use crate::domain::error::AppError;
+use crate::domain::refresh_token::RefreshToken;
+use crate::domain::role::Role;
 use crate::domain::user::User;
-use crate::infrastructure::jwt::JwtService;
+use crate::infrastructure::hash::{dummy_verify_password, hash_token};
+use crate::infrastructure::jwt::{Claims, JwtService};
+use crate::infrastructure::revocation::RevocationCache;
 
 #[derive(Deserialize, Default)]
 pub struct RegisterUserReq {
@@ -20,41 +35,339 @@ pub struct LoginUserReq {
     pub password: String,
 }
 
+#[derive(Deserialize, Default)]
+pub struct RefreshTokenReq {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct LogoutReq {
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SetUserRoleReq {
+    pub role: String,
+}
+
 #[derive(Serialize, Default)]
 pub struct RegisteredUser {
     pub token: String,
+    pub refresh_token: String,
+}
+
+/// Result of the password step: accounts with two-factor authentication get a
+/// challenge that has to be exchanged for tokens with `verify_two_factor`.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResp {
+    Authenticated(RegisteredUser),
+    TwoFactorRequired(TwoFactorChallenge),
 }
 
 pub struct AuthService {
     jwt_service: Arc<JwtService>,
     user_repo: Arc<UserRepository>,
+    refresh_repo: Arc<RefreshTokenRepository>,
+    revoked_repo: Arc<RevokedTokenRepository>,
+    verification_service: Arc<EmailVerificationService>,
+    login_guard: Arc<LoginGuard>,
+    two_factor_service: Arc<TwoFactorService>,
+    session_service: Arc<SessionService>,
+    revocation_cache: RevocationCache,
+    refresh_token_ttl: TimeDelta,
 }
 
 impl AuthService {
-    fn build_reg_user(&self, user: User) -> Result<RegisteredUser, AppError> {
-        let new_token = self
-            .jwt_service
-            .generate_token(&user.username, &user.email, user.id)?;
-        Ok(RegisteredUser { token: new_token })
+    async fn build_reg_user(
+        &self,
+        user: User,
+        family_id: Option<String>,
+        client: &ClientInfo,
+    ) -> Result<RegisteredUser, AppError> {
+        let token_id = self.refresh_repo.next_token_id().await?;
+        let (refresh_token, raw_refresh_token) =
+            RefreshToken::create(token_id, user.id, family_id, self.refresh_token_ttl);
+        self.refresh_repo.add_refresh_token(&refresh_token).await?;
+        let session_id = self
+            .session_service
+            .open_session(user.id, &refresh_token.family_id, client)
+            .await?;
+
+        let new_token = self.jwt_service.generate_token(
+            &user.username,
+            &user.email,
+            user.id,
+            user.role,
+            Some(session_id),
+        )?;
+
+        Ok(RegisteredUser {
+            token: new_token,
+            refresh_token: raw_refresh_token,
+        })
     }
-    pub fn new(jwt_service: Arc<JwtService>, user_repo: Arc<UserRepository>) -> Self {
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        jwt_service: Arc<JwtService>,
+        user_repo: Arc<UserRepository>,
+        refresh_repo: Arc<RefreshTokenRepository>,
+        revoked_repo: Arc<RevokedTokenRepository>,
+        verification_service: Arc<EmailVerificationService>,
+        login_guard: Arc<LoginGuard>,
+        two_factor_service: Arc<TwoFactorService>,
+        session_service: Arc<SessionService>,
+        refresh_token_ttl: TimeDelta,
+    ) -> Self {
         Self {
             jwt_service,
             user_repo,
+            refresh_repo,
+            revoked_repo,
+            verification_service,
+            login_guard,
+            two_factor_service,
+            session_service,
+            revocation_cache: RevocationCache::new(),
+            refresh_token_ttl,
+        }
+    }
+
+    pub fn verify_access_token(&self, token: &str) -> Option<Claims> {
+        let claims = self.jwt_service.verify_token(token)?;
+        if self.revocation_cache.is_revoked(&claims.jti) {
+            info!("Attempt to use revoked token by user: {}", claims.id);
+            return None;
+        }
+        if claims
+            .sid
+            .is_some_and(|session_id| self.session_service.is_revoked(session_id))
+        {
+            info!("Attempt to use token of revoked session by user: {}", claims.id);
+            return None;
+        }
+        Some(claims)
+    }
+
+    pub async fn sync_revocations(&self) -> Result<(), AppError> {
+        // Sessions go first so their in-memory state is pruned even if the tokens fail.
+        let sessions = self.session_service.sync_revocations().await;
+        let purged = self.revoked_repo.delete_expired().await?;
+        if purged > 0 {
+            info!("Purged {purged} expired token revocations");
         }
+        let revoked = self.revoked_repo.get_active_revocations().await?;
+        self.revocation_cache.merge(revoked);
+        self.revocation_cache.purge_expired();
+        sessions
     }
-    pub async fn register(&self, reg_req: RegisterUserReq) -> Result<RegisteredUser, AppError> {
+
+    pub async fn register(
+        &self,
+        reg_req: RegisterUserReq,
+        client: ClientInfo,
+    ) -> Result<RegisteredUser, AppError> {
+        reg_req.validate()?;
         let user_id = self.user_repo.next_user_id().await?;
         let new_user = User::create(user_id, reg_req.username, reg_req.email, reg_req.password)?;
 
         self.user_repo.add_new_user(&new_user).await?;
-        self.build_reg_user(new_user)
+        // The account is usable even if the mail can't be delivered now, the user can
+        // ask for another verification mail later.
+        if let Err(e) = self.verification_service.send_verification(&new_user).await {
+            error!("Can't send verification mail to user {}: {e}", new_user.id);
+        }
+        self.build_reg_user(new_user, None, &client).await
     }
 
-    pub async fn login(&self, log_req: LoginUserReq) -> Result<RegisteredUser, AppError> {
+    pub async fn login(
+        &self,
+        log_req: LoginUserReq,
+        client: ClientInfo,
+    ) -> Result<LoginResp, AppError> {
         let user_name = log_req.username;
-        let user = self.user_repo.get_user(&user_name).await?;
-        user.verify_user(&log_req.password)?;
-        self.build_reg_user(user)
+        self.login_guard.check(&user_name, client.ip).await?;
+
+        // Unknown users and wrong passwords must be indistinguishable, both in the
+        // response and in the time it takes.
+        let verified = match self.user_repo.get_user(&user_name).await {
+            Ok(user) => user.verify_user(&log_req.password).map(|_| user),
+            Err(AppError::UserNotFound(_)) => {
+                dummy_verify_password(&log_req.password);
+                info!("Attempt to log in as unknown user: {user_name}");
+                Err(AppError::UserNotFound(user_name.clone()))
+            }
+            Err(e) => return Err(e),
+        };
+
+        let Ok(mut user) = verified else {
+            self.login_guard.record_failure(&user_name, client.ip).await?;
+            return Err(AppError::Unauthorized("invalid credentials".to_string()));
+        };
+        self.rehash_password(&mut user, &log_req.password).await;
+
+        if user.is_two_factor_enabled() {
+            let challenge = self.two_factor_service.create_challenge(&user).await?;
+            info!("User {} passed password check, waiting for second factor", user.id);
+            return Ok(LoginResp::TwoFactorRequired(challenge));
+        }
+
+        self.login_guard.record_success(&user_name).await?;
+        let reg_user = self.build_reg_user(user, None, &client).await?;
+        Ok(LoginResp::Authenticated(reg_user))
+    }
+
+    /// Brings the hash up to the current parameters while the password is at hand.
+    /// The login doesn't fail if that's not possible, the old hash stays valid.
+    async fn rehash_password(&self, user: &mut User, password: &str) {
+        if !user.is_password_hash_outdated() {
+            return;
+        }
+
+        let rehashed = match user.set_password(password) {
+            Ok(()) => self.user_repo.update_password_hash(user).await,
+            Err(e) => Err(e),
+        };
+        match rehashed {
+            Ok(()) => info!("Password hash of user {} upgraded", user.id),
+            Err(e) => error!("Can't upgrade password hash of user {}: {e}", user.id),
+        }
+    }
+
+    pub async fn verify_two_factor(
+        &self,
+        verify_req: VerifyTwoFactorReq,
+        client: ClientInfo,
+    ) -> Result<RegisteredUser, AppError> {
+        let (challenge, user) = self
+            .two_factor_service
+            .open_challenge(&verify_req.challenge_token)
+            .await?;
+        self.login_guard.check(&user.username, client.ip).await?;
+
+        if !self.two_factor_service.verify_code(&user, &verify_req.code).await? {
+            self.login_guard
+                .record_failure(&user.username, client.ip)
+                .await?;
+            return Err(AppError::Unauthorized("invalid two-factor code".to_string()));
+        }
+
+        self.two_factor_service.close_challenge(&challenge).await?;
+        self.login_guard.record_success(&user.username).await?;
+        self.build_reg_user(user, None, &client).await
+    }
+
+    /// Finishes a login whose first factor was checked elsewhere, e.g. by an identity
+    /// provider. Accounts with two-factor authentication still get a challenge.
+    pub async fn sign_in(&self, user: User, client: ClientInfo) -> Result<LoginResp, AppError> {
+        if user.is_two_factor_enabled() {
+            let challenge = self.two_factor_service.create_challenge(&user).await?;
+            return Ok(LoginResp::TwoFactorRequired(challenge));
+        }
+        let reg_user = self.build_reg_user(user, None, &client).await?;
+        Ok(LoginResp::Authenticated(reg_user))
+    }
+
+    pub async fn purge_login_throttles(&self) -> Result<(), AppError> {
+        self.login_guard.purge_stale().await?;
+        self.two_factor_service.purge_expired_challenges().await
+    }
+
+    pub async fn refresh(
+        &self,
+        refresh_req: RefreshTokenReq,
+        client: ClientInfo,
+    ) -> Result<RegisteredUser, AppError> {
+        let token_hash = hash_token(&refresh_req.refresh_token);
+        let stored = self.refresh_repo.get_refresh_token(&token_hash).await?;
+
+        if stored.revoked_at.is_some() {
+            return Err(AppError::Unauthorized("refresh token revoked".to_string()));
+        }
+
+        if stored.is_expired() && !stored.is_consumed() {
+            return Err(AppError::Unauthorized("refresh token expired".to_string()));
+        }
+
+        // A consumed token can only be presented again if it leaked, so the whole
+        // chain issued from the same login is revoked.
+        if stored.is_consumed() || !self.refresh_repo.mark_used(stored.id).await? {
+            warn!(
+                "Refresh token reuse detected for user: {}, revoking family: {}",
+                stored.user_id, stored.family_id
+            );
+            self.refresh_repo.revoke_family(&stored.family_id).await?;
+            self.session_service
+                .end_family_session(&stored.family_id)
+                .await?;
+            return Err(AppError::Unauthorized("refresh token reused".to_string()));
+        }
+
+        let user = self.user_repo.get_user_by_id(stored.user_id).await?;
+        self.build_reg_user(user, Some(stored.family_id), &client)
+            .await
+    }
+
+    pub async fn logout(&self, claims: Claims, logout_req: LogoutReq) -> Result<(), AppError> {
+        if claims.is_personal_token() {
+            return Err(AppError::InvalidRequest(
+                "personal access tokens are revoked, not logged out".to_string(),
+            ));
+        }
+
+        let expires_at = claims.expires_at();
+        self.revoked_repo
+            .add_revoked_token(&claims.jti, claims.id, expires_at)
+            .await?;
+        self.revocation_cache.insert(claims.jti, expires_at);
+        if let Some(session_id) = claims.sid {
+            match self.session_service.end_session(claims.id, session_id).await {
+                Ok(()) | Err(AppError::SessionNotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some(refresh_token) = logout_req.refresh_token {
+            let token_hash = hash_token(&refresh_token);
+            match self.refresh_repo.get_refresh_token(&token_hash).await {
+                Ok(stored) if stored.user_id == claims.id => {
+                    self.refresh_repo.revoke_family(&stored.family_id).await?;
+                    self.session_service
+                        .end_family_session(&stored.family_id)
+                        .await?;
+                }
+                Ok(_) => {
+                    warn!(
+                        "Attempt to revoke foreign refresh token by user: {}",
+                        claims.id
+                    );
+                }
+                Err(AppError::Unauthorized(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        info!("User {} logged out", claims.id);
+        Ok(())
+    }
+
+    pub async fn set_user_role(
+        &self,
+        auth_user: AuthUser,
+        user_id: i64,
+        role_req: SetUserRoleReq,
+    ) -> Result<(), AppError> {
+        policy::authorize(&auth_user, Action::ManageRoles)?;
+        let role = role_req.role.parse::<Role>()?;
+        if user_id == auth_user.id {
+            return Err(AppError::InvalidRequest(
+                "can't change own role".to_string(),
+            ));
+        }
+
+        self.user_repo.set_user_role(user_id, role).await?;
+        info!("User {} set role {role} for user {user_id}", auth_user.id);
+        Ok(())
     }
 }
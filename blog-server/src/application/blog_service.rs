@@ -1,21 +1,53 @@
 use actix_web::HttpMessage;
 use actix_web::{FromRequest, HttpRequest, dev::Payload};
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::info;
 
 use std::future::{Ready, ready};
 use std::sync::Arc;
 
 use crate::data::post_repository::PostRepository;
+use crate::data::post_revision_repository::PostRevisionRepository;
+use crate::data::tag_repository::TagRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::content_format::ContentFormat;
 use crate::domain::error::AppError;
 use crate::domain::post::Post;
+use crate::domain::post_cursor::{Direction, PostCursor};
+use crate::domain::post_revision::PostRevision;
+use crate::domain::post_search::{self, SearchHit};
+use crate::domain::post_status::PostStatus;
+use crate::domain::role::Role;
+use crate::domain::scope::Scope;
+use crate::domain::tag::{self, TagCount};
+use crate::domain::word_diff::{DiffChunk, word_diff};
 use crate::infrastructure::jwt::Claims;
-use tracing::warn;
+
+use super::policy::{self, Action};
+use super::validation::Validate;
 
 #[derive(Default, Debug)]
 pub struct AuthUser {
     pub username: String,
     pub email: String,
     pub id: i64,
+    pub role: Role,
+    pub scopes: Option<Vec<Scope>>,
+    pub session_id: Option<i64>,
+}
+
+impl From<Claims> for AuthUser {
+    fn from(claims: Claims) -> Self {
+        Self {
+            username: claims.username,
+            email: claims.email,
+            id: claims.id,
+            role: claims.role,
+            scopes: claims.scopes,
+            session_id: claims.sid,
+        }
+    }
 }
 
 impl FromRequest for AuthUser {
@@ -24,11 +56,7 @@ impl FromRequest for AuthUser {
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
         if let Some(claims) = req.extensions().get::<Claims>() {
-            let mut auth_user = AuthUser::default();
-            auth_user.id = claims.id;
-            auth_user.username = claims.username.clone();
-            auth_user.email = claims.email.clone();
-            return ready(Ok(auth_user));
+            return ready(Ok(AuthUser::from(claims.clone())));
         }
         ready(Err(AppError::InternalError("Invalid type".to_string())))
     }
@@ -38,12 +66,27 @@ impl FromRequest for AuthUser {
 pub struct NewPost {
     pub title: String,
     pub content: String,
+    /// Published right away if not set.
+    pub status: Option<PostStatus>,
+    pub publish_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Plain text if not set.
+    pub content_format: Option<ContentFormat>,
 }
 
 #[derive(Deserialize, Default)]
 pub struct UpdatePost {
     pub title: Option<String>,
     pub content: Option<String>,
+    pub status: Option<PostStatus>,
+    pub publish_at: Option<DateTime<Utc>>,
+    /// Replaces all tags of the post when set.
+    pub tags: Option<Vec<String>>,
+    pub content_format: Option<ContentFormat>,
+    /// Version the edit is based on. Comes from the `If-Match` header on HTTP.
+    #[serde(skip)]
+    pub expected_version: Option<i64>,
 }
 
 #[derive(Deserialize, Default)]
@@ -51,49 +94,241 @@ pub struct PostId {
     pub id: i64,
 }
 
+#[derive(Deserialize, Default)]
+pub struct PostSlug {
+    pub slug: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct RevisionId {
+    pub id: i64,
+    pub revision: i32,
+}
+
+#[derive(Deserialize, Default)]
+pub struct RevisionDiffReq {
+    pub from: i32,
+    pub to: i32,
+}
+
 #[derive(Deserialize, Default)]
 pub struct GetPostsReq {
     pub offset: Option<i64>,
     pub limit: Option<i64>,
+    pub tag: Option<String>,
+    /// `next_cursor` or `prev_cursor` of a previous page, takes precedence over `offset`.
+    pub cursor: Option<String>,
+    /// Whether to count all posts of the feed, which costs an extra query.
+    #[serde(default)]
+    pub with_total: bool,
 }
 
 #[derive(Serialize, Default)]
 pub struct PostInfo {
     pub id: i64,
     pub title: String,
+    pub slug: String,
     pub content: String,
+    pub content_format: ContentFormat,
+    /// Sanitized HTML of the content.
+    pub content_html: String,
     pub author_id: i64,
     pub created_at: String,
     pub updated_at: String,
+    pub status: PostStatus,
+    pub published_at: Option<String>,
+    pub version: i64,
+    /// Set only for posts in the trash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    pub tags: Vec<String>,
 }
 
 impl From<Post> for PostInfo {
     fn from(post: Post) -> Self {
         Self {
             id: post.id,
+            content_html: post.content_html(),
             content: post.content,
+            content_format: post.content_format,
             title: post.title,
+            slug: post.slug,
             author_id: post.author_id,
             created_at: post.created_at.to_rfc3339(),
             updated_at: post.updated_at.to_rfc3339(),
+            status: post.status,
+            published_at: post.published_at.map(|published_at| published_at.to_rfc3339()),
+            version: post.version,
+            deleted_at: post.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()),
+            tags: post.tags,
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct TagInfo {
+    pub name: String,
+    pub post_count: i64,
+}
+
+impl From<TagCount> for TagInfo {
+    fn from(tag: TagCount) -> Self {
+        Self {
+            name: tag.name,
+            post_count: tag.post_count,
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct RevisionInfo {
+    pub post_id: i64,
+    pub revision: i32,
+    pub title: String,
+    /// Left out of revision lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    pub editor_id: Option<i64>,
+    pub created_at: String,
+}
+
+impl From<PostRevision> for RevisionInfo {
+    fn from(revision: PostRevision) -> Self {
+        Self {
+            post_id: revision.post_id,
+            revision: revision.revision,
+            title: revision.title,
+            content: Some(revision.content),
+            editor_id: revision.editor_id,
+            created_at: revision.created_at.to_rfc3339(),
         }
     }
 }
 
+/// Changes made between two revisions.
+#[derive(Serialize, Default)]
+pub struct RevisionDiff {
+    pub post_id: i64,
+    pub from: i32,
+    pub to: i32,
+    pub title: Vec<DiffChunk>,
+    pub content: Vec<DiffChunk>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SearchPostsReq {
+    pub q: String,
+    /// Text search configuration the query is parsed with, the server one if not set.
+    pub lang: Option<String>,
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Default)]
+pub struct SearchHitInfo {
+    pub post: PostInfo,
+    pub rank: f32,
+    /// HTML-escaped fragments of the content with matches wrapped in `<mark>`.
+    pub snippet: String,
+}
+
+impl From<SearchHit> for SearchHitInfo {
+    fn from(hit: SearchHit) -> Self {
+        Self {
+            post: PostInfo::from(hit.post),
+            rank: hit.rank,
+            snippet: post_search::highlight(&hit.snippet),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct SearchResp {
+    pub offset: i64,
+    pub limit: i64,
+    pub hits: Vec<SearchHitInfo>,
+}
+
 #[derive(Serialize, Default)]
 pub struct PostResp {
     pub offset: i64,
     pub limit: i64,
     pub posts: Vec<PostInfo>,
+    /// Cursor of the page with older posts, `None` on the last page.
+    pub next_cursor: Option<String>,
+    /// Cursor of the page with newer posts, `None` on the first page.
+    pub prev_cursor: Option<String>,
+    pub total: Option<i64>,
+}
+
+const DEFAULT_PAGE_SIZE: i64 = 10;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Page size of a listing, kept within `1..=MAX_PAGE_SIZE`.
+fn page_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Rejects the change if the caller saw another version of the post.
+fn check_version(post: &Post, expected_version: Option<i64>) -> Result<(), AppError> {
+    match expected_version {
+        Some(expected) if expected != post.version => Err(AppError::VersionConflict(format!(
+            "post {} is at version {}, not {expected}",
+            post.id, post.version
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Unpublished posts are hidden from everyone who can't edit them, posts in the trash
+/// from everyone.
+pub(crate) fn can_view(viewer: Option<&AuthUser>, post: &Post) -> bool {
+    let action = Action::ViewDraft {
+        author_id: post.author_id,
+    };
+    !post.is_in_trash()
+        && (post.is_published()
+            || viewer.is_some_and(|viewer| policy::authorize(viewer, action).is_ok()))
+}
+
+/// Authors see their own drafts and scheduled posts among the published ones.
+fn draft_viewer_id(viewer: Option<AuthUser>) -> Option<i64> {
+    viewer
+        .filter(|viewer| {
+            policy::authorize(viewer, Action::ViewDraft { author_id: viewer.id }).is_ok()
+        })
+        .map(|viewer| viewer.id)
 }
 
 pub struct BlogService {
     post_repo: Arc<PostRepository>,
+    revision_repo: Arc<PostRevisionRepository>,
+    tag_repo: Arc<TagRepository>,
+    user_repo: Arc<UserRepository>,
+    allow_unverified_posting: bool,
+    trash_retention: TimeDelta,
+    search_language: String,
 }
 
 impl BlogService {
-    pub fn new(post_repo: Arc<PostRepository>) -> Self {
-        BlogService { post_repo }
+    pub fn new(
+        post_repo: Arc<PostRepository>,
+        revision_repo: Arc<PostRevisionRepository>,
+        tag_repo: Arc<TagRepository>,
+        user_repo: Arc<UserRepository>,
+        allow_unverified_posting: bool,
+        trash_retention: TimeDelta,
+        search_language: String,
+    ) -> Self {
+        BlogService {
+            post_repo,
+            revision_repo,
+            tag_repo,
+            user_repo,
+            allow_unverified_posting,
+            trash_retention,
+            search_language,
+        }
     }
 
     pub async fn create_post(
@@ -101,15 +336,59 @@ impl BlogService {
         auth_user: AuthUser,
         new_post: NewPost,
     ) -> Result<PostInfo, AppError> {
+        new_post.validate()?;
+        policy::authorize(&auth_user, Action::CreatePost)?;
+        if !self.allow_unverified_posting {
+            let user = self.user_repo.get_user_by_id(auth_user.id).await?;
+            if !user.is_email_verified() {
+                return Err(AppError::Forbidden("email is not verified".to_string()));
+            }
+        }
+
         let post_id = self.post_repo.next_post_id().await?;
-        let post = Post::create(post_id, new_post.title, new_post.content, auth_user.id);
+        let mut post = Post::create(
+            post_id,
+            new_post.title,
+            new_post.content,
+            auth_user.id,
+            new_post.tags,
+        );
+        if let Some(status) = new_post.status {
+            post.set_status(status, new_post.publish_at);
+        }
+        if let Some(content_format) = new_post.content_format {
+            post.set_content_format(content_format);
+        }
 
-        self.post_repo.add_new_post(&post).await?;
+        self.post_repo
+            .add_new_post(&mut post, &self.search_language)
+            .await?;
         Ok(PostInfo::from(post))
     }
 
-    pub async fn get_post(&self, post_id: PostId) -> Result<PostInfo, AppError> {
+    pub async fn get_post(
+        &self,
+        viewer: Option<AuthUser>,
+        post_id: PostId,
+    ) -> Result<PostInfo, AppError> {
         let post = self.post_repo.get_post(post_id.id).await?;
+        if !can_view(viewer.as_ref(), &post) {
+            return Err(AppError::PostNotFound(post_id.id.to_string()));
+        }
+        Ok(PostInfo::from(post))
+    }
+
+    /// A post renamed since the slug was issued is still found by it; the current
+    /// slug is in the returned post.
+    pub async fn get_post_by_slug(
+        &self,
+        viewer: Option<AuthUser>,
+        post_slug: PostSlug,
+    ) -> Result<PostInfo, AppError> {
+        let post = self.post_repo.get_post_by_slug(&post_slug.slug).await?;
+        if !can_view(viewer.as_ref(), &post) {
+            return Err(AppError::PostNotFound(post_slug.slug));
+        }
         Ok(PostInfo::from(post))
     }
 
@@ -119,50 +398,469 @@ impl BlogService {
         post_id: PostId,
         update_post: UpdatePost,
     ) -> Result<PostInfo, AppError> {
-        let author_id = self.post_repo.get_post_author_id(post_id.id).await?;
-        if author_id != auth_user.id {
-            warn!(
-                "Attempt to update post: {} by user: {:?}",
-                post_id.id, auth_user
-            );
-            return Err(AppError::Unauthorized(
-                "No permission for update".to_string(),
-            ));
+        update_post.validate()?;
+        let mut post = self.post_repo.get_post(post_id.id).await?;
+        policy::authorize(
+            &auth_user,
+            Action::EditPost {
+                author_id: post.author_id,
+            },
+        )?;
+        check_version(&post, update_post.expected_version)?;
+
+        let changed = post.update(update_post.title, update_post.content);
+        if let Some(status) = update_post.status {
+            post.set_status(status, update_post.publish_at);
+        }
+        if let Some(tags) = update_post.tags {
+            post.set_tags(tags);
         }
+        if let Some(content_format) = update_post.content_format {
+            post.set_content_format(content_format);
+        }
+        self.post_repo
+            .update_post(&mut post, changed.then_some(auth_user.id))
+            .await?;
+        Ok(PostInfo::from(post))
+    }
 
-        let post = self
-            .post_repo
-            .update_post(post_id.id, update_post.title, update_post.content)
+    async fn authorize_history(&self, auth_user: &AuthUser, post_id: i64) -> Result<(), AppError> {
+        let author_id = self.post_repo.get_post_author_id(post_id).await?;
+        policy::authorize(auth_user, Action::ViewHistory { author_id })
+    }
+
+    pub async fn list_revisions(
+        &self,
+        auth_user: AuthUser,
+        post_id: PostId,
+    ) -> Result<Vec<RevisionInfo>, AppError> {
+        self.authorize_history(&auth_user, post_id.id).await?;
+        let revisions = self.revision_repo.get_revisions(post_id.id).await?;
+        Ok(revisions
+            .into_iter()
+            .map(|revision| RevisionInfo {
+                content: None,
+                ..RevisionInfo::from(revision)
+            })
+            .collect())
+    }
+
+    pub async fn get_revision(
+        &self,
+        auth_user: AuthUser,
+        revision_id: RevisionId,
+    ) -> Result<RevisionInfo, AppError> {
+        self.authorize_history(&auth_user, revision_id.id).await?;
+        let revision = self
+            .revision_repo
+            .get_revision(revision_id.id, revision_id.revision)
             .await?;
+        Ok(RevisionInfo::from(revision))
+    }
+
+    pub async fn diff_revisions(
+        &self,
+        auth_user: AuthUser,
+        post_id: PostId,
+        diff_req: RevisionDiffReq,
+    ) -> Result<RevisionDiff, AppError> {
+        self.authorize_history(&auth_user, post_id.id).await?;
+        let from = self.revision_repo.get_revision(post_id.id, diff_req.from).await?;
+        let to = self.revision_repo.get_revision(post_id.id, diff_req.to).await?;
+        Ok(RevisionDiff {
+            post_id: post_id.id,
+            from: from.revision,
+            to: to.revision,
+            title: word_diff(&from.title, &to.title),
+            content: word_diff(&from.content, &to.content),
+        })
+    }
+
+    /// Brings back the title and the content of a revision. The restored text is
+    /// saved as a new revision, so the history is never rewritten.
+    pub async fn restore_revision(
+        &self,
+        auth_user: AuthUser,
+        revision_id: RevisionId,
+        expected_version: Option<i64>,
+    ) -> Result<PostInfo, AppError> {
+        let mut post = self.post_repo.get_post(revision_id.id).await?;
+        policy::authorize(
+            &auth_user,
+            Action::EditPost {
+                author_id: post.author_id,
+            },
+        )?;
+        check_version(&post, expected_version)?;
+        let revision = self
+            .revision_repo
+            .get_revision(revision_id.id, revision_id.revision)
+            .await?;
+
+        let changed = post.update(Some(revision.title), Some(revision.content));
+        if changed {
+            self.post_repo
+                .update_post(&mut post, Some(auth_user.id))
+                .await?;
+            info!(
+                "Post {} restored to revision {} by user {}",
+                post.id, revision.revision, auth_user.id
+            );
+        }
         Ok(PostInfo::from(post))
     }
 
     pub async fn delete_post(&self, auth_user: AuthUser, post_id: PostId) -> Result<(), AppError> {
         let author_id = self.post_repo.get_post_author_id(post_id.id).await?;
-        if author_id != auth_user.id {
-            warn!(
-                "Attempt to delete post: {} by user: {:?}",
-                post_id.id, auth_user
-            );
-            return Err(AppError::Unauthorized(
-                "No permission for delete".to_string(),
-            ));
-        }
-        self.post_repo.delete_post(post_id.id).await
+        policy::authorize(&auth_user, Action::DeletePost { author_id })?;
+        self.post_repo.delete_post(post_id.id).await?;
+        info!("Post {} moved to trash by user {}", post_id.id, auth_user.id);
+        Ok(())
     }
 
-    pub async fn get_posts(&self, query: GetPostsReq) -> Result<PostResp, AppError> {
+    pub async fn get_trash(
+        &self,
+        auth_user: AuthUser,
+        query: GetPostsReq,
+    ) -> Result<PostResp, AppError> {
+        policy::authorize(
+            &auth_user,
+            Action::ViewTrash {
+                author_id: auth_user.id,
+            },
+        )?;
         let offset = query.offset.unwrap_or(0);
-        let limit = query.limit.unwrap_or(10);
+        let limit = page_limit(query.limit);
+
+        let posts = self
+            .post_repo
+            .get_trash(auth_user.id, offset, limit)
+            .await?;
+        Ok(PostResp {
+            offset,
+            limit,
+            posts: posts.into_iter().map(PostInfo::from).collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Takes a post out of the trash. Allowed to whoever could delete it.
+    pub async fn restore_post(
+        &self,
+        auth_user: AuthUser,
+        post_id: PostId,
+    ) -> Result<PostInfo, AppError> {
+        let mut post = self.post_repo.get_deleted_post(post_id.id).await?;
+        policy::authorize(
+            &auth_user,
+            Action::DeletePost {
+                author_id: post.author_id,
+            },
+        )?;
+        self.post_repo.restore_post(post.id).await?;
+        post.restore_from_trash();
+        info!("Post {} restored from trash by user {}", post.id, auth_user.id);
+        Ok(PostInfo::from(post))
+    }
+
+    /// Deletes a post from the trash for good.
+    pub async fn purge_post(&self, auth_user: AuthUser, post_id: PostId) -> Result<(), AppError> {
+        let post = self.post_repo.get_deleted_post(post_id.id).await?;
+        policy::authorize(
+            &auth_user,
+            Action::DeletePost {
+                author_id: post.author_id,
+            },
+        )?;
+        self.post_repo.purge_post(post.id).await?;
+        info!("Post {} purged by user {}", post.id, auth_user.id);
+        Ok(())
+    }
+
+    /// Deletes posts that stayed in the trash longer than the retention period.
+    pub async fn purge_trash(&self) -> Result<(), AppError> {
+        let purged = self
+            .post_repo
+            .purge_deleted(Utc::now() - self.trash_retention)
+            .await?;
+        for post_id in &purged {
+            info!("Post {post_id} purged from trash");
+        }
+        Ok(())
+    }
+
+    pub async fn get_posts(
+        &self,
+        viewer: Option<AuthUser>,
+        query: GetPostsReq,
+    ) -> Result<PostResp, AppError> {
+        let limit = page_limit(query.limit);
+        let cursor = query.cursor.as_deref().map(PostCursor::decode).transpose()?;
+        let offset = match cursor {
+            Some(_) => 0,
+            None => query.offset.unwrap_or(0),
+        };
+
+        let viewer_id = draft_viewer_id(viewer);
+        let tag = query.tag.as_deref().map(tag::normalize);
+        // One extra post tells whether there is another page in the same direction.
+        let mut posts = self
+            .post_repo
+            .get_posts(viewer_id, tag.as_deref(), cursor.as_ref(), offset, limit + 1)
+            .await?;
+        let has_more = posts.len() as i64 > limit;
+        posts.truncate(limit as usize);
+
+        let backward = cursor
+            .as_ref()
+            .is_some_and(|cursor| cursor.direction == Direction::Before);
+        let (has_older, has_newer) = if backward {
+            posts.reverse();
+            (true, has_more)
+        } else {
+            (has_more, cursor.is_some() || offset > 0)
+        };
+        let next_cursor = posts
+            .last()
+            .filter(|_| has_older)
+            .map(|post| PostCursor::after(post).encode());
+        let prev_cursor = posts
+            .first()
+            .filter(|_| has_newer)
+            .map(|post| PostCursor::before(post).encode());
 
-        let posts = self.post_repo.get_posts(offset, limit).await?;
-        let posts_info: Vec<PostInfo> =
-            posts.into_iter().map(|post| PostInfo::from(post)).collect();
+        let total = if query.with_total {
+            Some(self.post_repo.count_posts(viewer_id, tag.as_deref()).await?)
+        } else {
+            None
+        };
 
         Ok(PostResp {
             offset,
             limit,
-            posts: posts_info,
+            posts: posts.into_iter().map(PostInfo::from).collect(),
+            next_cursor,
+            prev_cursor,
+            total,
         })
     }
+
+    /// Full-text search over the posts the viewer can see, best matches first.
+    pub async fn search_posts(
+        &self,
+        viewer: Option<AuthUser>,
+        query: SearchPostsReq,
+    ) -> Result<SearchResp, AppError> {
+        query.validate()?;
+        let offset = query.offset.unwrap_or(0);
+        let limit = page_limit(query.limit);
+        let language = query.lang.as_deref().unwrap_or(&self.search_language);
+
+        let hits = self
+            .post_repo
+            .search_posts(draft_viewer_id(viewer), &query.q, language, offset, limit)
+            .await?;
+        Ok(SearchResp {
+            offset,
+            limit,
+            hits: hits.into_iter().map(SearchHitInfo::from).collect(),
+        })
+    }
+
+    pub async fn list_tags(&self) -> Result<Vec<TagInfo>, AppError> {
+        let tags = self.tag_repo.get_tag_counts().await?;
+        Ok(tags.into_iter().map(TagInfo::from).collect())
+    }
+
+    /// Publishes scheduled posts whose publication time has come.
+    pub async fn publish_scheduled(&self) -> Result<(), AppError> {
+        let published = self.post_repo.publish_due().await?;
+        for post_id in &published {
+            info!("Scheduled post {post_id} published");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::User;
+    use sqlx::PgPool;
+
+    #[test]
+    fn test_page_limit() {
+        assert_eq!(page_limit(None), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_limit(Some(25)), 25);
+        assert_eq!(page_limit(Some(0)), 1);
+        assert_eq!(page_limit(Some(-5)), 1);
+        assert_eq!(page_limit(Some(i64::MAX)), MAX_PAGE_SIZE);
+    }
+
+    fn author(id: i64) -> AuthUser {
+        AuthUser {
+            id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_can_view() {
+        let mut post = Post::create(1, "Title".to_string(), "Text".to_string(), 6, vec![]);
+        assert!(can_view(None, &post));
+        assert!(can_view(Some(&author(7)), &post));
+
+        post.set_status(PostStatus::Draft, None);
+        assert!(!can_view(None, &post));
+        assert!(!can_view(Some(&author(7)), &post));
+        assert!(can_view(Some(&author(6)), &post));
+    }
+
+    #[test]
+    fn test_trashed_post_is_not_viewable() {
+        let mut post = Post::create(1, "Title".to_string(), "Text".to_string(), 6, vec![]);
+        post.deleted_at = Some(Utc::now());
+        assert!(!can_view(None, &post));
+        assert!(!can_view(Some(&author(6)), &post));
+
+        post.restore_from_trash();
+        assert!(can_view(None, &post));
+    }
+
+    async fn service_with_post(pool: PgPool) -> (BlogService, PostInfo) {
+        let user_repo = Arc::new(UserRepository::new(pool.clone()));
+        let user_id = user_repo.next_user_id().await.unwrap();
+        let user = User::create(
+            user_id,
+            "alice".to_string(),
+            "alice@example.com".to_string(),
+            "password1".to_string(),
+        )
+        .unwrap();
+        user_repo.add_new_user(&user).await.unwrap();
+
+        let service = BlogService::new(
+            Arc::new(PostRepository::new(pool.clone())),
+            Arc::new(PostRevisionRepository::new(pool.clone())),
+            Arc::new(TagRepository::new(pool)),
+            user_repo,
+            true,
+            TimeDelta::days(30),
+            "simple".to_string(),
+        );
+        let new_post = NewPost {
+            title: "Trash".to_string(),
+            content: "Soon gone".to_string(),
+            ..Default::default()
+        };
+        let post = service.create_post(author(user_id), new_post).await.unwrap();
+        (service, post)
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_restore_and_purge_need_trashed_post(pool: PgPool) {
+        let (service, post) = service_with_post(pool).await;
+        let post_id = || PostId { id: post.id };
+
+        let restored = service.restore_post(author(post.author_id), post_id()).await;
+        assert!(matches!(restored, Err(AppError::PostNotFound(_))));
+        let purged = service.purge_post(author(post.author_id), post_id()).await;
+        assert!(matches!(purged, Err(AppError::PostNotFound(_))));
+
+        service
+            .get_post(None, post_id())
+            .await
+            .expect("post must survive a failed purge");
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_trashed_post_is_hidden(pool: PgPool) {
+        let (service, post) = service_with_post(pool).await;
+        let post_id = || PostId { id: post.id };
+        let post_slug = || PostSlug {
+            slug: post.slug.clone(),
+        };
+        service
+            .delete_post(author(post.author_id), post_id())
+            .await
+            .unwrap();
+
+        for viewer in [None, Some(post.author_id)] {
+            let found = service.get_post(viewer.map(author), post_id()).await;
+            assert!(matches!(found, Err(AppError::PostNotFound(_))));
+            let found = service.get_post_by_slug(viewer.map(author), post_slug()).await;
+            assert!(matches!(found, Err(AppError::PostNotFound(_))));
+            let feed = service
+                .get_posts(viewer.map(author), GetPostsReq::default())
+                .await
+                .unwrap();
+            assert!(feed.posts.is_empty());
+        }
+
+        let trash = service
+            .get_trash(author(post.author_id), GetPostsReq::default())
+            .await
+            .unwrap();
+        assert_eq!(trash.posts.len(), 1);
+        service
+            .restore_post(author(post.author_id), post_id())
+            .await
+            .unwrap();
+        service.get_post(None, post_id()).await.unwrap();
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_restore_revision_checks_version(pool: PgPool) {
+        let (service, post) = service_with_post(pool).await;
+        let revision_id = || RevisionId {
+            id: post.id,
+            revision: 1,
+        };
+
+        let stale = post.version - 1;
+        let restored = service
+            .restore_revision(author(post.author_id), revision_id(), Some(stale))
+            .await;
+        assert!(matches!(restored, Err(AppError::VersionConflict(_))));
+        service
+            .restore_revision(author(post.author_id), revision_id(), Some(post.version))
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_publish_posts_scheduled_for_same_time(pool: PgPool) {
+        let (service, post) = service_with_post(pool.clone()).await;
+        let mut scheduled = Vec::new();
+        for title in ["First", "Second"] {
+            let new_post = NewPost {
+                title: title.to_string(),
+                content: "Later".to_string(),
+                status: Some(PostStatus::Scheduled),
+                publish_at: Some(Utc::now() + TimeDelta::hours(1)),
+                ..Default::default()
+            };
+            let created = service
+                .create_post(author(post.author_id), new_post)
+                .await
+                .unwrap();
+            scheduled.push(created.id);
+        }
+        sqlx::query("UPDATE posts SET published_at = $1 WHERE id = ANY($2)")
+            .bind(Utc::now() - TimeDelta::minutes(1))
+            .bind(&scheduled)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        service.publish_scheduled().await.unwrap();
+        for id in scheduled {
+            let published = service.get_post(None, PostId { id }).await.unwrap();
+            assert_eq!(published.status, PostStatus::Published);
+        }
+    }
 }
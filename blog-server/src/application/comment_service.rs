@@ -0,0 +1,215 @@
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+
+use std::sync::Arc;
+
+use super::blog_service::{AuthUser, PostId, can_view};
+use super::policy::{self, Action};
+use super::validation::Validate;
+use crate::data::comment_repository::CommentRepository;
+use crate::data::post_repository::PostRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::comment::{self, Comment};
+use crate::domain::error::AppError;
+
+#[derive(Deserialize, Default)]
+pub struct NewComment {
+    pub content: String,
+    /// Id of the comment this one replies to.
+    pub parent_id: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct UpdateComment {
+    pub content: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct CommentId {
+    /// Id of the post.
+    pub id: i64,
+    pub comment_id: i64,
+}
+
+#[derive(Serialize, Default)]
+pub struct CommentInfo {
+    pub id: i64,
+    pub post_id: i64,
+    pub parent_id: Option<i64>,
+    pub author_id: Option<i64>,
+    /// Empty for a deleted comment that is kept for its replies.
+    pub content: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Nesting level in the thread, 0 for top-level comments.
+    pub depth: usize,
+    pub deleted: bool,
+}
+
+impl From<Comment> for CommentInfo {
+    fn from(comment: Comment) -> Self {
+        Self {
+            id: comment.id,
+            post_id: comment.post_id,
+            parent_id: comment.parent_id,
+            author_id: comment.author_id,
+            deleted: comment.is_deleted(),
+            content: comment.content,
+            created_at: comment.created_at.to_rfc3339(),
+            updated_at: comment.updated_at.to_rfc3339(),
+            depth: 0,
+        }
+    }
+}
+
+pub struct CommentService {
+    comment_repo: Arc<CommentRepository>,
+    post_repo: Arc<PostRepository>,
+    user_repo: Arc<UserRepository>,
+    allow_unverified_posting: bool,
+    edit_window: TimeDelta,
+}
+
+impl CommentService {
+    pub fn new(
+        comment_repo: Arc<CommentRepository>,
+        post_repo: Arc<PostRepository>,
+        user_repo: Arc<UserRepository>,
+        allow_unverified_posting: bool,
+        edit_window: TimeDelta,
+    ) -> Self {
+        CommentService {
+            comment_repo,
+            post_repo,
+            user_repo,
+            allow_unverified_posting,
+            edit_window,
+        }
+    }
+
+    /// Comments of a post the viewer can see, in thread order.
+    pub async fn list_comments(
+        &self,
+        viewer: Option<AuthUser>,
+        post_id: PostId,
+    ) -> Result<Vec<CommentInfo>, AppError> {
+        let post = self.post_repo.get_post(post_id.id).await?;
+        if !can_view(viewer.as_ref(), &post) {
+            return Err(AppError::PostNotFound(post_id.id.to_string()));
+        }
+
+        let comments = self.comment_repo.get_comments(post.id).await?;
+        Ok(comment::thread(comments)
+            .into_iter()
+            .map(|(comment, depth)| CommentInfo {
+                depth,
+                ..CommentInfo::from(comment)
+            })
+            .collect())
+    }
+
+    pub async fn add_comment(
+        &self,
+        auth_user: AuthUser,
+        post_id: PostId,
+        new_comment: NewComment,
+    ) -> Result<CommentInfo, AppError> {
+        new_comment.validate()?;
+        policy::authorize(&auth_user, Action::CreateComment)?;
+        if !self.allow_unverified_posting {
+            let user = self.user_repo.get_user_by_id(auth_user.id).await?;
+            if !user.is_email_verified() {
+                return Err(AppError::Forbidden("email is not verified".to_string()));
+            }
+        }
+
+        let post = self.post_repo.get_post(post_id.id).await?;
+        if !can_view(Some(&auth_user), &post) {
+            return Err(AppError::PostNotFound(post_id.id.to_string()));
+        }
+        if let Some(parent_id) = new_comment.parent_id {
+            let parent = self.comment_repo.get_comment(parent_id).await?;
+            if parent.post_id != post.id {
+                return Err(AppError::CommentNotFound(parent_id.to_string()));
+            }
+        }
+
+        let comment_id = self.comment_repo.next_comment_id().await?;
+        let comment = Comment::create(
+            comment_id,
+            post.id,
+            new_comment.parent_id,
+            auth_user.id,
+            new_comment.content,
+        );
+        self.comment_repo.add_comment(&comment).await?;
+        self.with_depth(comment).await
+    }
+
+    async fn with_depth(&self, comment: Comment) -> Result<CommentInfo, AppError> {
+        let depth = match comment.parent_id {
+            Some(_) => {
+                let comments = self.comment_repo.get_comments(comment.post_id).await?;
+                comment::depth(&comments, &comment)
+            }
+            None => 0,
+        };
+        Ok(CommentInfo {
+            depth,
+            ..CommentInfo::from(comment)
+        })
+    }
+
+    /// Comments of other posts are not found, so ids from a wrong URL don't match.
+    async fn get_comment(&self, comment_id: &CommentId) -> Result<Comment, AppError> {
+        let comment = self.comment_repo.get_comment(comment_id.comment_id).await?;
+        if comment.post_id != comment_id.id {
+            return Err(AppError::CommentNotFound(comment_id.comment_id.to_string()));
+        }
+        Ok(comment)
+    }
+
+    pub async fn update_comment(
+        &self,
+        auth_user: AuthUser,
+        comment_id: CommentId,
+        update_comment: UpdateComment,
+    ) -> Result<CommentInfo, AppError> {
+        update_comment.validate()?;
+        let mut comment = self.get_comment(&comment_id).await?;
+        policy::authorize(
+            &auth_user,
+            Action::EditComment {
+                author_id: comment.author_id,
+            },
+        )?;
+        if !comment.is_editable(self.edit_window) {
+            return Err(AppError::Forbidden(format!(
+                "comments can be edited only within {} minutes",
+                self.edit_window.num_minutes()
+            )));
+        }
+
+        if comment.update(update_comment.content) {
+            self.comment_repo.update_comment(&comment).await?;
+        }
+        self.with_depth(comment).await
+    }
+
+    pub async fn delete_comment(
+        &self,
+        auth_user: AuthUser,
+        comment_id: CommentId,
+    ) -> Result<(), AppError> {
+        let comment = self.get_comment(&comment_id).await?;
+        let post_author_id = self.post_repo.get_post_author_id(comment.post_id).await?;
+        policy::authorize(
+            &auth_user,
+            Action::DeleteComment {
+                author_id: comment.author_id,
+                post_author_id,
+            },
+        )?;
+        self.comment_repo.delete_comment(comment.id).await
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::TimeDelta;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use std::sync::Arc;
+
+use super::blog_service::AuthUser;
+use super::policy::{self, Action};
+use crate::data::email_verification_repository::EmailVerificationRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::error::AppError;
+use crate::domain::one_time_token::OneTimeToken;
+use crate::domain::user::User;
+use crate::infrastructure::hash::hash_token;
+use crate::infrastructure::mailer::{Mail, Mailer};
+
+#[derive(Deserialize, Default)]
+pub struct VerifyEmailReq {
+    pub token: String,
+}
+
+pub struct EmailVerificationService {
+    user_repo: Arc<UserRepository>,
+    verification_repo: Arc<EmailVerificationRepository>,
+    mailer: Arc<dyn Mailer>,
+    verification_token_ttl: TimeDelta,
+    public_url: String,
+}
+
+impl EmailVerificationService {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        verification_repo: Arc<EmailVerificationRepository>,
+        mailer: Arc<dyn Mailer>,
+        verification_token_ttl: TimeDelta,
+        public_url: String,
+    ) -> Self {
+        Self {
+            user_repo,
+            verification_repo,
+            mailer,
+            verification_token_ttl,
+            public_url,
+        }
+    }
+
+    pub async fn send_verification(&self, user: &User) -> Result<(), AppError> {
+        let token_id = self.verification_repo.next_token_id().await?;
+        let (verification_token, raw_token) =
+            OneTimeToken::create(token_id, user.id, self.verification_token_ttl);
+        self.verification_repo
+            .add_verification_token(&verification_token)
+            .await?;
+
+        let link = format!(
+            "{}/api/auth/verify-email?token={raw_token}",
+            self.public_url.trim_end_matches('/')
+        );
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Confirm your email".to_string(),
+            body: format!(
+                "Hello, {}!\n\nOpen this link to confirm your email: {link}\n\
+                 The link expires in {} hours.",
+                user.username,
+                self.verification_token_ttl.num_hours()
+            ),
+        };
+        self.mailer.send(mail).await?;
+
+        info!("Verification mail sent to user: {}", user.id);
+        Ok(())
+    }
+
+    pub async fn resend_verification(&self, auth_user: AuthUser) -> Result<(), AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        let user = self.user_repo.get_user_by_id(auth_user.id).await?;
+        if user.is_email_verified() {
+            return Err(AppError::InvalidRequest("email already verified".to_string()));
+        }
+
+        self.restart_verification(&user).await
+    }
+
+    /// Invalidates the links sent earlier, they may point to a previous address.
+    pub async fn restart_verification(&self, user: &User) -> Result<(), AppError> {
+        self.verification_repo.invalidate_user_tokens(user.id).await?;
+        self.send_verification(user).await
+    }
+
+    pub async fn verify_email(&self, verify_req: VerifyEmailReq) -> Result<(), AppError> {
+        let token_hash = hash_token(&verify_req.token);
+        let stored = self
+            .verification_repo
+            .get_verification_token(&token_hash)
+            .await?;
+
+        if stored.is_used() {
+            warn!("Attempt to reuse verification token of user: {}", stored.user_id);
+            return Err(AppError::Unauthorized("verification token already used".to_string()));
+        }
+
+        if stored.is_expired() {
+            return Err(AppError::Unauthorized("verification token expired".to_string()));
+        }
+
+        if !self
+            .verification_repo
+            .verify_email(stored.id, stored.user_id)
+            .await?
+        {
+            return Err(AppError::Unauthorized("verification token already used".to_string()));
+        }
+
+        info!("Email verified for user: {}", stored.user_id);
+        Ok(())
+    }
+}
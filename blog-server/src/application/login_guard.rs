@@ -0,0 +1,98 @@
+use chrono::{TimeDelta, Utc};
+use tracing::{info, warn};
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::data::login_throttle_repository::LoginThrottleRepository;
+use crate::domain::error::AppError;
+use crate::domain::login_throttle::{LockoutPolicy, LoginThrottle};
+use crate::infrastructure::config::AccountConfig;
+
+/// Counts failed logins per username and per client address and locks both out
+/// with exponential backoff.
+pub struct LoginGuard {
+    throttle_repo: Arc<LoginThrottleRepository>,
+    user_policy: LockoutPolicy,
+    ip_policy: LockoutPolicy,
+}
+
+impl LoginGuard {
+    pub fn new(throttle_repo: Arc<LoginThrottleRepository>, config: &AccountConfig) -> Self {
+        let policy = |free_attempts| LockoutPolicy {
+            free_attempts,
+            base_lockout: TimeDelta::seconds(config.login_lockout_base_sec),
+            max_lockout: TimeDelta::seconds(config.login_lockout_max_sec),
+            attempts_window: TimeDelta::minutes(config.login_attempts_window_min),
+        };
+
+        Self {
+            throttle_repo,
+            user_policy: policy(config.login_free_attempts),
+            ip_policy: policy(config.login_ip_free_attempts),
+        }
+    }
+
+    fn keys(&self, username: &str, client_ip: Option<IpAddr>) -> Vec<(String, &LockoutPolicy)> {
+        let mut keys = vec![(LoginThrottle::user_key(username), &self.user_policy)];
+        if let Some(ip) = client_ip {
+            keys.push((LoginThrottle::ip_key(&ip), &self.ip_policy));
+        }
+        keys
+    }
+
+    pub async fn check(&self, username: &str, client_ip: Option<IpAddr>) -> Result<(), AppError> {
+        let now = Utc::now();
+        for (key, _) in self.keys(username, client_ip) {
+            let Some(throttle) = self.throttle_repo.get_throttle(&key).await? else {
+                continue;
+            };
+
+            if let Some(retry_after) = throttle.retry_after(now) {
+                info!("Login rejected for locked {key}");
+                return Err(AppError::TooManyAttempts(format!(
+                    "retry after {} seconds",
+                    retry_after.num_seconds() + 1
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn record_failure(
+        &self,
+        username: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<(), AppError> {
+        let now = Utc::now();
+        for (key, policy) in self.keys(username, client_ip) {
+            let throttle = self.throttle_repo.record_failure(&key, now, policy).await?;
+            if throttle.locked_until.is_some() {
+                warn!(
+                    "Login locked for {key} after {} failed attempts",
+                    throttle.failures
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Only the user counter is cleared: a valid login for one account must not reset
+    /// the counter of an address that is guessing others.
+    pub async fn record_success(&self, username: &str) -> Result<(), AppError> {
+        self.throttle_repo
+            .delete_throttle(&LoginThrottle::user_key(username))
+            .await
+    }
+
+    pub async fn purge_stale(&self) -> Result<(), AppError> {
+        let window = self.user_policy.attempts_window.max(self.ip_policy.attempts_window);
+        let purged = self.throttle_repo.delete_stale(Utc::now() - window).await?;
+        if purged > 0 {
+            info!("Purged {purged} stale login throttles");
+        }
+        Ok(())
+    }
+}
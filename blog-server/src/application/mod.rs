@@ -1,2 +1,13 @@
+pub mod account_service;
 pub mod auth_service;
 pub mod blog_service;
+pub mod comment_service;
+pub mod email_verification_service;
+pub mod login_guard;
+pub mod oidc_service;
+pub mod password_reset_service;
+pub mod personal_token_service;
+pub mod policy;
+pub mod session_service;
+pub mod two_factor_service;
+pub mod validation;
@@ -0,0 +1,225 @@
+use chrono::{TimeDelta, Utc};
+use serde::Deserialize;
+use tracing::info;
+
+use std::sync::Arc;
+
+use super::auth_service::{AuthService, LoginResp};
+use super::session_service::ClientInfo;
+use super::validation::Validator;
+use crate::data::linked_identity_repository::LinkedIdentityRepository;
+use crate::data::oidc_login_repository::OidcLoginRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::error::AppError;
+use crate::domain::linked_identity::LinkedIdentity;
+use crate::domain::oidc_login::OidcLogin;
+use crate::domain::user::User;
+use crate::infrastructure::hash::{generate_secret, hash_token};
+use crate::infrastructure::oidc::{
+    IdTokenClaims, OidcClient, check_state_binding, pkce_challenge,
+};
+
+const USERNAME_BASE_MAX_LEN: usize = 24;
+const USERNAME_ATTEMPTS: usize = 5;
+
+/// Query of the redirect back from the identity provider.
+#[derive(Deserialize, Default)]
+pub struct OidcCallbackReq {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Username for a new account: the provider's preferred username or the local part
+/// of the email, reduced to the characters local usernames may contain.
+fn username_base(claims: &IdTokenClaims, email: &str) -> String {
+    let source = claims.preferred_username.as_deref().unwrap_or(email);
+    let base: String = source
+        .split('@')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .skip_while(|c| !c.is_ascii_alphanumeric())
+        .take(USERNAME_BASE_MAX_LEN)
+        .collect();
+    if base.len() < 3 { "user".to_string() } else { base }
+}
+
+pub struct OidcService {
+    user_repo: Arc<UserRepository>,
+    identity_repo: Arc<LinkedIdentityRepository>,
+    login_repo: Arc<OidcLoginRepository>,
+    auth_service: Arc<AuthService>,
+    client: Option<OidcClient>,
+    login_ttl: TimeDelta,
+}
+
+impl OidcService {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        identity_repo: Arc<LinkedIdentityRepository>,
+        login_repo: Arc<OidcLoginRepository>,
+        auth_service: Arc<AuthService>,
+        client: Option<OidcClient>,
+        login_ttl: TimeDelta,
+    ) -> Self {
+        Self {
+            user_repo,
+            identity_repo,
+            login_repo,
+            auth_service,
+            client,
+            login_ttl,
+        }
+    }
+
+    fn client(&self) -> Result<&OidcClient, AppError> {
+        self.client
+            .as_ref()
+            .ok_or_else(|| AppError::InvalidRequest("OIDC login is not configured".to_string()))
+    }
+
+    pub fn login_ttl(&self) -> TimeDelta {
+        self.login_ttl
+    }
+
+    /// Returns the url of the identity provider the browser has to be sent to and the
+    /// state the browser has to keep until the callback.
+    pub async fn start_login(&self) -> Result<(String, String), AppError> {
+        let client = self.client()?;
+        let login_id = self.login_repo.next_login_id().await?;
+        let (login, raw_state) = OidcLogin::create(login_id, self.login_ttl);
+        self.login_repo.add_login(&login).await?;
+
+        let url = client
+            .authorization_url(&raw_state, &login.nonce, &pkce_challenge(&login.code_verifier))
+            .await?;
+        Ok((url, raw_state))
+    }
+
+    pub async fn finish_login(
+        &self,
+        callback: OidcCallbackReq,
+        browser_state: Option<String>,
+        client: ClientInfo,
+    ) -> Result<LoginResp, AppError> {
+        let oidc = self.client()?;
+        if let Some(error) = callback.error {
+            info!("Identity provider denied login: {error}");
+            return Err(AppError::Unauthorized(format!("identity provider error: {error}")));
+        }
+        let (Some(code), Some(state)) = (callback.code, callback.state) else {
+            return Err(AppError::InvalidRequest("code and state are required".to_string()));
+        };
+        check_state_binding(&state, browser_state.as_deref())?;
+
+        let login = self.login_repo.get_login(&hash_token(&state)).await?;
+        if login.is_used() || login.is_expired() {
+            return Err(AppError::Unauthorized("login state expired".to_string()));
+        }
+        if !self.login_repo.mark_used(login.id).await? {
+            return Err(AppError::Unauthorized("login state expired".to_string()));
+        }
+
+        let id_token = oidc.exchange_code(&code, &login.code_verifier).await?;
+        let claims = oidc.verify_id_token(&id_token, &login.nonce).await?;
+        let user = self.linked_user(oidc.issuer(), claims).await?;
+        self.auth_service.sign_in(user, client).await
+    }
+
+    async fn linked_user(&self, issuer: &str, claims: IdTokenClaims) -> Result<User, AppError> {
+        if let Some(identity) = self.identity_repo.get_identity(issuer, &claims.sub).await? {
+            self.identity_repo
+                .touch_login(identity.id, claims.email.as_deref())
+                .await?;
+            info!("User {} signed in with {issuer}", identity.user_id);
+            return self.user_repo.get_user_by_id(identity.user_id).await;
+        }
+
+        let Some(email) = claims.email.clone() else {
+            return Err(AppError::InvalidRequest(
+                "identity provider didn't share an email".to_string(),
+            ));
+        };
+        Validator::new().email("email", &email).finish()?;
+        // Taking over a local account by its email would let the provider's users
+        // sign in as anyone who registered with their address.
+        match self.user_repo.get_user_by_email(&email).await {
+            Ok(_) => {
+                return Err(AppError::AlreadyExists(format!(
+                    "{email} belongs to a local account"
+                )));
+            }
+            Err(AppError::UserNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let username = self.free_username(&username_base(&claims, &email)).await?;
+        let user_id = self.user_repo.next_user_id().await?;
+        // Nobody knows the password, the account can get one through a password reset.
+        let mut user = User::create(user_id, username, email, generate_secret(32))?;
+        if claims.email_verified {
+            user.email_verified_at = Some(Utc::now());
+        }
+
+        let identity_id = self.identity_repo.next_identity_id().await?;
+        let identity =
+            LinkedIdentity::create(identity_id, user.id, issuer.to_string(), claims.sub, claims.email);
+        self.identity_repo
+            .add_user_with_identity(&user, &identity)
+            .await?;
+
+        info!("User {} created on first login with {issuer}", user.id);
+        Ok(user)
+    }
+
+    async fn free_username(&self, base: &str) -> Result<String, AppError> {
+        let mut candidate = base.to_string();
+        for _ in 0..USERNAME_ATTEMPTS {
+            match self.user_repo.get_user(&candidate).await {
+                Err(AppError::UserNotFound(_)) => return Ok(candidate),
+                Ok(_) => candidate = format!("{base}-{}", generate_secret(2)),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(AppError::AlreadyExists(format!("username {base}")))
+    }
+
+    pub async fn purge_expired_logins(&self) -> Result<(), AppError> {
+        let purged = self.login_repo.delete_expired().await?;
+        if purged > 0 {
+            info!("Purged {purged} expired OIDC logins");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(preferred_username: Option<&str>) -> IdTokenClaims {
+        IdTokenClaims {
+            iss: "https://idp.example.com".to_string(),
+            sub: "1".to_string(),
+            nonce: None,
+            email: None,
+            email_verified: false,
+            preferred_username: preferred_username.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_username_base() {
+        assert_eq!(username_base(&claims(Some("j.doe")), "x@example.com"), "j.doe");
+        assert_eq!(username_base(&claims(Some("j.doe@corp")), "x@example.com"), "j.doe");
+        assert_eq!(username_base(&claims(None), "john.doe@example.com"), "john.doe");
+        assert_eq!(username_base(&claims(Some("_Иван Doe")), "x@example.com"), "Doe");
+        assert_eq!(username_base(&claims(Some("иван")), "x@example.com"), "user");
+        assert_eq!(
+            username_base(&claims(Some(&"a".repeat(40))), "x@example.com").len(),
+            USERNAME_BASE_MAX_LEN
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::TimeDelta;
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use std::sync::Arc;
+
+use super::session_service::SessionService;
+use super::validation::Validate;
+use crate::data::password_reset_repository::PasswordResetRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::error::AppError;
+use crate::domain::one_time_token::OneTimeToken;
+use crate::infrastructure::hash::hash_token;
+use crate::infrastructure::mailer::{Mail, Mailer};
+
+#[derive(Deserialize, Default)]
+pub struct PasswordResetReq {
+    pub email: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct PasswordResetConfirmReq {
+    pub token: String,
+    pub new_password: String,
+}
+
+pub struct PasswordResetService {
+    user_repo: Arc<UserRepository>,
+    reset_repo: Arc<PasswordResetRepository>,
+    session_service: Arc<SessionService>,
+    mailer: Arc<dyn Mailer>,
+    reset_token_ttl: TimeDelta,
+}
+
+impl PasswordResetService {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        reset_repo: Arc<PasswordResetRepository>,
+        session_service: Arc<SessionService>,
+        mailer: Arc<dyn Mailer>,
+        reset_token_ttl: TimeDelta,
+    ) -> Self {
+        Self {
+            user_repo,
+            reset_repo,
+            session_service,
+            mailer,
+            reset_token_ttl,
+        }
+    }
+
+    /// Always succeeds for unknown addresses, so the endpoint can't be used to probe accounts.
+    pub async fn request_reset(&self, reset_req: PasswordResetReq) -> Result<(), AppError> {
+        let user = match self.user_repo.get_user_by_email(&reset_req.email).await {
+            Ok(user) => user,
+            Err(AppError::UserNotFound(_)) => {
+                info!("Password reset requested for unknown email");
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let token_id = self.reset_repo.next_token_id().await?;
+        let (reset_token, raw_token) =
+            OneTimeToken::create(token_id, user.id, self.reset_token_ttl);
+        self.reset_repo.add_reset_token(&reset_token).await?;
+
+        let mail = Mail {
+            to: user.email,
+            subject: "Password reset".to_string(),
+            body: format!(
+                "Hello, {}!\n\nUse this token to set a new password: {raw_token}\n\
+                 The token expires in {} minutes. If you didn't request a reset, ignore this mail.",
+                user.username,
+                self.reset_token_ttl.num_minutes()
+            ),
+        };
+        // Sent in the background: neither a failing nor a slow mailer may tell known
+        // addresses from unknown ones.
+        let mailer = self.mailer.clone();
+        let user_id = user.id;
+        tokio::spawn(async move {
+            if let Err(e) = mailer.send(mail).await {
+                error!("Can't send password reset mail to user {user_id}: {e}");
+            }
+        });
+
+        info!("Password reset requested for user: {}", user.id);
+        Ok(())
+    }
+
+    pub async fn confirm_reset(&self, confirm_req: PasswordResetConfirmReq) -> Result<(), AppError> {
+        confirm_req.validate()?;
+        let token_hash = hash_token(&confirm_req.token);
+        let stored = self.reset_repo.get_reset_token(&token_hash).await?;
+
+        if stored.is_used() {
+            warn!("Attempt to reuse password reset token of user: {}", stored.user_id);
+            return Err(AppError::Unauthorized("reset token already used".to_string()));
+        }
+
+        if stored.is_expired() {
+            return Err(AppError::Unauthorized("reset token expired".to_string()));
+        }
+
+        let mut user = self.user_repo.get_user_by_id(stored.user_id).await?;
+        user.set_password(&confirm_req.new_password)?;
+        if !self.reset_repo.reset_password(stored.id, &user).await? {
+            return Err(AppError::Unauthorized("reset token already used".to_string()));
+        }
+
+        // A password reset ends every existing session of the account.
+        self.session_service.end_user_sessions(user.id).await?;
+
+        info!("Password reset completed for user: {}", user.id);
+        Ok(())
+    }
+}
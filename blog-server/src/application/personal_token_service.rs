@@ -0,0 +1,128 @@
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use std::sync::Arc;
+
+use super::blog_service::AuthUser;
+use super::policy::{self, Action};
+use super::validation::Validate;
+use crate::data::personal_token_repository::PersonalTokenRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::error::AppError;
+use crate::domain::personal_token::PersonalToken;
+use crate::domain::scope::Scope;
+use crate::infrastructure::hash::hash_token;
+use crate::infrastructure::jwt::Claims;
+
+#[derive(Deserialize, Default)]
+pub struct CreatePersonalTokenReq {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize, Default)]
+pub struct PersonalTokenInfo {
+    pub id: i64,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+    /// The raw token, returned only when the token is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl From<PersonalToken> for PersonalTokenInfo {
+    fn from(token: PersonalToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            scopes: token.scopes,
+            created_at: token.created_at.to_rfc3339(),
+            expires_at: token.expires_at.map(|expires_at| expires_at.to_rfc3339()),
+            last_used_at: token.last_used_at.map(|last_used_at| last_used_at.to_rfc3339()),
+            token: None,
+        }
+    }
+}
+
+pub struct PersonalTokenService {
+    user_repo: Arc<UserRepository>,
+    token_repo: Arc<PersonalTokenRepository>,
+}
+
+impl PersonalTokenService {
+    pub fn new(user_repo: Arc<UserRepository>, token_repo: Arc<PersonalTokenRepository>) -> Self {
+        Self {
+            user_repo,
+            token_repo,
+        }
+    }
+
+    pub async fn create_token(
+        &self,
+        auth_user: AuthUser,
+        create_req: CreatePersonalTokenReq,
+    ) -> Result<PersonalTokenInfo, AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        create_req.validate()?;
+        let scopes = Scope::parse_all(&create_req.scopes)?;
+
+        let token_id = self.token_repo.next_token_id().await?;
+        let (token, raw_token) = PersonalToken::create(
+            token_id,
+            auth_user.id,
+            create_req.name,
+            scopes,
+            create_req.expires_in_days.map(TimeDelta::days),
+        );
+        self.token_repo.add_token(&token).await?;
+
+        info!("User {} created personal access token {token_id}", auth_user.id);
+        Ok(PersonalTokenInfo {
+            token: Some(raw_token),
+            ..PersonalTokenInfo::from(token)
+        })
+    }
+
+    pub async fn list_tokens(&self, auth_user: AuthUser) -> Result<Vec<PersonalTokenInfo>, AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        let tokens = self.token_repo.get_user_tokens(auth_user.id).await?;
+        Ok(tokens.into_iter().map(PersonalTokenInfo::from).collect())
+    }
+
+    pub async fn revoke_token(&self, auth_user: AuthUser, token_id: i64) -> Result<(), AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        if !self.token_repo.revoke_token(auth_user.id, token_id).await? {
+            return Err(AppError::TokenNotFound(token_id.to_string()));
+        }
+
+        info!("User {} revoked personal access token {token_id}", auth_user.id);
+        Ok(())
+    }
+
+    pub async fn verify_token(&self, raw_token: &str) -> Option<Claims> {
+        let token = match self.token_repo.touch_token(&hash_token(raw_token)).await {
+            Ok(token) => token,
+            Err(AppError::Unauthorized(_)) => {
+                info!("Attempt to use unknown or expired personal access token");
+                return None;
+            }
+            Err(e) => {
+                warn!("Can't verify personal access token: {e}");
+                return None;
+            }
+        };
+
+        if !token.is_active() {
+            info!("Attempt to use expired personal access token {}", token.id);
+            return None;
+        }
+
+        let user = self.user_repo.get_user_by_id(token.user_id).await.ok()?;
+        Some(Claims::for_personal_token(&user, &token))
+    }
+}
@@ -0,0 +1,257 @@
+use tracing::{info, warn};
+
+use std::fmt::Display;
+
+use super::blog_service::AuthUser;
+use crate::domain::error::AppError;
+use crate::domain::scope::Scope;
+
+pub enum Action {
+    CreatePost,
+    EditPost { author_id: i64 },
+    DeletePost { author_id: i64 },
+    /// Reading a post that isn't published yet.
+    ViewDraft { author_id: i64 },
+    /// Reading earlier revisions of a post.
+    ViewHistory { author_id: i64 },
+    /// Listing deleted posts, which can still be restored.
+    ViewTrash { author_id: i64 },
+    CreateComment,
+    /// Only the author edits a comment; `None` once the author's account is deleted.
+    EditComment { author_id: Option<i64> },
+    /// Allowed to the comment author and to the author of the post it's under.
+    DeleteComment {
+        author_id: Option<i64>,
+        post_author_id: i64,
+    },
+    ManageRoles,
+    ManageAccount,
+    /// Handing the posts of a deleted account over to another user.
+    ReassignPosts { new_author_id: i64 },
+}
+
+impl Action {
+    /// Scope a personal access token needs for the action. Actions without a scope
+    /// are available only to password logins.
+    fn required_scope(&self) -> Option<Scope> {
+        match self {
+            Action::CreatePost | Action::EditPost { .. } | Action::DeletePost { .. } => {
+                Some(Scope::PostsWrite)
+            }
+            Action::ViewDraft { .. } | Action::ViewHistory { .. } | Action::ViewTrash { .. } => {
+                Some(Scope::PostsRead)
+            }
+            Action::CreateComment | Action::EditComment { .. } | Action::DeleteComment { .. } => {
+                Some(Scope::CommentsWrite)
+            }
+            Action::ManageRoles | Action::ManageAccount | Action::ReassignPosts { .. } => None,
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::CreatePost => write!(f, "create post"),
+            Action::EditPost { author_id } => write!(f, "edit post of author {author_id}"),
+            Action::DeletePost { author_id } => write!(f, "delete post of author {author_id}"),
+            Action::ViewDraft { author_id } => write!(f, "view draft of author {author_id}"),
+            Action::ViewHistory { author_id } => {
+                write!(f, "view post history of author {author_id}")
+            }
+            Action::ViewTrash { author_id } => write!(f, "view trash of author {author_id}"),
+            Action::CreateComment => write!(f, "create comment"),
+            Action::EditComment { author_id } => match author_id {
+                Some(author_id) => write!(f, "edit comment of author {author_id}"),
+                None => write!(f, "edit comment of deleted author"),
+            },
+            Action::DeleteComment { post_author_id, .. } => {
+                write!(f, "delete comment on post of author {post_author_id}")
+            }
+            Action::ManageRoles => write!(f, "manage roles"),
+            Action::ManageAccount => write!(f, "manage account"),
+            Action::ReassignPosts { new_author_id } => {
+                write!(f, "reassign posts to user {new_author_id}")
+            }
+        }
+    }
+}
+
+fn is_scope_granted(actor: &AuthUser, action: &Action) -> bool {
+    match &actor.scopes {
+        None => true,
+        Some(scopes) => action
+            .required_scope()
+            .is_some_and(|scope| scopes.contains(&scope)),
+    }
+}
+
+fn is_allowed(actor: &AuthUser, action: &Action) -> bool {
+    if !is_scope_granted(actor, action) {
+        return false;
+    }
+
+    match action {
+        Action::CreatePost | Action::CreateComment | Action::ManageAccount => true,
+        Action::EditPost { author_id }
+        | Action::DeletePost { author_id }
+        | Action::ViewDraft { author_id }
+        | Action::ViewHistory { author_id }
+        | Action::ViewTrash { author_id } => {
+            *author_id == actor.id || actor.role.can_moderate()
+        }
+        Action::EditComment { author_id } => *author_id == Some(actor.id),
+        Action::DeleteComment {
+            author_id,
+            post_author_id,
+        } => {
+            *author_id == Some(actor.id)
+                || *post_author_id == actor.id
+                || actor.role.can_moderate()
+        }
+        Action::ManageRoles => actor.role.can_manage_roles(),
+        Action::ReassignPosts { .. } => actor.role.can_moderate(),
+    }
+}
+
+pub fn authorize(actor: &AuthUser, action: Action) -> Result<(), AppError> {
+    if is_allowed(actor, &action) {
+        info!(
+            "Policy: user {} ({}) allowed to {action}",
+            actor.id, actor.role
+        );
+        return Ok(());
+    }
+
+    warn!(
+        "Policy: user {} ({}) denied to {action}",
+        actor.id, actor.role
+    );
+    Err(AppError::Forbidden(format!("no permission to {action}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::role::Role;
+
+    fn actor(id: i64, role: Role) -> AuthUser {
+        AuthUser {
+            id,
+            role,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_author_manages_own_posts() {
+        let user = actor(1, Role::User);
+        assert!(authorize(&user, Action::EditPost { author_id: 1 }).is_ok());
+        assert!(authorize(&user, Action::DeletePost { author_id: 1 }).is_ok());
+        assert!(authorize(&user, Action::EditPost { author_id: 2 }).is_err());
+        assert!(authorize(&user, Action::DeletePost { author_id: 2 }).is_err());
+        assert!(authorize(&user, Action::ViewDraft { author_id: 1 }).is_ok());
+        assert!(authorize(&user, Action::ViewDraft { author_id: 2 }).is_err());
+        assert!(authorize(&user, Action::ViewHistory { author_id: 1 }).is_ok());
+        assert!(authorize(&user, Action::ViewHistory { author_id: 2 }).is_err());
+        assert!(authorize(&user, Action::ViewTrash { author_id: 1 }).is_ok());
+        assert!(authorize(&user, Action::ViewTrash { author_id: 2 }).is_err());
+        assert!(authorize(&user, Action::ManageRoles).is_err());
+    }
+
+    #[test]
+    fn test_comment_rules() {
+        let user = actor(1, Role::User);
+        assert!(authorize(&user, Action::CreateComment).is_ok());
+        assert!(authorize(&user, Action::EditComment { author_id: Some(1) }).is_ok());
+        assert!(authorize(&user, Action::EditComment { author_id: Some(2) }).is_err());
+        assert!(authorize(&user, Action::EditComment { author_id: None }).is_err());
+        let own = Action::DeleteComment {
+            author_id: Some(1),
+            post_author_id: 2,
+        };
+        assert!(authorize(&user, own).is_ok());
+        let on_own_post = Action::DeleteComment {
+            author_id: Some(2),
+            post_author_id: 1,
+        };
+        assert!(authorize(&user, on_own_post).is_ok());
+        let foreign = Action::DeleteComment {
+            author_id: None,
+            post_author_id: 2,
+        };
+        assert!(authorize(&user, foreign).is_err());
+
+        let moderator = actor(1, Role::Moderator);
+        let foreign = Action::DeleteComment {
+            author_id: Some(2),
+            post_author_id: 2,
+        };
+        assert!(authorize(&moderator, foreign).is_ok());
+        assert!(authorize(&moderator, Action::EditComment { author_id: Some(2) }).is_err());
+
+        let commenter = AuthUser {
+            id: 1,
+            scopes: Some(vec![Scope::CommentsWrite]),
+            ..Default::default()
+        };
+        assert!(authorize(&commenter, Action::CreateComment).is_ok());
+        assert!(authorize(&commenter, Action::CreatePost).is_err());
+        let writer = AuthUser {
+            scopes: Some(vec![Scope::PostsWrite]),
+            ..commenter
+        };
+        assert!(authorize(&writer, Action::CreateComment).is_err());
+    }
+
+    #[test]
+    fn test_staff_manages_any_post() {
+        for role in [Role::Moderator, Role::Admin] {
+            let staff = actor(1, role);
+            assert!(authorize(&staff, Action::EditPost { author_id: 2 }).is_ok());
+            assert!(authorize(&staff, Action::DeletePost { author_id: 2 }).is_ok());
+        }
+        assert!(authorize(&actor(1, Role::Moderator), Action::ManageRoles).is_err());
+        assert!(authorize(&actor(1, Role::Admin), Action::ManageRoles).is_ok());
+
+        let reassign = || Action::ReassignPosts { new_author_id: 2 };
+        assert!(authorize(&actor(1, Role::User), reassign()).is_err());
+        assert!(authorize(&actor(1, Role::Moderator), reassign()).is_ok());
+        assert!(authorize(&actor(1, Role::Admin), reassign()).is_ok());
+    }
+
+    #[test]
+    fn test_personal_token_scopes() {
+        let writer = AuthUser {
+            id: 1,
+            scopes: Some(vec![Scope::PostsWrite]),
+            ..Default::default()
+        };
+        assert!(authorize(&writer, Action::CreatePost).is_ok());
+        assert!(authorize(&writer, Action::EditPost { author_id: 1 }).is_ok());
+        assert!(authorize(&writer, Action::EditPost { author_id: 2 }).is_err());
+        assert!(authorize(&writer, Action::ManageAccount).is_err());
+
+        let reader = AuthUser {
+            id: 1,
+            role: Role::Admin,
+            scopes: Some(vec![Scope::PostsRead]),
+            ..Default::default()
+        };
+        assert!(authorize(&reader, Action::CreatePost).is_err());
+        assert!(authorize(&reader, Action::ViewDraft { author_id: 2 }).is_ok());
+        assert!(authorize(&writer, Action::ViewDraft { author_id: 1 }).is_err());
+        assert!(authorize(&writer, Action::ViewTrash { author_id: 1 }).is_err());
+        assert!(authorize(&reader, Action::DeletePost { author_id: 1 }).is_err());
+        assert!(authorize(&reader, Action::ManageRoles).is_err());
+        assert!(authorize(&actor(1, Role::User), Action::ManageAccount).is_ok());
+    }
+
+    #[test]
+    fn test_denied_is_forbidden() {
+        let err = authorize(&actor(1, Role::User), Action::ManageRoles)
+            .err()
+            .unwrap();
+        assert!(matches!(err, AppError::Forbidden(_)));
+    }
+}
@@ -0,0 +1,229 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use tracing::{error, info};
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use super::blog_service::AuthUser;
+use super::policy::{self, Action};
+use crate::data::refresh_token_repository::RefreshTokenRepository;
+use crate::data::session_repository::SessionRepository;
+use crate::domain::error::AppError;
+use crate::domain::session::Session;
+use crate::infrastructure::revocation::RevocationCache;
+
+const USER_AGENT_MAX_LEN: usize = 512;
+/// Requests within this interval don't update `last_seen_at` again.
+const TOUCH_INTERVAL: TimeDelta = TimeDelta::minutes(1);
+
+/// Where a login comes from, recorded with the session.
+#[derive(Default, Clone)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn new(ip: Option<IpAddr>, user_agent: Option<&str>) -> Self {
+        Self {
+            ip,
+            user_agent: user_agent
+                .filter(|user_agent| !user_agent.is_empty())
+                .map(|user_agent| user_agent.chars().take(USER_AGENT_MAX_LEN).collect()),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct SessionInfo {
+    pub id: i64,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: String,
+    pub last_seen_at: String,
+    /// The session the request was made from.
+    pub current: bool,
+}
+
+impl SessionInfo {
+    fn new(session: Session, current_id: Option<i64>) -> Self {
+        Self {
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at.to_rfc3339(),
+            last_seen_at: session.last_seen_at.to_rfc3339(),
+            current: current_id == Some(session.id),
+        }
+    }
+}
+
+pub struct SessionService {
+    session_repo: Arc<SessionRepository>,
+    refresh_repo: Arc<RefreshTokenRepository>,
+    revoked: RevocationCache<i64>,
+    last_touched: Mutex<HashMap<i64, DateTime<Utc>>>,
+    access_token_ttl: TimeDelta,
+}
+
+impl SessionService {
+    pub fn new(
+        session_repo: Arc<SessionRepository>,
+        refresh_repo: Arc<RefreshTokenRepository>,
+        access_token_ttl: TimeDelta,
+    ) -> Self {
+        Self {
+            session_repo,
+            refresh_repo,
+            revoked: RevocationCache::new(),
+            last_touched: Mutex::new(HashMap::new()),
+            access_token_ttl,
+        }
+    }
+
+    /// Starts the session of a new refresh token family or continues an existing one.
+    pub async fn open_session(
+        &self,
+        user_id: i64,
+        family_id: &str,
+        client: &ClientInfo,
+    ) -> Result<i64, AppError> {
+        let ip_address = client.ip.map(|ip| ip.to_string());
+        self.session_repo
+            .open_session(
+                user_id,
+                family_id,
+                client.user_agent.as_deref(),
+                ip_address.as_deref(),
+            )
+            .await
+    }
+
+    pub fn is_revoked(&self, session_id: i64) -> bool {
+        self.revoked.is_revoked(&session_id)
+    }
+
+    /// Records that the session is in use, at most once per `TOUCH_INTERVAL`.
+    pub async fn touch(&self, session_id: i64) {
+        let current = Utc::now();
+        {
+            let Ok(mut last_touched) = self.last_touched.lock() else {
+                return;
+            };
+            if last_touched
+                .get(&session_id)
+                .is_some_and(|touched_at| current - *touched_at < TOUCH_INTERVAL)
+            {
+                return;
+            }
+            last_touched.insert(session_id, current);
+        }
+
+        if let Err(e) = self.session_repo.touch_session(session_id).await {
+            error!("Can't update session {session_id}: {e}");
+        }
+    }
+
+    /// Marks the session as revoked right away, the access tokens of the session are
+    /// rejected until they expire.
+    fn forget(&self, session_id: i64) {
+        self.revoked
+            .insert(session_id, Utc::now() + self.access_token_ttl);
+        if let Ok(mut last_touched) = self.last_touched.lock() {
+            last_touched.remove(&session_id);
+        }
+    }
+
+    pub async fn list_sessions(&self, auth_user: AuthUser) -> Result<Vec<SessionInfo>, AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        let sessions = self.session_repo.get_user_sessions(auth_user.id).await?;
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionInfo::new(session, auth_user.session_id))
+            .collect())
+    }
+
+    pub async fn revoke_session(&self, auth_user: AuthUser, session_id: i64) -> Result<(), AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        self.end_session(auth_user.id, session_id).await?;
+        info!("User {} revoked session {session_id}", auth_user.id);
+        Ok(())
+    }
+
+    pub async fn revoke_all_sessions(&self, auth_user: AuthUser) -> Result<(), AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        let revoked = self.end_user_sessions(auth_user.id).await?;
+        info!("User {} revoked {} sessions", auth_user.id, revoked);
+        Ok(())
+    }
+
+    /// Revokes every session of the user with their refresh and access tokens.
+    /// Returns the number of revoked sessions.
+    pub async fn end_user_sessions(&self, user_id: i64) -> Result<usize, AppError> {
+        let revoked = self.session_repo.revoke_user_sessions(user_id).await?;
+        self.refresh_repo.revoke_user_tokens(user_id).await?;
+        for session_id in &revoked {
+            self.forget(*session_id);
+        }
+        Ok(revoked.len())
+    }
+
+    /// Revokes the session with its refresh tokens.
+    pub async fn end_session(&self, user_id: i64, session_id: i64) -> Result<(), AppError> {
+        let Some(family_id) = self.session_repo.revoke_session(user_id, session_id).await? else {
+            return Err(AppError::SessionNotFound(session_id.to_string()));
+        };
+        self.refresh_repo.revoke_family(&family_id).await?;
+        self.forget(session_id);
+        Ok(())
+    }
+
+    /// Revokes the session a refresh token family belongs to, if there is one.
+    pub async fn end_family_session(&self, family_id: &str) -> Result<(), AppError> {
+        if let Some(session_id) = self.session_repo.revoke_family_session(family_id).await? {
+            self.forget(session_id);
+        }
+        Ok(())
+    }
+
+    /// Reloads revocations made by other server instances and drops stale sessions.
+    pub async fn sync_revocations(&self) -> Result<(), AppError> {
+        // Pruned before the database calls, so a failing database can't stop it.
+        if let Ok(mut last_touched) = self.last_touched.lock() {
+            let current = Utc::now();
+            last_touched.retain(|_, touched_at| current - *touched_at < TOUCH_INTERVAL);
+        }
+
+        let cutoff = Utc::now() - self.access_token_ttl;
+        let purged = self.session_repo.delete_stale(cutoff).await?;
+        if purged > 0 {
+            info!("Purged {purged} stale sessions");
+        }
+
+        let revoked = self.session_repo.get_revoked_since(cutoff).await?;
+        self.revoked.merge(
+            revoked
+                .into_iter()
+                .map(|(session_id, revoked_at)| (session_id, revoked_at + self.access_token_ttl))
+                .collect(),
+        );
+        self.revoked.purge_expired();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_info() {
+        let ip = "10.0.0.1".parse().ok();
+        let client = ClientInfo::new(ip, Some(&"a".repeat(1000)));
+        assert_eq!(client.ip, ip);
+        assert_eq!(client.user_agent.unwrap().len(), USER_AGENT_MAX_LEN);
+        assert!(ClientInfo::new(None, Some("")).user_agent.is_none());
+    }
+}
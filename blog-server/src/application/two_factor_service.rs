@@ -0,0 +1,244 @@
+use chrono::{TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use std::sync::Arc;
+
+use super::blog_service::AuthUser;
+use super::policy::{self, Action};
+use crate::data::login_challenge_repository::LoginChallengeRepository;
+use crate::data::recovery_code_repository::RecoveryCodeRepository;
+use crate::data::user_repository::UserRepository;
+use crate::domain::error::AppError;
+use crate::domain::one_time_token::OneTimeToken;
+use crate::domain::recovery_code::{generate_recovery_codes, hash_recovery_code, is_recovery_code};
+use crate::domain::user::User;
+use crate::infrastructure::hash::hash_token;
+use crate::infrastructure::secret_box::SecretBox;
+use crate::infrastructure::totp::{generate_totp_secret, totp_matching_step, totp_provisioning};
+
+#[derive(Deserialize, Default)]
+pub struct TotpCodeReq {
+    pub code: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct VerifyTwoFactorReq {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct TwoFactorChallenge {
+    pub challenge_token: String,
+    pub expires_in: i64,
+}
+
+pub struct TwoFactorService {
+    user_repo: Arc<UserRepository>,
+    recovery_repo: Arc<RecoveryCodeRepository>,
+    challenge_repo: Arc<LoginChallengeRepository>,
+    secret_box: Option<SecretBox>,
+    issuer: String,
+    challenge_ttl: TimeDelta,
+}
+
+impl TwoFactorService {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        recovery_repo: Arc<RecoveryCodeRepository>,
+        challenge_repo: Arc<LoginChallengeRepository>,
+        secret_box: Option<SecretBox>,
+        issuer: String,
+        challenge_ttl: TimeDelta,
+    ) -> Self {
+        Self {
+            user_repo,
+            recovery_repo,
+            challenge_repo,
+            secret_box,
+            issuer,
+            challenge_ttl,
+        }
+    }
+
+    fn secret_box(&self) -> Result<&SecretBox, AppError> {
+        self.secret_box.as_ref().ok_or_else(|| {
+            AppError::InvalidRequest("two-factor authentication is not configured".to_string())
+        })
+    }
+
+    fn totp_secret(&self, user: &User) -> Result<Vec<u8>, AppError> {
+        let Some(secret_enc) = &user.totp_secret_enc else {
+            return Err(AppError::InvalidRequest(
+                "two-factor enrolment is not started".to_string(),
+            ));
+        };
+
+        self.secret_box()?.decrypt(secret_enc).map_err(|e| {
+            error!("Can't decrypt totp secret of user {}: {e}", user.id);
+            AppError::InternalError("Can't decrypt totp secret".to_string())
+        })
+    }
+
+    async fn check_totp(&self, user: &User, code: &str) -> Result<bool, AppError> {
+        let secret = self.totp_secret(user)?;
+        let now = Utc::now().timestamp() as u64;
+        let step = match totp_matching_step(&secret, code.trim(), now) {
+            Ok(Some(step)) => step,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                error!("Can't check totp code of user {}: {e}", user.id);
+                return Err(AppError::InternalError("Can't check totp code".to_string()));
+            }
+        };
+
+        // Each code is accepted only once, even within its validity window.
+        if !self.user_repo.update_totp_last_step(user.id, step as i64).await? {
+            warn!("Replayed totp code for user {}", user.id);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Accepts either a TOTP code or an unused recovery code.
+    pub async fn verify_code(&self, user: &User, code: &str) -> Result<bool, AppError> {
+        if is_recovery_code(code) {
+            let used = self
+                .recovery_repo
+                .use_code(user.id, &hash_recovery_code(code))
+                .await?;
+            if used {
+                info!("User {} used a recovery code", user.id);
+            }
+            return Ok(used);
+        }
+        self.check_totp(user, code).await
+    }
+
+    pub async fn enroll(&self, auth_user: AuthUser) -> Result<TotpEnrollment, AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        let user = self.user_repo.get_user_by_id(auth_user.id).await?;
+        if user.is_two_factor_enabled() {
+            return Err(AppError::InvalidRequest(
+                "two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        let secret = generate_totp_secret();
+        let (secret_base32, otpauth_uri) = totp_provisioning(&secret, &self.issuer, &user.username)
+            .map_err(|e| {
+                error!("Can't build totp uri for user {}: {e}", user.id);
+                AppError::InternalError("Can't build totp uri".to_string())
+            })?;
+        let secret_enc = self.secret_box()?.encrypt(&secret).map_err(|e| {
+            error!("Can't encrypt totp secret of user {}: {e}", user.id);
+            AppError::InternalError("Can't encrypt totp secret".to_string())
+        })?;
+
+        self.user_repo.set_totp_secret(user.id, &secret_enc).await?;
+        info!("User {} started two-factor enrolment", user.id);
+        Ok(TotpEnrollment {
+            secret: secret_base32,
+            otpauth_uri,
+        })
+    }
+
+    pub async fn confirm(
+        &self,
+        auth_user: AuthUser,
+        code_req: TotpCodeReq,
+    ) -> Result<RecoveryCodes, AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        let user = self.user_repo.get_user_by_id(auth_user.id).await?;
+        if user.is_two_factor_enabled() {
+            return Err(AppError::InvalidRequest(
+                "two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        if !self.check_totp(&user, &code_req.code).await? {
+            return Err(AppError::Unauthorized("invalid two-factor code".to_string()));
+        }
+
+        let recovery_codes = generate_recovery_codes();
+        let code_hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| hash_recovery_code(code))
+            .collect();
+        self.recovery_repo.replace_codes(user.id, &code_hashes).await?;
+        self.user_repo.enable_totp(user.id).await?;
+
+        info!("User {} enabled two-factor authentication", user.id);
+        Ok(RecoveryCodes { recovery_codes })
+    }
+
+    pub async fn disable(&self, auth_user: AuthUser, code_req: TotpCodeReq) -> Result<(), AppError> {
+        policy::authorize(&auth_user, Action::ManageAccount)?;
+        let user = self.user_repo.get_user_by_id(auth_user.id).await?;
+        if !user.is_two_factor_enabled() {
+            return Err(AppError::InvalidRequest(
+                "two-factor authentication is not enabled".to_string(),
+            ));
+        }
+
+        if !self.verify_code(&user, &code_req.code).await? {
+            return Err(AppError::Unauthorized("invalid two-factor code".to_string()));
+        }
+
+        self.user_repo.disable_totp(user.id).await?;
+        self.recovery_repo.delete_user_codes(user.id).await?;
+        info!("User {} disabled two-factor authentication", user.id);
+        Ok(())
+    }
+
+    pub async fn create_challenge(&self, user: &User) -> Result<TwoFactorChallenge, AppError> {
+        let challenge_id = self.challenge_repo.next_challenge_id().await?;
+        let (challenge, raw_token) = OneTimeToken::create(challenge_id, user.id, self.challenge_ttl);
+        self.challenge_repo.add_challenge(&challenge).await?;
+
+        Ok(TwoFactorChallenge {
+            challenge_token: raw_token,
+            expires_in: self.challenge_ttl.num_seconds(),
+        })
+    }
+
+    pub async fn open_challenge(&self, raw_token: &str) -> Result<(OneTimeToken, User), AppError> {
+        let challenge = self
+            .challenge_repo
+            .get_challenge(&hash_token(raw_token))
+            .await?;
+        if challenge.is_used() || challenge.is_expired() {
+            return Err(AppError::Unauthorized("challenge token expired".to_string()));
+        }
+
+        let user = self.user_repo.get_user_by_id(challenge.user_id).await?;
+        Ok((challenge, user))
+    }
+
+    pub async fn close_challenge(&self, challenge: &OneTimeToken) -> Result<(), AppError> {
+        if !self.challenge_repo.mark_used(challenge.id).await? {
+            return Err(AppError::Unauthorized("challenge token expired".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn purge_expired_challenges(&self) -> Result<(), AppError> {
+        let purged = self.challenge_repo.delete_expired().await?;
+        if purged > 0 {
+            info!("Purged {purged} expired login challenges");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,482 @@
+use chrono::{DateTime, Utc};
+
+use super::account_service::{ChangeEmailReq, ChangePasswordReq};
+use super::auth_service::RegisterUserReq;
+use super::blog_service::{NewPost, SearchPostsReq, UpdatePost};
+use super::comment_service::{NewComment, UpdateComment};
+use super::password_reset_service::PasswordResetConfirmReq;
+use super::personal_token_service::CreatePersonalTokenReq;
+use crate::domain::error::{AppError, FieldErrors};
+use crate::domain::post_status::PostStatus;
+use crate::domain::scope::Scope;
+
+const USERNAME_MIN_LEN: usize = 3;
+const USERNAME_MAX_LEN: usize = 32;
+const EMAIL_MAX_LEN: usize = 254;
+const PASSWORD_MIN_LEN: usize = 8;
+const PASSWORD_MAX_LEN: usize = 128;
+const TITLE_MAX_LEN: usize = 200;
+const CONTENT_MAX_LEN: usize = 100_000;
+const COMMENT_MAX_LEN: usize = 5_000;
+const SEARCH_QUERY_MAX_LEN: usize = 200;
+const TAG_MAX_LEN: usize = 32;
+const TAGS_MAX_COUNT: usize = 10;
+const TOKEN_NAME_MAX_LEN: usize = 64;
+const TOKEN_MAX_TTL_DAYS: i64 = 3650;
+
+pub trait Validate {
+    fn validate(&self) -> Result<(), AppError>;
+}
+
+/// Collects errors of all fields, so that the client can show them at once.
+#[derive(Default)]
+pub struct Validator {
+    errors: FieldErrors,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn username(mut self, field: &str, value: &str) -> Self {
+        let len = value.chars().count();
+        if !(USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len) {
+            self.errors.add(
+                field,
+                format!("must be {USERNAME_MIN_LEN} to {USERNAME_MAX_LEN} characters long"),
+            );
+        } else if !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            self.errors
+                .add(field, "may contain only latin letters, digits, '_', '-' and '.'");
+        } else if !value.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            self.errors.add(field, "must start with a letter or a digit");
+        }
+        self
+    }
+
+    pub fn email(mut self, field: &str, value: &str) -> Self {
+        if value.len() > EMAIL_MAX_LEN || !is_valid_email(value) {
+            self.errors.add(field, "must be a valid email address");
+        }
+        self
+    }
+
+    pub fn password(mut self, field: &str, value: &str) -> Self {
+        let len = value.chars().count();
+        if !(PASSWORD_MIN_LEN..=PASSWORD_MAX_LEN).contains(&len) {
+            self.errors.add(
+                field,
+                format!("must be {PASSWORD_MIN_LEN} to {PASSWORD_MAX_LEN} characters long"),
+            );
+        } else if !value.chars().any(char::is_alphabetic) || !value.chars().any(|c| c.is_ascii_digit())
+        {
+            self.errors.add(field, "must contain both letters and digits");
+        }
+        self
+    }
+
+    pub fn text(mut self, field: &str, value: &str, max_len: usize) -> Self {
+        if value.trim().is_empty() {
+            self.errors.add(field, "must not be empty");
+        } else if value.chars().count() > max_len {
+            self.errors
+                .add(field, format!("must be at most {max_len} characters long"));
+        }
+        self
+    }
+
+    pub fn scopes(mut self, field: &str, values: &[String]) -> Self {
+        if values.is_empty() {
+            self.errors.add(field, "must not be empty");
+        } else if let Some(unknown) = values.iter().find(|value| value.parse::<Scope>().is_err()) {
+            self.errors.add(field, format!("unknown scope: {unknown}"));
+        }
+        self
+    }
+
+    pub fn tags(mut self, field: &str, values: &[String]) -> Self {
+        if values.len() > TAGS_MAX_COUNT {
+            self.errors
+                .add(field, format!("must hold at most {TAGS_MAX_COUNT} tags"));
+        } else if values.iter().any(|value| value.trim().is_empty()) {
+            self.errors.add(field, "must not contain empty tags");
+        } else if let Some(long) = values
+            .iter()
+            .find(|value| value.trim().chars().count() > TAG_MAX_LEN)
+        {
+            self.errors.add(
+                field,
+                format!("tag {long} is longer than {TAG_MAX_LEN} characters"),
+            );
+        } else if let Some(invalid) = values.iter().find(|value| {
+            !value
+                .trim()
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+        }) {
+            self.errors.add(
+                field,
+                format!("tag {invalid} may contain only letters, digits, '-' and '_'"),
+            );
+        }
+        self
+    }
+
+    pub fn range(mut self, field: &str, value: i64, min: i64, max: i64) -> Self {
+        if !(min..=max).contains(&value) {
+            self.errors.add(field, format!("must be between {min} and {max}"));
+        }
+        self
+    }
+
+    /// Publication time may be given only for a scheduled post, and it has to be ahead.
+    pub fn publish_at(
+        mut self,
+        field: &str,
+        status: Option<PostStatus>,
+        value: Option<DateTime<Utc>>,
+    ) -> Self {
+        match (status, value) {
+            (Some(PostStatus::Scheduled), None) => {
+                self.errors.add(field, "is required for a scheduled post");
+            }
+            (Some(PostStatus::Scheduled), Some(value)) if value <= Utc::now() => {
+                self.errors.add(field, "must be in the future");
+            }
+            (Some(PostStatus::Scheduled), Some(_)) | (_, None) => {}
+            (_, Some(_)) => self.errors.add(field, "is allowed only for a scheduled post"),
+        }
+        self
+    }
+
+    pub fn finish(self) -> Result<(), AppError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.errors))
+        }
+    }
+}
+
+fn is_valid_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+
+    let valid_chars = |part: &str| {
+        part.chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && c != '@')
+    };
+    let labels_ok = domain.split('.').all(|label| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+    });
+
+    !local.is_empty()
+        && valid_chars(local)
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && domain.contains('.')
+        && labels_ok
+}
+
+impl Validate for RegisterUserReq {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .username("username", &self.username)
+            .email("email", &self.email)
+            .password("password", &self.password)
+            .finish()
+    }
+}
+
+impl Validate for NewPost {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .text("title", &self.title, TITLE_MAX_LEN)
+            .text("content", &self.content, CONTENT_MAX_LEN)
+            .publish_at("publish_at", self.status, self.publish_at)
+            .tags("tags", &self.tags)
+            .finish()
+    }
+}
+
+impl Validate for UpdatePost {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        if let Some(title) = &self.title {
+            validator = validator.text("title", title, TITLE_MAX_LEN);
+        }
+        if let Some(content) = &self.content {
+            validator = validator.text("content", content, CONTENT_MAX_LEN);
+        }
+        if let Some(tags) = &self.tags {
+            validator = validator.tags("tags", tags);
+        }
+        validator
+            .publish_at("publish_at", self.status, self.publish_at)
+            .finish()
+    }
+}
+
+impl Validate for NewComment {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .text("content", &self.content, COMMENT_MAX_LEN)
+            .finish()
+    }
+}
+
+impl Validate for UpdateComment {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .text("content", &self.content, COMMENT_MAX_LEN)
+            .finish()
+    }
+}
+
+impl Validate for SearchPostsReq {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .text("q", &self.q, SEARCH_QUERY_MAX_LEN)
+            .finish()
+    }
+}
+
+impl Validate for PasswordResetConfirmReq {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .password("new_password", &self.new_password)
+            .finish()
+    }
+}
+
+impl Validate for ChangePasswordReq {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .password("new_password", &self.new_password)
+            .finish()
+    }
+}
+
+impl Validate for ChangeEmailReq {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new().email("email", &self.email).finish()
+    }
+}
+
+impl Validate for CreatePersonalTokenReq {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new()
+            .text("name", &self.name, TOKEN_NAME_MAX_LEN)
+            .scopes("scopes", &self.scopes);
+        if let Some(expires_in_days) = self.expires_in_days {
+            validator = validator.range("expires_in_days", expires_in_days, 1, TOKEN_MAX_TTL_DAYS);
+        }
+        validator.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(result: Result<(), AppError>) -> Vec<String> {
+        match result {
+            Ok(()) => vec![],
+            Err(AppError::Validation(errors)) => {
+                errors.0.into_iter().map(|error| error.field).collect()
+            }
+            Err(e) => panic!("Wrong type error: {e}"),
+        }
+    }
+
+    fn register_req(username: &str, email: &str, password: &str) -> RegisterUserReq {
+        RegisterUserReq {
+            username: username.to_string(),
+            email: email.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_register_valid() {
+        let req = register_req("alice_01", "alice@example.com", "secret123");
+        assert!(req.validate().is_ok());
+    }
+
+    #[test]
+    fn test_register_collects_all_fields() {
+        let req = register_req("", "not-an-email", "1");
+        assert_eq!(fields(req.validate()), ["username", "email", "password"]);
+    }
+
+    #[test]
+    fn test_username_rules() {
+        for username in ["ab", "alice bob", "алиса", "_alice", &"a".repeat(33)] {
+            let req = register_req(username, "alice@example.com", "secret123");
+            assert_eq!(fields(req.validate()), ["username"], "{username}");
+        }
+    }
+
+    #[test]
+    fn test_email_rules() {
+        for email in ["alice@example.com", "a.b+tag@mail.example.org"] {
+            assert!(is_valid_email(email), "{email}");
+        }
+        for email in ["", "alice", "@example.com", "alice@", "alice@example", "a b@example.com",
+            "alice@-example.com", "alice@example..com", "a@b@example.com"]
+        {
+            assert!(!is_valid_email(email), "{email}");
+        }
+    }
+
+    #[test]
+    fn test_password_rules() {
+        for password in ["short1", "onlyletters", "1234567890", &"a1".repeat(65)] {
+            let req = register_req("alice", "alice@example.com", password);
+            assert_eq!(fields(req.validate()), ["password"], "{password}");
+        }
+    }
+
+    #[test]
+    fn test_post_rules() {
+        let new_post = NewPost {
+            title: "  ".to_string(),
+            content: "c".repeat(CONTENT_MAX_LEN + 1),
+            ..Default::default()
+        };
+        assert_eq!(fields(new_post.validate()), ["title", "content"]);
+
+        let update_post = UpdatePost {
+            title: Some("t".repeat(TITLE_MAX_LEN + 1)),
+            ..Default::default()
+        };
+        assert_eq!(fields(update_post.validate()), ["title"]);
+        assert!(UpdatePost::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_tag_rules() {
+        let tagged = |tags: &[&str]| NewPost {
+            title: "title".to_string(),
+            content: "content".to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(tagged(&["rust", " Web-dev ", "веб_2"]).validate().is_ok());
+        assert!(tagged(&[]).validate().is_ok());
+        let long = "t".repeat(TAG_MAX_LEN + 1);
+        for tags in [
+            vec!["a"; TAGS_MAX_COUNT + 1],
+            vec!["rust", " "],
+            vec![long.as_str()],
+            vec!["two words"],
+            vec!["c++"],
+        ] {
+            assert_eq!(fields(tagged(&tags).validate()), ["tags"], "{tags:?}");
+        }
+
+        let update_post = UpdatePost {
+            tags: Some(vec!["#rust".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(fields(update_post.validate()), ["tags"]);
+    }
+
+    #[test]
+    fn test_comment_rules() {
+        let comment = |content: &str| NewComment {
+            content: content.to_string(),
+            parent_id: Some(1),
+        };
+        assert!(comment("Nice post").validate().is_ok());
+        assert_eq!(fields(comment("\n ").validate()), ["content"]);
+        let edit = UpdateComment {
+            content: "c".repeat(COMMENT_MAX_LEN + 1),
+        };
+        assert_eq!(fields(edit.validate()), ["content"]);
+    }
+
+    #[test]
+    fn test_search_rules() {
+        let search = |q: &str| SearchPostsReq {
+            q: q.to_string(),
+            ..Default::default()
+        };
+        assert!(search("rust -java").validate().is_ok());
+        assert_eq!(fields(search(" ").validate()), ["q"]);
+        assert_eq!(
+            fields(search(&"q".repeat(SEARCH_QUERY_MAX_LEN + 1)).validate()),
+            ["q"]
+        );
+    }
+
+    #[test]
+    fn test_publish_at_rules() {
+        let scheduled = |publish_at| UpdatePost {
+            status: Some(PostStatus::Scheduled),
+            publish_at,
+            ..Default::default()
+        };
+        let ahead = Utc::now() + chrono::TimeDelta::hours(1);
+        assert!(scheduled(Some(ahead)).validate().is_ok());
+        assert_eq!(fields(scheduled(None).validate()), ["publish_at"]);
+        assert_eq!(
+            fields(scheduled(Some(Utc::now() - chrono::TimeDelta::hours(1))).validate()),
+            ["publish_at"]
+        );
+
+        let draft = UpdatePost {
+            status: Some(PostStatus::Draft),
+            publish_at: Some(ahead),
+            ..Default::default()
+        };
+        assert_eq!(fields(draft.validate()), ["publish_at"]);
+        let no_status = UpdatePost {
+            publish_at: Some(ahead),
+            ..Default::default()
+        };
+        assert_eq!(fields(no_status.validate()), ["publish_at"]);
+    }
+
+    #[test]
+    fn test_account_rules() {
+        let req = ChangePasswordReq {
+            current_password: String::new(),
+            new_password: "secret123".to_string(),
+        };
+        assert!(req.validate().is_ok());
+        assert_eq!(fields(ChangePasswordReq::default().validate()), ["new_password"]);
+
+        let req = ChangeEmailReq {
+            email: "alice@".to_string(),
+            password: "secret123".to_string(),
+        };
+        assert_eq!(fields(req.validate()), ["email"]);
+    }
+
+    #[test]
+    fn test_personal_token_rules() {
+        let req = CreatePersonalTokenReq {
+            name: "ci".to_string(),
+            scopes: vec!["posts:write".to_string()],
+            expires_in_days: Some(90),
+        };
+        assert!(req.validate().is_ok());
+
+        let req = CreatePersonalTokenReq {
+            name: String::new(),
+            scopes: vec!["posts:write".to_string(), "admin".to_string()],
+            expires_in_days: Some(0),
+        };
+        assert_eq!(fields(req.validate()), ["name", "scopes", "expires_in_days"]);
+        assert_eq!(fields(CreatePersonalTokenReq::default().validate()), ["name", "scopes"]);
+    }
+}
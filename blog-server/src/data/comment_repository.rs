@@ -0,0 +1,161 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::comment::Comment;
+use crate::domain::error::AppError;
+
+pub struct CommentRepository {
+    pool: PgPool,
+}
+
+impl CommentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn next_comment_id(&self) -> Result<i64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT NEXTVAL('comments_id_seq')
+            "#
+        };
+
+        let next_comment_id = match query.fetch_one(&self.pool).await {
+            Ok(row) => {
+                if let Some(val) = row.nextval {
+                    val
+                } else {
+                    info!("Can't generate comment id");
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(next_comment_id)
+    }
+
+    pub async fn add_comment(&self, comment: &Comment) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO comments (id, post_id, parent_id, author_id, content, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            comment.id,
+            comment.post_id,
+            comment.parent_id,
+            comment.author_id,
+            comment.content,
+            comment.created_at,
+            comment.updated_at
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    /// Deleted comments are not found.
+    pub async fn get_comment(&self, comment_id: i64) -> Result<Comment, AppError> {
+        let query = sqlx::query_as! {
+            Comment,
+            r#"
+             SELECT id, post_id, parent_id, author_id, content, created_at, updated_at, deleted_at
+             FROM comments
+             WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            comment_id
+        };
+
+        let comment = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::CommentNotFound(comment_id.to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(comment)
+    }
+
+    /// All comments of the post, deleted ones included, see [`crate::domain::comment::thread`].
+    pub async fn get_comments(&self, post_id: i64) -> Result<Vec<Comment>, AppError> {
+        let query = sqlx::query_as! {
+            Comment,
+            r#"
+             SELECT id, post_id, parent_id, author_id, content, created_at, updated_at, deleted_at
+             FROM comments
+             WHERE post_id = $1
+             ORDER BY created_at, id
+            "#,
+            post_id
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(comments) => Ok(comments),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn update_comment(&self, comment: &Comment) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE comments
+             SET content = $1, updated_at = $2
+             WHERE id = $3 AND deleted_at IS NULL
+            "#,
+            comment.content,
+            comment.updated_at,
+            comment.id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                Err(AppError::CommentNotFound(comment.id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// The comment stays as a placeholder for its replies, its content is erased.
+    pub async fn delete_comment(&self, comment_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE comments
+             SET content = '', deleted_at = $2
+             WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            comment_id,
+            Utc::now()
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                Err(AppError::CommentNotFound(comment_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::linked_identity::LinkedIdentity;
+use crate::domain::user::User;
+
+pub struct LinkedIdentityRepository {
+    pool: PgPool,
+}
+
+impl LinkedIdentityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn next_identity_id(&self) -> Result<i64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT NEXTVAL('linked_identities_id_seq')
+            "#
+        };
+
+        let next_identity_id = match query.fetch_one(&self.pool).await {
+            Ok(row) => {
+                if let Some(val) = row.nextval {
+                    val
+                } else {
+                    info!("Can't generate linked identity id");
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(next_identity_id)
+    }
+
+    pub async fn get_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<LinkedIdentity>, AppError> {
+        let query = sqlx::query_as! {
+            LinkedIdentity,
+            r#"
+             SELECT * FROM linked_identities
+             WHERE issuer = $1 AND subject = $2
+            "#,
+            issuer,
+            subject
+        };
+
+        match query.fetch_optional(&self.pool).await {
+            Ok(identity) => Ok(identity),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Creates the account and its identity at once, so a failed first login doesn't
+    /// leave a user nobody can sign in as.
+    pub async fn add_user_with_identity(
+        &self,
+        user: &User,
+        identity: &LinkedIdentity,
+    ) -> Result<(), AppError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        let add_user = sqlx::query! {
+            r#"
+             INSERT INTO users (id, username, email, password_hash, created_at, role, email_verified_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            user.id,
+            user.username,
+            user.email,
+            user.password_hash,
+            user.created_at,
+            user.role.as_str(),
+            user.email_verified_at
+        };
+
+        if let Err(e) = add_user.execute(&mut *tx).await {
+            info!("{e}");
+            let Some(e) = e.into_database_error() else {
+                return Err(AppError::InternalError("DB error".to_string()));
+            };
+
+            if let sqlx::error::ErrorKind::UniqueViolation = e.kind() {
+                return Err(AppError::AlreadyExists(format!("{user}")));
+            } else {
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        let add_identity = sqlx::query! {
+            r#"
+             INSERT INTO linked_identities (id, user_id, issuer, subject, email, created_at, last_login_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            identity.id,
+            identity.user_id,
+            identity.issuer,
+            identity.subject,
+            identity.email,
+            identity.created_at,
+            identity.last_login_at
+        };
+
+        if let Err(e) = add_identity.execute(&mut *tx).await {
+            info!("{e}");
+            let Some(e) = e.into_database_error() else {
+                return Err(AppError::InternalError("DB error".to_string()));
+            };
+
+            if let sqlx::error::ErrorKind::UniqueViolation = e.kind() {
+                return Err(AppError::AlreadyExists(format!(
+                    "identity {} of {}",
+                    identity.subject, identity.issuer
+                )));
+            } else {
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        if let Err(e) = tx.commit().await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn touch_login(&self, identity_id: i64, email: Option<&str>) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE linked_identities
+             SET last_login_at = $1, email = $2
+             WHERE id = $3
+            "#,
+            Utc::now(),
+            email,
+            identity_id
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+}
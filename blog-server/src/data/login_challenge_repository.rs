@@ -0,0 +1,126 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::one_time_token::OneTimeToken;
+
+pub struct LoginChallengeRepository {
+    pool: PgPool,
+}
+
+impl LoginChallengeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn next_challenge_id(&self) -> Result<i64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT NEXTVAL('login_challenges_id_seq')
+            "#
+        };
+
+        let next_challenge_id = match query.fetch_one(&self.pool).await {
+            Ok(row) => {
+                if let Some(val) = row.nextval {
+                    val
+                } else {
+                    info!("Can't generate login challenge id");
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(next_challenge_id)
+    }
+
+    pub async fn add_challenge(&self, token: &OneTimeToken) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO login_challenges (id, user_id, token_hash, expires_at, created_at)
+             VALUES ($1, $2, $3, $4, $5)
+            "#,
+            token.id,
+            token.user_id,
+            token.token_hash,
+            token.expires_at,
+            token.created_at
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_challenge(&self, token_hash: &str) -> Result<OneTimeToken, AppError> {
+        let query = sqlx::query_as! {
+            OneTimeToken,
+            r#"
+             SELECT * FROM login_challenges
+             WHERE token_hash = $1
+            "#,
+            token_hash
+        };
+
+        let token = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::Unauthorized("invalid challenge token".to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(token)
+    }
+
+    /// Returns `false` if the challenge was already used by a concurrent request.
+    pub async fn mark_used(&self, token_id: i64) -> Result<bool, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE login_challenges
+             SET used_at = $1
+             WHERE id = $2 AND used_at IS NULL
+            "#,
+            Utc::now(),
+            token_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected() == 1),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn delete_expired(&self) -> Result<u64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM login_challenges
+             WHERE expires_at <= $1
+            "#,
+            Utc::now()
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
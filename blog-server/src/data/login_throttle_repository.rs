@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::login_throttle::{LockoutPolicy, LoginThrottle};
+
+pub struct LoginThrottleRepository {
+    pool: PgPool,
+}
+
+impl LoginThrottleRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_throttle(&self, key: &str) -> Result<Option<LoginThrottle>, AppError> {
+        let query = sqlx::query_as! {
+            LoginThrottle,
+            r#"
+             SELECT * FROM login_throttles
+             WHERE key = $1
+            "#,
+            key
+        };
+
+        match query.fetch_optional(&self.pool).await {
+            Ok(throttle) => Ok(throttle),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Registers a failure under a row lock, so concurrent failures for the same key are
+    /// all counted.
+    pub async fn record_failure(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        policy: &LockoutPolicy,
+    ) -> Result<LoginThrottle, AppError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        let new = LoginThrottle::new(key.to_string());
+        let insert = sqlx::query! {
+            r#"
+             INSERT INTO login_throttles (key, failures, last_failure_at)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (key) DO NOTHING
+            "#,
+            new.key,
+            new.failures,
+            new.last_failure_at
+        };
+
+        if let Err(e) = insert.execute(&mut *tx).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        let select = sqlx::query_as! {
+            LoginThrottle,
+            r#"
+             SELECT * FROM login_throttles
+             WHERE key = $1
+             FOR UPDATE
+            "#,
+            key
+        };
+
+        let mut throttle = match select.fetch_one(&mut *tx).await {
+            Ok(throttle) => throttle,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+        throttle.register_failure(now, policy);
+
+        let update = sqlx::query! {
+            r#"
+             UPDATE login_throttles
+             SET failures = $2, last_failure_at = $3, locked_until = $4
+             WHERE key = $1
+            "#,
+            throttle.key,
+            throttle.failures,
+            throttle.last_failure_at,
+            throttle.locked_until
+        };
+
+        if let Err(e) = update.execute(&mut *tx).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        if let Err(e) = tx.commit().await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(throttle)
+    }
+
+    pub async fn delete_throttle(&self, key: &str) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM login_throttles
+             WHERE key = $1
+            "#,
+            key
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn delete_stale(&self, failed_before: DateTime<Utc>) -> Result<u64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM login_throttles
+             WHERE last_failure_at < $1
+               AND (locked_until IS NULL OR locked_until < $2)
+            "#,
+            failed_before,
+            Utc::now()
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_concurrent_failures_are_all_counted(pool: PgPool) {
+        const FAILURES: i32 = 20;
+        let repo = Arc::new(LoginThrottleRepository::new(pool));
+
+        let tasks: Vec<_> = (0..FAILURES)
+            .map(|_| {
+                let repo = repo.clone();
+                tokio::spawn(async move {
+                    let policy = LockoutPolicy {
+                        free_attempts: 5,
+                        base_lockout: TimeDelta::seconds(10),
+                        max_lockout: TimeDelta::minutes(15),
+                        attempts_window: TimeDelta::minutes(15),
+                    };
+                    repo.record_failure("user:alice", Utc::now(), &policy).await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let throttle = repo.get_throttle("user:alice").await.unwrap().unwrap();
+        assert_eq!(throttle.failures, FAILURES);
+        assert!(throttle.locked_until.is_some());
+    }
+}
@@ -1,2 +1,16 @@
+pub mod comment_repository;
+pub mod email_verification_repository;
+pub mod linked_identity_repository;
+pub mod login_challenge_repository;
+pub mod login_throttle_repository;
+pub mod oidc_login_repository;
+pub mod password_reset_repository;
+pub mod personal_token_repository;
 pub mod post_repository;
+pub mod post_revision_repository;
+pub mod recovery_code_repository;
+pub mod refresh_token_repository;
+pub mod revoked_token_repository;
+pub mod session_repository;
+pub mod tag_repository;
 pub mod user_repository;
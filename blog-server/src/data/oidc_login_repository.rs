@@ -0,0 +1,127 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::oidc_login::OidcLogin;
+
+pub struct OidcLoginRepository {
+    pool: PgPool,
+}
+
+impl OidcLoginRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn next_login_id(&self) -> Result<i64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT NEXTVAL('oidc_logins_id_seq')
+            "#
+        };
+
+        let next_login_id = match query.fetch_one(&self.pool).await {
+            Ok(row) => {
+                if let Some(val) = row.nextval {
+                    val
+                } else {
+                    info!("Can't generate oidc login id");
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(next_login_id)
+    }
+
+    pub async fn add_login(&self, login: &OidcLogin) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO oidc_logins (id, state_hash, nonce, code_verifier, expires_at, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            login.id,
+            login.state_hash,
+            login.nonce,
+            login.code_verifier,
+            login.expires_at,
+            login.created_at
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_login(&self, state_hash: &str) -> Result<OidcLogin, AppError> {
+        let query = sqlx::query_as! {
+            OidcLogin,
+            r#"
+             SELECT * FROM oidc_logins
+             WHERE state_hash = $1
+            "#,
+            state_hash
+        };
+
+        let login = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::Unauthorized("invalid login state".to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(login)
+    }
+
+    /// Returns `false` if the login was already used by a concurrent request.
+    pub async fn mark_used(&self, login_id: i64) -> Result<bool, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE oidc_logins
+             SET used_at = $1
+             WHERE id = $2 AND used_at IS NULL
+            "#,
+            Utc::now(),
+            login_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected() == 1),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn delete_expired(&self) -> Result<u64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM oidc_logins
+             WHERE expires_at <= $1
+            "#,
+            Utc::now()
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::one_time_token::OneTimeToken;
+use crate::domain::user::User;
+
+pub struct PasswordResetRepository {
+    pool: PgPool,
+}
+
+impl PasswordResetRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn next_token_id(&self) -> Result<i64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT NEXTVAL('password_reset_tokens_id_seq')
+            "#
+        };
+
+        let next_token_id = match query.fetch_one(&self.pool).await {
+            Ok(row) => {
+                if let Some(val) = row.nextval {
+                    val
+                } else {
+                    info!("Can't generate password reset token id");
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(next_token_id)
+    }
+
+    pub async fn add_reset_token(&self, token: &OneTimeToken) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at, created_at)
+             VALUES ($1, $2, $3, $4, $5)
+            "#,
+            token.id,
+            token.user_id,
+            token.token_hash,
+            token.expires_at,
+            token.created_at
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_reset_token(&self, token_hash: &str) -> Result<OneTimeToken, AppError> {
+        let query = sqlx::query_as! {
+            OneTimeToken,
+            r#"
+             SELECT * FROM password_reset_tokens
+             WHERE token_hash = $1
+            "#,
+            token_hash
+        };
+
+        let token = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::Unauthorized("invalid reset token".to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(token)
+    }
+
+    /// Uses the token to set the new password hash of `user` and invalidates the other
+    /// reset tokens of the user, all or nothing. Returns `false` if the token was
+    /// already used by a concurrent request.
+    pub async fn reset_password(&self, token_id: i64, user: &User) -> Result<bool, AppError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+        let current = Utc::now();
+
+        let mark_used = sqlx::query! {
+            r#"
+             UPDATE password_reset_tokens
+             SET used_at = $1
+             WHERE id = $2 AND used_at IS NULL
+            "#,
+            current,
+            token_id
+        };
+
+        match mark_used.execute(&mut *tx).await {
+            Ok(res) if res.rows_affected() == 0 => return Ok(false),
+            Ok(_) => {}
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        let update_hash = sqlx::query! {
+            r#"
+             UPDATE users
+             SET password_hash = $1
+             WHERE id = $2
+            "#,
+            user.password_hash,
+            user.id
+        };
+
+        match update_hash.execute(&mut *tx).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                return Err(AppError::UserNotFound(user.id.to_string()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        let invalidate = sqlx::query! {
+            r#"
+             UPDATE password_reset_tokens
+             SET used_at = $1
+             WHERE user_id = $2 AND used_at IS NULL
+            "#,
+            current,
+            user.id
+        };
+
+        if let Err(e) = invalidate.execute(&mut *tx).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        if let Err(e) = tx.commit().await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(true)
+    }
+
+    /// Invalidates every outstanding reset token of the user.
+    pub async fn invalidate_user_tokens(&self, user_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE password_reset_tokens
+             SET used_at = $1
+             WHERE user_id = $2 AND used_at IS NULL
+            "#,
+            Utc::now(),
+            user_id
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+}
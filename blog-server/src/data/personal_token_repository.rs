@@ -0,0 +1,139 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::personal_token::PersonalToken;
+use crate::domain::scope::Scope;
+
+pub struct PersonalTokenRepository {
+    pool: PgPool,
+}
+
+impl PersonalTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn next_token_id(&self) -> Result<i64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT NEXTVAL('personal_access_tokens_id_seq')
+            "#
+        };
+
+        let next_token_id = match query.fetch_one(&self.pool).await {
+            Ok(row) => {
+                if let Some(val) = row.nextval {
+                    val
+                } else {
+                    info!("Can't generate personal access token id");
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(next_token_id)
+    }
+
+    pub async fn add_token(&self, token: &PersonalToken) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO personal_access_tokens (id, user_id, name, token_hash, scopes, created_at, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            token.id,
+            token.user_id,
+            token.name,
+            token.token_hash,
+            &token.scopes as &[Scope],
+            token.created_at,
+            token.expires_at
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_user_tokens(&self, user_id: i64) -> Result<Vec<PersonalToken>, AppError> {
+        let query = sqlx::query_as! {
+            PersonalToken,
+            r#"
+             SELECT id, user_id, name, token_hash, scopes as "scopes: Vec<Scope>", created_at,
+                    expires_at, last_used_at, revoked_at
+             FROM personal_access_tokens
+             WHERE user_id = $1 AND revoked_at IS NULL
+             ORDER BY id
+            "#,
+            user_id
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(tokens) => Ok(tokens),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Looks the token up by its hash and records the time of use. Revoked and expired
+    /// tokens are not found.
+    pub async fn touch_token(&self, token_hash: &str) -> Result<PersonalToken, AppError> {
+        let query = sqlx::query_as! {
+            PersonalToken,
+            r#"
+             UPDATE personal_access_tokens
+             SET last_used_at = $1
+             WHERE token_hash = $2 AND revoked_at IS NULL
+               AND (expires_at IS NULL OR expires_at > $1)
+             RETURNING id, user_id, name, token_hash, scopes as "scopes: Vec<Scope>", created_at,
+                       expires_at, last_used_at, revoked_at
+            "#,
+            Utc::now(),
+            token_hash
+        };
+
+        match query.fetch_one(&self.pool).await {
+            Ok(token) => Ok(token),
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    Err(AppError::Unauthorized("invalid personal access token".to_string()))
+                } else {
+                    Err(AppError::InternalError("DB error".to_string()))
+                }
+            }
+        }
+    }
+
+    /// Returns `false` if the user has no active token with this id.
+    pub async fn revoke_token(&self, user_id: i64, token_id: i64) -> Result<bool, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE personal_access_tokens
+             SET revoked_at = $1
+             WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL
+            "#,
+            Utc::now(),
+            token_id,
+            user_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected() == 1),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
@@ -1,13 +1,153 @@
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
 use tracing::info;
 
+use crate::domain::content_format::ContentFormat;
 use crate::domain::error::AppError;
 use crate::domain::post::Post;
+use crate::domain::post_cursor::{Direction, PostCursor};
+use crate::domain::post_search::{MATCH_END, MATCH_START, SearchHit};
+use crate::domain::post_status::PostStatus;
+use crate::domain::slug;
 
+/// Postgres error code of a missing text search configuration.
+const UNDEFINED_OBJECT: &str = "42704";
+
+/// Posts are read from the `posts_with_tags` view. sqlx takes every column of a view
+/// for nullable, so the queries mark the NOT NULL ones with `!`.
 pub struct PostRepository {
     pool: PgPool,
 }
 
+/// Saves the current title and content of the post as its next revision.
+async fn add_revision(conn: &mut PgConnection, post: &Post, editor_id: i64) -> Result<(), AppError> {
+    let query = sqlx::query! {
+        r#"
+         INSERT INTO post_revisions (post_id, revision, title, content, editor_id, created_at)
+         SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, $3, $4, $5
+         FROM post_revisions
+         WHERE post_id = $1
+        "#,
+        post.id,
+        post.title,
+        post.content,
+        editor_id,
+        post.updated_at
+    };
+
+    if let Err(e) = query.execute(conn).await {
+        info!("{e}");
+        return Err(AppError::InternalError("DB error".to_string()));
+    };
+
+    Ok(())
+}
+
+/// Replaces the tags of the post, creating the ones that don't exist yet.
+async fn save_tags(conn: &mut PgConnection, post: &Post) -> Result<(), AppError> {
+    let add_tags = sqlx::query! {
+        r#"
+         INSERT INTO tags (name)
+         SELECT UNNEST($1::text[])
+         ON CONFLICT (name) DO NOTHING
+        "#,
+        &post.tags
+    };
+
+    if let Err(e) = add_tags.execute(&mut *conn).await {
+        info!("{e}");
+        return Err(AppError::InternalError("DB error".to_string()));
+    };
+
+    let unlink_tags = sqlx::query! {
+        r#"
+         DELETE FROM post_tags
+         WHERE post_id = $1
+        "#,
+        post.id
+    };
+
+    if let Err(e) = unlink_tags.execute(&mut *conn).await {
+        info!("{e}");
+        return Err(AppError::InternalError("DB error".to_string()));
+    };
+
+    let link_tags = sqlx::query! {
+        r#"
+         INSERT INTO post_tags (post_id, tag_id)
+         SELECT $1, id FROM tags
+         WHERE name = ANY($2)
+        "#,
+        post.id,
+        &post.tags
+    };
+
+    if let Err(e) = link_tags.execute(conn).await {
+        info!("{e}");
+        return Err(AppError::InternalError("DB error".to_string()));
+    };
+
+    Ok(())
+}
+
+/// Makes the slug of the post unique among the current and the previous slugs of
+/// other posts. The replaced slug is kept as a redirect to the post.
+async fn assign_slug(conn: &mut PgConnection, post: &mut Post) -> Result<(), AppError> {
+    let taken = sqlx::query! {
+        r#"
+         SELECT slug as "slug!" FROM posts
+         WHERE id <> $2 AND (slug = $1 OR slug LIKE $1 || '-%')
+         UNION ALL
+         SELECT slug FROM post_slugs
+         WHERE post_id <> $2 AND (slug = $1 OR slug LIKE $1 || '-%')
+        "#,
+        post.slug,
+        post.id
+    };
+
+    let taken: Vec<String> = match taken.fetch_all(&mut *conn).await {
+        Ok(rows) => rows.into_iter().map(|row| row.slug).collect(),
+        Err(e) => {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        }
+    };
+    post.slug = slug::first_free(&post.slug, &taken);
+
+    let keep_old = sqlx::query! {
+        r#"
+         INSERT INTO post_slugs (slug, post_id)
+         SELECT slug, id FROM posts
+         WHERE id = $1 AND slug <> $2
+         ON CONFLICT (slug) DO NOTHING
+        "#,
+        post.id,
+        post.slug
+    };
+
+    if let Err(e) = keep_old.execute(&mut *conn).await {
+        info!("{e}");
+        return Err(AppError::InternalError("DB error".to_string()));
+    };
+
+    // A post renamed back gets its old slug back.
+    let reclaim = sqlx::query! {
+        r#"
+         DELETE FROM post_slugs
+         WHERE slug = $2 AND post_id = $1
+        "#,
+        post.id,
+        post.slug
+    };
+
+    if let Err(e) = reclaim.execute(conn).await {
+        info!("{e}");
+        return Err(AppError::InternalError("DB error".to_string()));
+    };
+
+    Ok(())
+}
+
 impl PostRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
@@ -25,45 +165,76 @@ impl PostRepository {
                     val
                 } else {
                     info!("Can't generate post id");
-                    return Err(AppError::InternalError(format!("DB error")));
+                    return Err(AppError::InternalError("DB error".to_string()));
                 }
             }
             Err(e) => {
                 info!("{e}");
-                return Err(AppError::InternalError(format!("DB error")));
+                return Err(AppError::InternalError("DB error".to_string()));
             }
         };
 
         Ok(next_post_id)
     }
 
-    pub async fn add_new_post(&self, post: &Post) -> Result<(), AppError> {
+    /// `search_language` is the text search configuration the post is indexed with.
+    pub async fn add_new_post(
+        &self,
+        post: &mut Post,
+        search_language: &str,
+    ) -> Result<(), AppError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        assign_slug(&mut tx, post).await?;
+
         let query = sqlx::query! {
             r#"
-             INSERT INTO posts (id, title, content, author_id, created_at, updated_at)
-             VALUES ($1, $2, $3, $4, $5, $6)
+             INSERT INTO posts (id, title, content, author_id, created_at, updated_at, status, published_at, version,
+                                search_language, slug, content_format, content_html)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::text::regconfig, $11, $12, $13)
             "#,
             post.id,
             post.title,
             post.content,
             post.author_id,
             post.created_at,
-            post.updated_at
+            post.updated_at,
+            post.status.as_str(),
+            post.published_at,
+            post.version,
+            search_language,
+            post.slug,
+            post.content_format.as_str(),
+            post.content_html
         };
 
-        if let Err(e) = query.execute(&self.pool).await {
+        if let Err(e) = query.execute(&mut *tx).await {
             info!("{e}");
-            return Err(AppError::InternalError(format!("DB error")));
+            return Err(AppError::InternalError("DB error".to_string()));
         };
 
+        add_revision(&mut tx, post, post.author_id).await?;
+        save_tags(&mut tx, post).await?;
+
+        if let Err(e) = tx.commit().await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        }
+
         Ok(())
     }
 
     pub async fn get_post_author_id(&self, post_id: i64) -> Result<i64, AppError> {
         let query = sqlx::query! {
             r#"
-             SELECT author_id FROM posts
-             WHERE id = $1
+             SELECT author_id as "author_id!" FROM posts
+             WHERE id = $1 AND deleted_at IS NULL
             "#,
             post_id
         };
@@ -75,7 +246,7 @@ impl PostRepository {
                 if let sqlx::error::Error::RowNotFound = e {
                     return Err(AppError::PostNotFound(post_id.to_string()));
                 } else {
-                    return Err(AppError::InternalError(format!("DB error")));
+                    return Err(AppError::InternalError("DB error".to_string()));
                 }
             }
         };
@@ -87,9 +258,13 @@ impl PostRepository {
         let query = sqlx::query_as! {
             Post,
             r#"
-             SELECT *
-             FROM posts
-             WHERE posts.id = $1
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!"
+             FROM posts_with_tags
+             WHERE id = $1 AND deleted_at IS NULL
             "#,
             post_id
         };
@@ -101,7 +276,7 @@ impl PostRepository {
                 if let sqlx::error::Error::RowNotFound = e {
                     return Err(AppError::PostNotFound(post_id.to_string()));
                 } else {
-                    return Err(AppError::InternalError(format!("DB error")));
+                    return Err(AppError::InternalError("DB error".to_string()));
                 }
             }
         };
@@ -109,76 +284,469 @@ impl PostRepository {
         Ok(post)
     }
 
-    pub async fn update_post(
-        &self,
-        post_id: i64,
-        new_title: Option<String>,
-        new_content: Option<String>,
-    ) -> Result<Post, AppError> {
-        let mut post = self.get_post(post_id).await?;
-        post.update(new_title, new_content);
+    /// Finds the post by its current or any of its previous slugs.
+    pub async fn get_post_by_slug(&self, slug: &str) -> Result<Post, AppError> {
+        let query = sqlx::query_as! {
+            Post,
+            r#"
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!"
+             FROM posts_with_tags
+             WHERE (slug = $1 OR id = (SELECT post_id FROM post_slugs WHERE post_slugs.slug = $1))
+                   AND deleted_at IS NULL
+            "#,
+            slug
+        };
+
+        let post = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::PostNotFound(slug.to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(post)
+    }
+
+    /// Saves the post if nobody else saved it since it was read, and moves it to the
+    /// next version. A new revision is recorded when `editor_id` is set, i.e. when
+    /// the title or the content changed.
+    pub async fn update_post(&self, post: &mut Post, editor_id: Option<i64>) -> Result<(), AppError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        assign_slug(&mut tx, post).await?;
+
         let query = sqlx::query! {
             r#"
              UPDATE posts
-             SET title = $1, content = $2, updated_at = $3
-             WHERE id = $4
+             SET title = $1, content = $2, updated_at = $3, status = $4, published_at = $5,
+                 version = version + 1, slug = $8, content_format = $9, content_html = $10
+             WHERE id = $6 AND version = $7 AND deleted_at IS NULL
+             RETURNING version
             "#,
             post.title,
             post.content,
             post.updated_at,
-            post_id
+            post.status.as_str(),
+            post.published_at,
+            post.id,
+            post.version,
+            post.slug,
+            post.content_format.as_str(),
+            post.content_html
         };
 
-        if let Err(e) = query.execute(&self.pool).await {
-            info!("{e}");
-            return Err(AppError::InternalError(format!("DB error")));
+        post.version = match query.fetch_optional(&mut *tx).await {
+            Ok(Some(row)) => row.version,
+            Ok(None) => {
+                return Err(AppError::VersionConflict(format!(
+                    "post {} was modified after version {}",
+                    post.id, post.version
+                )));
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
         };
 
-        Ok(post)
+        // The updated post row stays locked until commit, so revision numbers of
+        // concurrent edits don't clash.
+        if let Some(editor_id) = editor_id {
+            add_revision(&mut tx, post, editor_id).await?;
+        }
+        save_tags(&mut tx, post).await?;
+
+        if let Err(e) = tx.commit().await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        }
+
+        Ok(())
     }
 
+    /// Moves the post to the trash.
     pub async fn delete_post(&self, post_id: i64) -> Result<(), AppError> {
         let query = sqlx::query! {
             r#"
-             DELETE FROM posts
-             WHERE id = $1
+             UPDATE posts
+             SET deleted_at = $2
+             WHERE id = $1 AND deleted_at IS NULL
             "#,
-            post_id
+            post_id,
+            Utc::now()
         };
 
-        if let Err(e) = query.execute(&self.pool).await {
-            info!("{e}");
-            if let sqlx::error::Error::RowNotFound = e {
-                return Err(AppError::PostNotFound(post_id.to_string()));
-            } else {
-                return Err(AppError::InternalError(format!("DB error")));
+        match query.execute(&self.pool).await {
+            Ok(result) if result.rows_affected() == 0 => {
+                Err(AppError::PostNotFound(post_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
             }
+        }
+    }
+
+    /// Posts left in the trash by a deleted account are not found, nobody can restore them.
+    pub async fn get_deleted_post(&self, post_id: i64) -> Result<Post, AppError> {
+        let query = sqlx::query_as! {
+            Post,
+            r#"
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!"
+             FROM posts_with_tags
+             WHERE id = $1 AND deleted_at IS NOT NULL AND author_id IS NOT NULL
+            "#,
+            post_id
         };
 
-        Ok(())
+        match query.fetch_one(&self.pool).await {
+            Ok(post) => Ok(post),
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    Err(AppError::PostNotFound(post_id.to_string()))
+                } else {
+                    Err(AppError::InternalError("DB error".to_string()))
+                }
+            }
+        }
     }
 
-    pub async fn get_posts(&self, offset: i64, limit: i64) -> Result<Vec<Post>, AppError> {
+    /// Posts of the author in the trash, the most recently deleted first.
+    pub async fn get_trash(
+        &self,
+        author_id: i64,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Post>, AppError> {
         let query = sqlx::query_as! {
             Post,
             r#"
-             SELECT *
-             FROM posts
-             ORDER BY updated_at DESC
-             LIMIT $1 OFFSET $2
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!"
+             FROM posts_with_tags
+             WHERE author_id = $1 AND deleted_at IS NOT NULL
+             ORDER BY deleted_at DESC
+             LIMIT $2 OFFSET $3
+            "#,
+            author_id,
+            limit,
+            offset
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(posts) => Ok(posts),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Takes the post out of the trash.
+    pub async fn restore_post(&self, post_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE posts
+             SET deleted_at = NULL
+             WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+            post_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(result) if result.rows_affected() == 0 => {
+                Err(AppError::PostNotFound(post_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Deletes a post from the trash for good, together with its revisions.
+    pub async fn purge_post(&self, post_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM posts
+             WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+            post_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(result) if result.rows_affected() == 0 => {
+                Err(AppError::PostNotFound(post_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Deletes posts that were put in the trash before `deleted_before`, returns their ids.
+    pub async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<Vec<i64>, AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM posts
+             WHERE deleted_at < $1
+             RETURNING id
+            "#,
+            deleted_before
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(rows) => Ok(rows.into_iter().map(|row| row.id).collect()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Published posts, together with all posts of `viewer_id` if it's set. Only
+    /// posts with the normalized `tag` are returned if it's set.
+    ///
+    /// The feed goes newest first by `(updated_at, id)`. With a cursor the page starts
+    /// next to the cursor post and `offset` is ignored; `Before` pages come nearest to
+    /// the cursor first, i.e. in ascending order.
+    pub async fn get_posts(
+        &self,
+        viewer_id: Option<i64>,
+        tag: Option<&str>,
+        cursor: Option<&PostCursor>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Post>, AppError> {
+        let result = match cursor {
+            Some(cursor) if cursor.direction == Direction::Before => {
+                sqlx::query_as! {
+                    Post,
+                    r#"
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!"
+             FROM posts_with_tags
+             WHERE deleted_at IS NULL AND (status = 'published' OR author_id = $1)
+               AND ($2::text IS NULL OR $2 = ANY(tags))
+               AND (updated_at, id) > ($3, $4)
+             ORDER BY updated_at, id
+             LIMIT $5
+                    "#,
+                    viewer_id,
+                    tag,
+                    cursor.updated_at,
+                    cursor.id,
+                    limit
+                }
+                .fetch_all(&self.pool)
+                .await
+            }
+            Some(cursor) => {
+                sqlx::query_as! {
+                    Post,
+                    r#"
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!"
+             FROM posts_with_tags
+             WHERE deleted_at IS NULL AND (status = 'published' OR author_id = $1)
+               AND ($2::text IS NULL OR $2 = ANY(tags))
+               AND (updated_at, id) < ($3, $4)
+             ORDER BY updated_at DESC, id DESC
+             LIMIT $5
+                    "#,
+                    viewer_id,
+                    tag,
+                    cursor.updated_at,
+                    cursor.id,
+                    limit
+                }
+                .fetch_all(&self.pool)
+                .await
+            }
+            None => {
+                sqlx::query_as! {
+                    Post,
+                    r#"
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!"
+             FROM posts_with_tags
+             WHERE deleted_at IS NULL AND (status = 'published' OR author_id = $1)
+               AND ($2::text IS NULL OR $2 = ANY(tags))
+             ORDER BY updated_at DESC, id DESC
+             LIMIT $3 OFFSET $4
+                    "#,
+                    viewer_id,
+                    tag,
+                    limit,
+                    offset
+                }
+                .fetch_all(&self.pool)
+                .await
+            }
+        };
+
+        match result {
+            Ok(posts) => Ok(posts),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Number of posts in the feed [`Self::get_posts`] pages through.
+    pub async fn count_posts(&self, viewer_id: Option<i64>, tag: Option<&str>) -> Result<i64, AppError> {
+        let query = sqlx::query_scalar! {
+            r#"
+             SELECT COUNT(*) as "count!"
+             FROM posts_with_tags
+             WHERE deleted_at IS NULL AND (status = 'published' OR author_id = $1)
+               AND ($2::text IS NULL OR $2 = ANY(tags))
+            "#,
+            viewer_id,
+            tag
+        };
+
+        match query.fetch_one(&self.pool).await {
+            Ok(count) => Ok(count),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Posts matching the web search style `query`, the most relevant first. Visibility
+    /// is the same as in [`Self::get_posts`].
+    pub async fn search_posts(
+        &self,
+        viewer_id: Option<i64>,
+        query: &str,
+        language: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<SearchHit>, AppError> {
+        let headline_options = format!(
+            "StartSel={MATCH_START}, StopSel={MATCH_END}, MaxFragments=2, MaxWords=30, MinWords=10, \
+             FragmentDelimiter=\" ... \""
+        );
+        let query = sqlx::query! {
+            r#"
+             SELECT id as "id!", title as "title!", slug as "slug!", content as "content!",
+                    content_format as "content_format!: ContentFormat", content_html,
+                    author_id as "author_id!", created_at as "created_at!",
+                    updated_at as "updated_at!", status as "status!: PostStatus", published_at,
+                    version as "version!", deleted_at, tags as "tags!",
+                    ts_rank(search_vector, search_query) as "rank!",
+                    ts_headline(search_language, content, search_query, $6) as "snippet!"
+             FROM posts_with_tags, websearch_to_tsquery($2::text::regconfig, $1) search_query
+             WHERE search_vector @@ search_query
+               AND deleted_at IS NULL AND (status = 'published' OR author_id = $3)
+             ORDER BY ts_rank(search_vector, search_query) DESC, updated_at DESC
+             LIMIT $4 OFFSET $5
             "#,
+            query,
+            language,
+            viewer_id,
             limit,
             offset,
+            headline_options
         };
 
-        let posts = match query.fetch_all(&self.pool).await {
-            Ok(records) => records,
+        let rows = match query.fetch_all(&self.pool).await {
+            Ok(rows) => rows,
             Err(e) => {
                 info!("{e}");
-                return Err(AppError::InternalError(format!("DB error")));
+                if let Some(db_error) = e.as_database_error()
+                    && db_error.code().as_deref() == Some(UNDEFINED_OBJECT)
+                {
+                    return Err(AppError::InvalidRequest(format!(
+                        "unknown search language: {language}"
+                    )));
+                }
+                return Err(AppError::InternalError("DB error".to_string()));
             }
         };
 
-        Ok(posts)
+        Ok(rows
+            .into_iter()
+            .map(|row| SearchHit {
+                post: Post {
+                    id: row.id,
+                    title: row.title,
+                    slug: row.slug,
+                    content: row.content,
+                    content_format: row.content_format,
+                    content_html: row.content_html,
+                    author_id: row.author_id,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                    status: row.status,
+                    published_at: row.published_at,
+                    version: row.version,
+                    deleted_at: row.deleted_at,
+                    tags: row.tags,
+                },
+                rank: row.rank,
+                snippet: row.snippet,
+            })
+            .collect())
+    }
+
+    /// Publishes scheduled posts whose time has come, returns their ids.
+    pub async fn publish_due(&self) -> Result<Vec<i64>, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE posts
+             SET status = 'published', updated_at = $1, version = version + 1
+             WHERE status = 'scheduled' AND published_at <= $1 AND deleted_at IS NULL
+             RETURNING id
+            "#,
+            Utc::now()
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(rows) => Ok(rows.into_iter().map(|row| row.id).collect()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
     }
 }
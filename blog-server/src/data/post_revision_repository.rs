@@ -0,0 +1,60 @@
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::post_revision::PostRevision;
+
+pub struct PostRevisionRepository {
+    pool: PgPool,
+}
+
+impl PostRevisionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Revisions of the post, the latest first.
+    pub async fn get_revisions(&self, post_id: i64) -> Result<Vec<PostRevision>, AppError> {
+        let query = sqlx::query_as! {
+            PostRevision,
+            r#"
+             SELECT * FROM post_revisions
+             WHERE post_id = $1
+             ORDER BY revision DESC
+            "#,
+            post_id
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(revisions) => Ok(revisions),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn get_revision(&self, post_id: i64, revision: i32) -> Result<PostRevision, AppError> {
+        let query = sqlx::query_as! {
+            PostRevision,
+            r#"
+             SELECT * FROM post_revisions
+             WHERE post_id = $1 AND revision = $2
+            "#,
+            post_id,
+            revision
+        };
+
+        match query.fetch_one(&self.pool).await {
+            Ok(revision) => Ok(revision),
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    Err(AppError::RevisionNotFound(format!("{post_id}/{revision}")))
+                } else {
+                    Err(AppError::InternalError("DB error".to_string()))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+
+pub struct RecoveryCodeRepository {
+    pool: PgPool,
+}
+
+impl RecoveryCodeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Replaces all recovery codes of the user with a new set.
+    pub async fn replace_codes(&self, user_id: i64, code_hashes: &[String]) -> Result<(), AppError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        let delete = sqlx::query! {
+            r#"
+             DELETE FROM recovery_codes
+             WHERE user_id = $1
+            "#,
+            user_id
+        };
+
+        if let Err(e) = delete.execute(&mut *tx).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        let insert = sqlx::query! {
+            r#"
+             INSERT INTO recovery_codes (user_id, code_hash)
+             SELECT $1, * FROM UNNEST($2::VARCHAR[])
+            "#,
+            user_id,
+            code_hashes
+        };
+
+        if let Err(e) = insert.execute(&mut *tx).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        if let Err(e) = tx.commit().await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    /// Returns `false` if there is no unused code with this hash.
+    pub async fn use_code(&self, user_id: i64, code_hash: &str) -> Result<bool, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE recovery_codes
+             SET used_at = $1
+             WHERE id = (
+                 SELECT id FROM recovery_codes
+                 WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL
+                 LIMIT 1
+             ) AND used_at IS NULL
+            "#,
+            Utc::now(),
+            user_id,
+            code_hash
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected() == 1),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn delete_user_codes(&self, user_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM recovery_codes
+             WHERE user_id = $1
+            "#,
+            user_id
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+}
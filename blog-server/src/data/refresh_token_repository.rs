@@ -0,0 +1,147 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::refresh_token::RefreshToken;
+
+pub struct RefreshTokenRepository {
+    pool: PgPool,
+}
+
+impl RefreshTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn next_token_id(&self) -> Result<i64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT NEXTVAL('refresh_tokens_id_seq')
+            "#
+        };
+
+        let next_token_id = match query.fetch_one(&self.pool).await {
+            Ok(row) => {
+                if let Some(val) = row.nextval {
+                    val
+                } else {
+                    info!("Can't generate refresh token id");
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(next_token_id)
+    }
+
+    pub async fn add_refresh_token(&self, token: &RefreshToken) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO refresh_tokens (id, user_id, family_id, token_hash, expires_at, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            token.id,
+            token.user_id,
+            token.family_id,
+            token.token_hash,
+            token.expires_at,
+            token.created_at
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_refresh_token(&self, token_hash: &str) -> Result<RefreshToken, AppError> {
+        let query = sqlx::query_as! {
+            RefreshToken,
+            r#"
+             SELECT * FROM refresh_tokens
+             WHERE token_hash = $1
+            "#,
+            token_hash
+        };
+
+        let token = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::Unauthorized("invalid refresh token".to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(token)
+    }
+
+    /// Returns `false` if the token was already used or revoked by a concurrent request.
+    pub async fn mark_used(&self, token_id: i64) -> Result<bool, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE refresh_tokens
+             SET used_at = $1
+             WHERE id = $2 AND used_at IS NULL AND revoked_at IS NULL
+            "#,
+            Utc::now(),
+            token_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected() == 1),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn revoke_family(&self, family_id: &str) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE refresh_tokens
+             SET revoked_at = $1
+             WHERE family_id = $2 AND revoked_at IS NULL
+            "#,
+            Utc::now(),
+            family_id
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn revoke_user_tokens(&self, user_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE refresh_tokens
+             SET revoked_at = $1
+             WHERE user_id = $2 AND revoked_at IS NULL
+            "#,
+            Utc::now(),
+            user_id
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+
+pub struct RevokedTokenRepository {
+    pool: PgPool,
+}
+
+impl RevokedTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn add_revoked_token(
+        &self,
+        jti: &str,
+        user_id: i64,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO revoked_tokens (jti, user_id, expires_at, revoked_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (jti) DO NOTHING
+            "#,
+            jti,
+            user_id,
+            expires_at,
+            Utc::now()
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_active_revocations(&self) -> Result<Vec<(String, DateTime<Utc>)>, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT jti, expires_at FROM revoked_tokens
+             WHERE expires_at > $1
+            "#,
+            Utc::now()
+        };
+
+        let revoked = match query.fetch_all(&self.pool).await {
+            Ok(records) => records
+                .into_iter()
+                .map(|record| (record.jti, record.expires_at))
+                .collect(),
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        Ok(revoked)
+    }
+
+    pub async fn delete_expired(&self) -> Result<u64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM revoked_tokens
+             WHERE expires_at <= $1
+            "#,
+            Utc::now()
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
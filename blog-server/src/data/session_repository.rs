@@ -0,0 +1,215 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::session::Session;
+
+pub struct SessionRepository {
+    pool: PgPool,
+}
+
+impl SessionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Returns the id of the session the refresh token family belongs to, creating it
+    /// on login. Families issued before sessions existed get one on their next refresh.
+    pub async fn open_session(
+        &self,
+        user_id: i64,
+        family_id: &str,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> Result<i64, AppError> {
+        let current = Utc::now();
+        let query = sqlx::query! {
+            r#"
+             INSERT INTO sessions (user_id, family_id, user_agent, ip_address, created_at, last_seen_at)
+             VALUES ($1, $2, $3, $4, $5, $5)
+             ON CONFLICT (family_id) DO UPDATE
+             SET user_agent = COALESCE(EXCLUDED.user_agent, sessions.user_agent),
+                 ip_address = COALESCE(EXCLUDED.ip_address, sessions.ip_address),
+                 last_seen_at = EXCLUDED.last_seen_at
+             RETURNING id
+            "#,
+            user_id,
+            family_id,
+            user_agent,
+            ip_address,
+            current
+        };
+
+        match query.fetch_one(&self.pool).await {
+            Ok(row) => Ok(row.id),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Sessions that weren't revoked and can still be refreshed.
+    pub async fn get_user_sessions(&self, user_id: i64) -> Result<Vec<Session>, AppError> {
+        let query = sqlx::query_as! {
+            Session,
+            r#"
+             SELECT s.* FROM sessions s
+             WHERE s.user_id = $1 AND s.revoked_at IS NULL
+               AND EXISTS (
+                   SELECT 1 FROM refresh_tokens r
+                   WHERE r.family_id = s.family_id
+                     AND r.used_at IS NULL AND r.revoked_at IS NULL AND r.expires_at > $2
+               )
+             ORDER BY s.last_seen_at DESC
+            "#,
+            user_id,
+            Utc::now()
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(sessions) => Ok(sessions),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn touch_session(&self, session_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE sessions
+             SET last_seen_at = $1
+             WHERE id = $2
+            "#,
+            Utc::now(),
+            session_id
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    /// Returns the refresh token family of the revoked session, or `None` if the user
+    /// has no such active session.
+    pub async fn revoke_session(
+        &self,
+        user_id: i64,
+        session_id: i64,
+    ) -> Result<Option<String>, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE sessions
+             SET revoked_at = $1
+             WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL
+             RETURNING family_id
+            "#,
+            Utc::now(),
+            session_id,
+            user_id
+        };
+
+        match query.fetch_optional(&self.pool).await {
+            Ok(row) => Ok(row.map(|row| row.family_id)),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn revoke_family_session(&self, family_id: &str) -> Result<Option<i64>, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE sessions
+             SET revoked_at = $1
+             WHERE family_id = $2 AND revoked_at IS NULL
+             RETURNING id
+            "#,
+            Utc::now(),
+            family_id
+        };
+
+        match query.fetch_optional(&self.pool).await {
+            Ok(row) => Ok(row.map(|row| row.id)),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn revoke_user_sessions(&self, user_id: i64) -> Result<Vec<i64>, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE sessions
+             SET revoked_at = $1
+             WHERE user_id = $2 AND revoked_at IS NULL
+             RETURNING id
+            "#,
+            Utc::now(),
+            user_id
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(rows) => Ok(rows.into_iter().map(|row| row.id).collect()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Sessions revoked after `since`, together with the revocation time.
+    pub async fn get_revoked_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(i64, DateTime<Utc>)>, AppError> {
+        let query = sqlx::query! {
+            r#"
+             SELECT id, revoked_at AS "revoked_at!" FROM sessions
+             WHERE revoked_at > $1
+            "#,
+            since
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(rows) => Ok(rows.into_iter().map(|row| (row.id, row.revoked_at)).collect()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Deletes sessions that were revoked before `revoked_before` or can't be
+    /// refreshed anymore.
+    pub async fn delete_stale(&self, revoked_before: DateTime<Utc>) -> Result<u64, AppError> {
+        let query = sqlx::query! {
+            r#"
+             DELETE FROM sessions s
+             WHERE s.revoked_at < $1
+                OR NOT EXISTS (
+                   SELECT 1 FROM refresh_tokens r
+                   WHERE r.family_id = s.family_id AND r.expires_at > $2
+                )
+            "#,
+            revoked_before,
+            Utc::now()
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::domain::error::AppError;
+use crate::domain::tag::TagCount;
+
+pub struct TagRepository {
+    pool: PgPool,
+}
+
+impl TagRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Tags used by published posts, the most popular first.
+    pub async fn get_tag_counts(&self) -> Result<Vec<TagCount>, AppError> {
+        let query = sqlx::query_as! {
+            TagCount,
+            r#"
+             SELECT tags.name, COUNT(*) as "post_count!"
+             FROM tags
+             JOIN post_tags ON post_tags.tag_id = tags.id
+             JOIN posts ON posts.id = post_tags.post_id
+             WHERE posts.status = 'published' AND posts.deleted_at IS NULL
+             GROUP BY tags.name
+             ORDER BY 2 DESC, tags.name
+            "#
+        };
+
+        match query.fetch_all(&self.pool).await {
+            Ok(tags) => Ok(tags),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
+use chrono::Utc;
 use sqlx::PgPool;
 use tracing::info;
 
 use crate::domain::error::AppError;
+use crate::domain::role::Role;
 use crate::domain::user::User;
 
 pub struct UserRepository {
@@ -26,12 +28,12 @@ impl UserRepository {
                     val
                 } else {
                     info!("Can't generate post id");
-                    return Err(AppError::InternalError(format!("DB error")));
+                    return Err(AppError::InternalError("DB error".to_string()));
                 }
             }
             Err(e) => {
                 info!("{e}");
-                return Err(AppError::InternalError(format!("DB error")));
+                return Err(AppError::InternalError("DB error".to_string()));
             }
         };
 
@@ -41,27 +43,29 @@ impl UserRepository {
     pub async fn add_new_user(&self, user: &User) -> Result<(), AppError> {
         let query = sqlx::query! {
             r#"
-             INSERT INTO users (id, username, email, password_hash, created_at)
-             VALUES ($1, $2, $3, $4, $5)
+             INSERT INTO users (id, username, email, password_hash, created_at, role, email_verified_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
             "#,
             user.id,
             user.username,
             user.email,
             user.password_hash,
-            user.created_at
+            user.created_at,
+            user.role.as_str(),
+            user.email_verified_at
         };
 
         if let Err(e) = query.execute(&self.pool).await {
             info!("{e}");
             let Some(e) = e.into_database_error() else {
-                return Err(AppError::InternalError(format!("DB error")));
+                return Err(AppError::InternalError("DB error".to_string()));
             };
 
             let kind = e.kind();
             if let sqlx::error::ErrorKind::UniqueViolation = kind {
                 return Err(AppError::AlreadyExists(format!("{user}")));
             } else {
-                return Err(AppError::InternalError(format!("DB error")));
+                return Err(AppError::InternalError("DB error".to_string()));
             }
         };
 
@@ -72,7 +76,9 @@ impl UserRepository {
         let query = sqlx::query_as! {
             User,
             r#"
-             SELECT * from users where username = $1
+             SELECT id, username, email, password_hash, created_at, role as "role: Role",
+                    email_verified_at, totp_secret_enc, totp_enabled_at, totp_last_step
+             FROM users WHERE username = $1
             "#,
             username
         };
@@ -84,11 +90,356 @@ impl UserRepository {
                 if let sqlx::error::Error::RowNotFound = e {
                     return Err(AppError::UserNotFound(username.to_string()));
                 } else {
-                    return Err(AppError::InternalError(format!("DB error")));
+                    return Err(AppError::InternalError("DB error".to_string()));
                 }
             }
         };
 
         Ok(user)
     }
+
+    pub async fn get_user_by_id(&self, user_id: i64) -> Result<User, AppError> {
+        let query = sqlx::query_as! {
+            User,
+            r#"
+             SELECT id, username, email, password_hash, created_at, role as "role: Role",
+                    email_verified_at, totp_secret_enc, totp_enabled_at, totp_last_step
+             FROM users WHERE id = $1
+            "#,
+            user_id
+        };
+
+        let user = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::UserNotFound(user_id.to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(user)
+    }
+
+    pub async fn get_user_by_email(&self, email: &str) -> Result<User, AppError> {
+        let query = sqlx::query_as! {
+            User,
+            r#"
+             SELECT id, username, email, password_hash, created_at, role as "role: Role",
+                    email_verified_at, totp_secret_enc, totp_enabled_at, totp_last_step
+             FROM users WHERE email = $1
+            "#,
+            email
+        };
+
+        let user = match query.fetch_one(&self.pool).await {
+            Ok(row) => row,
+            Err(e) => {
+                info!("{e}");
+                if let sqlx::error::Error::RowNotFound = e {
+                    return Err(AppError::UserNotFound(email.to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            }
+        };
+
+        Ok(user)
+    }
+
+    pub async fn update_password_hash(&self, user: &User) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE users
+             SET password_hash = $1
+             WHERE id = $2
+            "#,
+            user.password_hash,
+            user.id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) if res.rows_affected() == 0 => Err(AppError::UserNotFound(user.id.to_string())),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Changes the email and drops its verification, the new address has to be confirmed again.
+    pub async fn update_email(&self, user_id: i64, email: &str) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE users
+             SET email = $1, email_verified_at = NULL
+             WHERE id = $2
+            "#,
+            email,
+            user_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                Err(AppError::UserNotFound(user_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                let Some(e) = e.into_database_error() else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                };
+
+                if let sqlx::error::ErrorKind::UniqueViolation = e.kind() {
+                    Err(AppError::AlreadyExists(format!("email: {email}")))
+                } else {
+                    Err(AppError::InternalError("DB error".to_string()))
+                }
+            }
+        }
+    }
+
+    /// Deletes the user with everything that references it. Posts are moved to the trash
+    /// and purged with it, unless `reassign_posts_to` names the user that takes them over.
+    /// Posts in the trash are never handed over.
+    pub async fn delete_user(
+        &self,
+        user_id: i64,
+        reassign_posts_to: Option<i64>,
+    ) -> Result<(), AppError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        if let Some(new_author_id) = reassign_posts_to {
+            let reassign = sqlx::query! {
+                r#"
+                 UPDATE posts
+                 SET author_id = $1
+                 WHERE author_id = $2 AND deleted_at IS NULL
+                "#,
+                new_author_id,
+                user_id
+            };
+
+            if let Err(e) = reassign.execute(&mut *tx).await {
+                info!("{e}");
+                let Some(e) = e.into_database_error() else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                };
+
+                if let sqlx::error::ErrorKind::ForeignKeyViolation = e.kind() {
+                    return Err(AppError::UserNotFound(new_author_id.to_string()));
+                } else {
+                    return Err(AppError::InternalError("DB error".to_string()));
+                }
+            };
+        } else {
+            let trash = sqlx::query! {
+                r#"
+                 UPDATE posts
+                 SET deleted_at = $1
+                 WHERE author_id = $2 AND deleted_at IS NULL
+                "#,
+                Utc::now(),
+                user_id
+            };
+
+            if let Err(e) = trash.execute(&mut *tx).await {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            };
+        }
+
+        let delete = sqlx::query! {
+            r#"
+             DELETE FROM users
+             WHERE id = $1
+            "#,
+            user_id
+        };
+
+        match delete.execute(&mut *tx).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                return Err(AppError::UserNotFound(user_id.to_string()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                info!("{e}");
+                return Err(AppError::InternalError("DB error".to_string()));
+            }
+        };
+
+        if let Err(e) = tx.commit().await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    pub async fn set_user_role(&self, user_id: i64, role: Role) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE users
+             SET role = $1
+             WHERE id = $2
+            "#,
+            role.as_str(),
+            user_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                Err(AppError::UserNotFound(user_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    /// Stores a pending secret. Two-factor login stays disabled until `enable_totp`.
+    pub async fn set_totp_secret(&self, user_id: i64, secret_enc: &str) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE users
+             SET totp_secret_enc = $1, totp_enabled_at = NULL, totp_last_step = NULL
+             WHERE id = $2
+            "#,
+            secret_enc,
+            user_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                Err(AppError::UserNotFound(user_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn enable_totp(&self, user_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE users
+             SET totp_enabled_at = $1
+             WHERE id = $2 AND totp_secret_enc IS NOT NULL
+            "#,
+            Utc::now(),
+            user_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) if res.rows_affected() == 0 => {
+                Err(AppError::UserNotFound(user_id.to_string()))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+
+    pub async fn disable_totp(&self, user_id: i64) -> Result<(), AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE users
+             SET totp_secret_enc = NULL, totp_enabled_at = NULL, totp_last_step = NULL
+             WHERE id = $1
+            "#,
+            user_id
+        };
+
+        if let Err(e) = query.execute(&self.pool).await {
+            info!("{e}");
+            return Err(AppError::InternalError("DB error".to_string()));
+        };
+
+        Ok(())
+    }
+
+    /// Remembers the time step of an accepted code. Returns `false` if the same or a
+    /// later step was already used, i.e. the code is being replayed.
+    pub async fn update_totp_last_step(&self, user_id: i64, step: i64) -> Result<bool, AppError> {
+        let query = sqlx::query! {
+            r#"
+             UPDATE users
+             SET totp_last_step = $1
+             WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)
+            "#,
+            step,
+            user_id
+        };
+
+        match query.execute(&self.pool).await {
+            Ok(res) => Ok(res.rows_affected() == 1),
+            Err(e) => {
+                info!("{e}");
+                Err(AppError::InternalError("DB error".to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::post_repository::PostRepository;
+    use crate::domain::post::Post;
+
+    #[sqlx::test]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_posts_of_deleted_user_go_to_trash(pool: PgPool) {
+        let user_repo = UserRepository::new(pool.clone());
+        let post_repo = PostRepository::new(pool);
+        let user_id = user_repo.next_user_id().await.unwrap();
+        let user = User::create(
+            user_id,
+            "alice".to_string(),
+            "alice@example.com".to_string(),
+            "password1".to_string(),
+        )
+        .unwrap();
+        user_repo.add_new_user(&user).await.unwrap();
+
+        let mut post_ids = Vec::new();
+        for title in ["Live", "Trashed"] {
+            let post_id = post_repo.next_post_id().await.unwrap();
+            let mut post = Post::create(
+                post_id,
+                title.to_string(),
+                "Text".to_string(),
+                user_id,
+                vec![],
+            );
+            post_repo.add_new_post(&mut post, "simple").await.unwrap();
+            post_ids.push(post_id);
+        }
+        post_repo.delete_post(post_ids[1]).await.unwrap();
+
+        user_repo.delete_user(user_id, None).await.unwrap();
+
+        for post_id in &post_ids {
+            let found = post_repo.get_post(*post_id).await;
+            assert!(matches!(found, Err(AppError::PostNotFound(_))));
+        }
+        let purged = post_repo.purge_deleted(Utc::now()).await.unwrap();
+        assert_eq!(purged.len(), post_ids.len());
+    }
 }
@@ -0,0 +1,202 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use std::collections::{HashMap, HashSet};
+
+pub struct Comment {
+    pub id: i64,
+    pub post_id: i64,
+    /// The comment this one replies to, `None` for a top-level comment.
+    pub parent_id: Option<i64>,
+    /// `None` once the author's account is deleted.
+    pub author_id: Option<i64>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Deleted comments keep their place in the thread while they have replies.
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Comment {
+    pub fn create(
+        id: i64,
+        post_id: i64,
+        parent_id: Option<i64>,
+        author_id: i64,
+        content: String,
+    ) -> Self {
+        let current = Utc::now();
+        Self {
+            id,
+            post_id,
+            parent_id,
+            author_id: Some(author_id),
+            content,
+            created_at: current,
+            updated_at: current,
+            deleted_at: None,
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Whether the comment is still within `edit_window` of being posted.
+    pub fn is_editable(&self, edit_window: TimeDelta) -> bool {
+        !self.is_deleted() && Utc::now() - self.created_at <= edit_window
+    }
+
+    /// Returns whether the content changed.
+    pub fn update(&mut self, content: String) -> bool {
+        if content == self.content {
+            return false;
+        }
+        self.content = content;
+        self.updated_at = Utc::now();
+        true
+    }
+}
+
+/// Nesting depth of `comment` among the other comments of its post.
+pub fn depth(comments: &[Comment], comment: &Comment) -> usize {
+    let parents: HashMap<i64, Option<i64>> = comments
+        .iter()
+        .map(|comment| (comment.id, comment.parent_id))
+        .collect();
+    let mut depth = 0;
+    let mut parent_id = comment.parent_id;
+    while let Some(id) = parent_id
+        && depth < comments.len()
+    {
+        depth += 1;
+        parent_id = parents.get(&id).copied().flatten();
+    }
+    depth
+}
+
+/// Orders the comments of a post into threads: every comment is followed by its
+/// replies, oldest first, and comes with its nesting depth. Deleted comments are
+/// dropped unless some reply below them is still there.
+pub fn thread(mut comments: Vec<Comment>) -> Vec<(Comment, usize)> {
+    comments.sort_by_key(|comment| (comment.created_at, comment.id));
+    let parents: HashMap<i64, Option<i64>> = comments
+        .iter()
+        .map(|comment| (comment.id, comment.parent_id))
+        .collect();
+
+    let mut shown = HashSet::new();
+    for comment in comments.iter().filter(|comment| !comment.is_deleted()) {
+        let mut id = Some(comment.id);
+        while let Some(current) = id
+            && shown.insert(current)
+        {
+            id = parents.get(&current).copied().flatten();
+        }
+    }
+
+    let mut replies: HashMap<Option<i64>, Vec<Comment>> = HashMap::new();
+    for comment in comments {
+        if shown.contains(&comment.id) {
+            replies.entry(comment.parent_id).or_default().push(comment);
+        }
+    }
+
+    let mut threaded = Vec::new();
+    let mut stack: Vec<(Comment, usize)> = replies
+        .remove(&None)
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .map(|comment| (comment, 0))
+        .collect();
+    while let Some((comment, depth)) = stack.pop() {
+        if let Some(children) = replies.remove(&Some(comment.id)) {
+            stack.extend(children.into_iter().rev().map(|reply| (reply, depth + 1)));
+        }
+        threaded.push((comment, depth));
+    }
+    threaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: i64, parent_id: Option<i64>, minute: i64) -> Comment {
+        let created_at = DateTime::UNIX_EPOCH + TimeDelta::minutes(minute);
+        Comment {
+            created_at,
+            updated_at: created_at,
+            ..Comment::create(id, 1, parent_id, 7, format!("comment {id}"))
+        }
+    }
+
+    fn deleted(comment: Comment) -> Comment {
+        Comment {
+            deleted_at: Some(Utc::now()),
+            ..comment
+        }
+    }
+
+    fn ids(threaded: &[(Comment, usize)]) -> Vec<(i64, usize)> {
+        threaded
+            .iter()
+            .map(|(comment, depth)| (comment.id, *depth))
+            .collect()
+    }
+
+    #[test]
+    fn test_edit_window() {
+        let mut fresh = Comment::create(1, 1, None, 7, "text".to_string());
+        assert!(fresh.is_editable(TimeDelta::minutes(15)));
+        assert!(!fresh.update("text".to_string()));
+        assert!(fresh.update("edited".to_string()));
+        assert_eq!(fresh.content, "edited");
+
+        let old = comment(2, None, 0);
+        assert!(!old.is_editable(TimeDelta::minutes(15)));
+        let removed = deleted(Comment::create(3, 1, None, 7, "text".to_string()));
+        assert!(!removed.is_editable(TimeDelta::minutes(15)));
+    }
+
+    #[test]
+    fn test_thread() {
+        let comments = vec![
+            comment(4, Some(1), 3),
+            comment(2, None, 1),
+            comment(1, None, 0),
+            comment(3, Some(1), 2),
+            comment(5, Some(3), 4),
+        ];
+        assert_eq!(
+            ids(&thread(comments)),
+            [(1, 0), (3, 1), (5, 2), (4, 1), (2, 0)]
+        );
+        assert!(thread(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_depth() {
+        let comments = vec![
+            comment(1, None, 0),
+            comment(2, Some(1), 1),
+            comment(3, Some(2), 2),
+        ];
+        assert_eq!(depth(&comments, &comments[0]), 0);
+        assert_eq!(depth(&comments, &comments[2]), 2);
+        assert_eq!(depth(&comments, &comment(4, Some(3), 3)), 3);
+    }
+
+    #[test]
+    fn test_thread_drops_deleted_without_replies() {
+        let comments = vec![
+            deleted(comment(1, None, 0)),
+            deleted(comment(2, Some(1), 1)),
+            comment(3, Some(2), 2),
+            deleted(comment(4, None, 3)),
+            deleted(comment(5, Some(4), 4)),
+            comment(6, None, 5),
+        ];
+        assert_eq!(ids(&thread(comments)), [(1, 0), (2, 1), (3, 2), (6, 0)]);
+    }
+}
@@ -0,0 +1,175 @@
+use pulldown_cmark::{Options, Parser};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type, encode::IsNull, error::BoxDynError};
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    #[default]
+    Plain,
+    Markdown,
+}
+
+impl ContentFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentFormat::Plain => "plain",
+            ContentFormat::Markdown => "markdown",
+        }
+    }
+
+    /// Sanitized HTML of the content, safe to insert into a page as is.
+    pub fn render(&self, content: &str) -> String {
+        match self {
+            ContentFormat::Plain => render_plain(content),
+            ContentFormat::Markdown => render_markdown(content),
+        }
+    }
+}
+
+/// Escapes text for use in HTML content and attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Blank lines separate paragraphs, other line breaks are kept.
+fn render_plain(content: &str) -> String {
+    let content = content.replace("\r\n", "\n");
+    content
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| format!("<p>{}</p>", escape_html(paragraph).replace('\n', "<br>\n")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Raw HTML in Markdown is allowed, but the result goes through the sanitizer, which
+/// keeps only known-safe tags and attributes and drops scripts, event handlers and
+/// links with schemes other than http, https and mailto.
+fn render_markdown(content: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, Parser::new_ext(content, options));
+
+    ammonia::Builder::default()
+        .url_schemes(["http", "https", "mailto"].into())
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&html)
+        .to_string()
+}
+
+impl Display for ContentFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ContentFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(ContentFormat::Plain),
+            "markdown" => Ok(ContentFormat::Markdown),
+            _ => Err(AppError::InvalidRequest(format!("unknown content format: {s}"))),
+        }
+    }
+}
+
+impl Type<Postgres> for ContentFormat {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for ContentFormat {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl Decode<'_, Postgres> for ContentFormat {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        let format = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(format.parse::<ContentFormat>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("plain".parse::<ContentFormat>().unwrap(), ContentFormat::Plain);
+        assert_eq!("markdown".parse::<ContentFormat>().unwrap(), ContentFormat::Markdown);
+        assert!("html".parse::<ContentFormat>().is_err());
+        assert_eq!(ContentFormat::Markdown.to_string(), "markdown");
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let html = ContentFormat::Plain.render("One <b>&</b>\nline\r\n\r\n\n\nTwo *not bold*");
+        assert_eq!(
+            html,
+            "<p>One &lt;b&gt;&amp;&lt;/b&gt;<br>\nline</p>\n<p>Two *not bold*</p>"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let html = ContentFormat::Markdown.render("# Title\n\nSome **bold** and `code`");
+        assert_eq!(
+            html,
+            "<h1>Title</h1>\n<p>Some <strong>bold</strong> and <code>code</code></p>\n"
+        );
+
+        let link = ContentFormat::Markdown.render("[site](https://example.com)");
+        assert!(link.contains(r#"href="https://example.com""#));
+        assert!(link.contains("nofollow"));
+    }
+
+    #[test]
+    fn test_render_markdown_sanitized() {
+        let html = ContentFormat::Markdown.render(
+            "<script>alert(1)</script>\n\n\
+             <img src=\"https://example.com/a.png\" onerror=\"alert(2)\">\n\n\
+             [click](javascript:alert(3)) <a href=\"data:text/html,x\">data</a>\n\n\
+             <iframe src=\"https://example.com\"></iframe><p style=\"color:red\">styled</p>",
+        );
+        assert!(!html.contains("script"));
+        assert!(!html.contains("alert"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("javascript"));
+        assert!(!html.contains("data:"));
+        assert!(!html.contains("iframe"));
+        assert!(!html.contains("style="));
+        assert!(html.contains(r#"src="https://example.com/a.png""#));
+        assert!(html.contains("styled"));
+    }
+}
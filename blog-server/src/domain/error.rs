@@ -1,6 +1,43 @@
 use actix_web::{HttpResponse, error::ResponseError, http::StatusCode};
+use serde::Serialize;
 use thiserror::Error;
 
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct FieldErrors(pub Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for FieldErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self
+            .0
+            .iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("User already exists: {0}")]
@@ -11,6 +48,24 @@ pub enum AppError {
     UserNotFound(String),
     #[error("Post not found: {0}")]
     PostNotFound(String),
+    #[error("Revision not found: {0}")]
+    RevisionNotFound(String),
+    #[error("Comment not found: {0}")]
+    CommentNotFound(String),
+    #[error("Version conflict: {0}")]
+    VersionConflict(String),
+    #[error("Token not found: {0}")]
+    TokenNotFound(String),
+    #[error("Session not found: {0}")]
+    SessionNotFound(String),
+    #[error("Access denied: {0}")]
+    Forbidden(String),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Too many attempts: {0}")]
+    TooManyAttempts(String),
+    #[error("Validation failed: {0}")]
+    Validation(FieldErrors),
     #[error("Internal server error: {0}")]
     InternalError(String),
 }
@@ -22,12 +77,26 @@ impl ResponseError for AppError {
             AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
             AppError::UserNotFound(_) => StatusCode::NOT_FOUND,
             AppError::PostNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::RevisionNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::CommentNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::VersionConflict(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::TokenNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::SessionNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::TooManyAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
-        HttpResponse::build(status).json(serde_json::json!({
+        let mut body = serde_json::json!({
             "error": self.to_string(),
             "status": status.as_u16()
-        }))
+        });
+        if let AppError::Validation(fields) = self {
+            body["fields"] = serde_json::json!(fields);
+        }
+
+        HttpResponse::build(status).json(body)
     }
 }
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+
+/// Account of an external identity provider that signs in as a local user.
+/// The pair of `issuer` and `subject` is unique.
+pub struct LinkedIdentity {
+    pub id: i64,
+    pub user_id: i64,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+impl LinkedIdentity {
+    pub fn create(
+        id: i64,
+        user_id: i64,
+        issuer: String,
+        subject: String,
+        email: Option<String>,
+    ) -> Self {
+        let current = Utc::now();
+        Self {
+            id,
+            user_id,
+            issuer,
+            subject,
+            email,
+            created_at: current,
+            last_login_at: Some(current),
+        }
+    }
+}
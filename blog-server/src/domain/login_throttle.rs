@@ -0,0 +1,128 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use std::net::IpAddr;
+
+pub struct LockoutPolicy {
+    /// Failures allowed before the first lockout.
+    pub free_attempts: i32,
+    pub base_lockout: TimeDelta,
+    pub max_lockout: TimeDelta,
+    /// Failures older than this are forgotten.
+    pub attempts_window: TimeDelta,
+}
+
+impl LockoutPolicy {
+    /// Lockout doubles with every failure past the free attempts, up to `max_lockout`.
+    pub fn lockout_for(&self, failures: i32) -> Option<TimeDelta> {
+        let over_limit = failures - self.free_attempts;
+        if over_limit < 0 {
+            return None;
+        }
+
+        let factor = 1i32.checked_shl(over_limit.min(30) as u32).unwrap_or(i32::MAX);
+        let lockout = self
+            .base_lockout
+            .checked_mul(factor)
+            .unwrap_or(self.max_lockout);
+        Some(lockout.min(self.max_lockout))
+    }
+}
+
+/// Failed login counter for a single subject: a username or a client address.
+pub struct LoginThrottle {
+    pub key: String,
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl LoginThrottle {
+    pub fn user_key(username: &str) -> String {
+        format!("user:{}", username.to_lowercase())
+    }
+
+    pub fn ip_key(ip: &IpAddr) -> String {
+        format!("ip:{ip}")
+    }
+
+    pub fn new(key: String) -> Self {
+        Self {
+            key,
+            failures: 0,
+            last_failure_at: DateTime::UNIX_EPOCH,
+            locked_until: None,
+        }
+    }
+
+    pub fn retry_after(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        self.locked_until
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| locked_until - now)
+    }
+
+    pub fn register_failure(&mut self, now: DateTime<Utc>, policy: &LockoutPolicy) {
+        if self.last_failure_at + policy.attempts_window <= now {
+            self.failures = 0;
+        }
+
+        self.failures = self.failures.saturating_add(1);
+        self.last_failure_at = now;
+        self.locked_until = policy.lockout_for(self.failures).map(|lockout| now + lockout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> LockoutPolicy {
+        LockoutPolicy {
+            free_attempts: 3,
+            base_lockout: TimeDelta::seconds(10),
+            max_lockout: TimeDelta::seconds(60),
+            attempts_window: TimeDelta::minutes(15),
+        }
+    }
+
+    #[test]
+    fn test_exponential_lockout() {
+        let policy = policy();
+        assert_eq!(policy.lockout_for(2), None);
+        assert_eq!(policy.lockout_for(3), Some(TimeDelta::seconds(10)));
+        assert_eq!(policy.lockout_for(4), Some(TimeDelta::seconds(20)));
+        assert_eq!(policy.lockout_for(5), Some(TimeDelta::seconds(40)));
+        assert_eq!(policy.lockout_for(6), Some(TimeDelta::seconds(60)));
+        assert_eq!(policy.lockout_for(i32::MAX), Some(TimeDelta::seconds(60)));
+    }
+
+    #[test]
+    fn test_register_failure() {
+        let policy = policy();
+        let now = Utc::now();
+        let mut throttle = LoginThrottle::new(LoginThrottle::user_key("Alice"));
+        assert_eq!(throttle.key, "user:alice");
+
+        throttle.register_failure(now, &policy);
+        throttle.register_failure(now, &policy);
+        assert!(throttle.retry_after(now).is_none());
+
+        throttle.register_failure(now, &policy);
+        assert_eq!(throttle.retry_after(now), Some(TimeDelta::seconds(10)));
+        assert!(throttle.retry_after(now + TimeDelta::seconds(10)).is_none());
+    }
+
+    #[test]
+    fn test_failures_expire() {
+        let policy = policy();
+        let now = Utc::now();
+        let mut throttle = LoginThrottle::new(LoginThrottle::ip_key(&"127.0.0.1".parse().unwrap()));
+        for _ in 0..3 {
+            throttle.register_failure(now, &policy);
+        }
+        assert_eq!(throttle.failures, 3);
+
+        throttle.register_failure(now + TimeDelta::minutes(20), &policy);
+        assert_eq!(throttle.failures, 1);
+        assert!(throttle.locked_until.is_none());
+    }
+}
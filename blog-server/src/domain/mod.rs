@@ -1,3 +1,22 @@
+pub mod comment;
+pub mod content_format;
 pub mod error;
+pub mod linked_identity;
+pub mod login_throttle;
+pub mod oidc_login;
+pub mod one_time_token;
+pub mod personal_token;
 pub mod post;
+pub mod post_cursor;
+pub mod post_revision;
+pub mod post_search;
+pub mod post_status;
+pub mod recovery_code;
+pub mod refresh_token;
+pub mod role;
+pub mod scope;
+pub mod session;
+pub mod slug;
+pub mod tag;
 pub mod user;
+pub mod word_diff;
@@ -0,0 +1,69 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::infrastructure::hash::{generate_secret, hash_token};
+
+const STATE_LEN: usize = 32;
+const NONCE_LEN: usize = 16;
+const CODE_VERIFIER_LEN: usize = 32;
+
+/// Login started at the identity provider and not finished yet. The raw `state` goes
+/// through the browser and only its hash is stored; the nonce and the PKCE code verifier
+/// never leave the server.
+pub struct OidcLogin {
+    pub id: i64,
+    pub state_hash: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl OidcLogin {
+    /// Returns the login together with the raw state for the authorization request.
+    pub fn create(id: i64, ttl: TimeDelta) -> (Self, String) {
+        let raw_state = generate_secret(STATE_LEN);
+        let current = Utc::now();
+        let login = Self {
+            id,
+            state_hash: hash_token(&raw_state),
+            nonce: generate_secret(NONCE_LEN),
+            code_verifier: generate_secret(CODE_VERIFIER_LEN),
+            expires_at: current + ttl,
+            created_at: current,
+            used_at: None,
+        };
+        (login, raw_state)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.used_at.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let (login, raw_state) = OidcLogin::create(1, TimeDelta::minutes(10));
+        assert_eq!(login.id, 1);
+        assert_eq!(login.state_hash, hash_token(&raw_state));
+        assert_ne!(login.nonce, login.code_verifier);
+        // RFC 7636 requires 43 to 128 characters.
+        assert!((43..=128).contains(&login.code_verifier.len()));
+        assert!(!login.is_expired());
+        assert!(!login.is_used());
+    }
+
+    #[test]
+    fn test_expired() {
+        let (login, _) = OidcLogin::create(1, TimeDelta::seconds(-1));
+        assert!(login.is_expired());
+    }
+}
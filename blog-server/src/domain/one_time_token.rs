@@ -0,0 +1,65 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::infrastructure::hash::{generate_secret, hash_token};
+
+const ONE_TIME_TOKEN_LEN: usize = 32;
+
+/// Single-use secret such as a password reset or an email verification token.
+/// Only the hash of the token is stored.
+pub struct OneTimeToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl OneTimeToken {
+    /// Returns the new token together with its raw value, which is only handed to the user.
+    pub fn create(id: i64, user_id: i64, ttl: TimeDelta) -> (Self, String) {
+        let raw_token = generate_secret(ONE_TIME_TOKEN_LEN);
+        let current = Utc::now();
+        let token = Self {
+            id,
+            user_id,
+            token_hash: hash_token(&raw_token),
+            expires_at: current + ttl,
+            created_at: current,
+            used_at: None,
+        };
+        (token, raw_token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.used_at.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let (token, raw_token) = OneTimeToken::create(1, 2, TimeDelta::minutes(30));
+        assert_eq!(token.id, 1);
+        assert_eq!(token.user_id, 2);
+        assert_eq!(token.token_hash, hash_token(&raw_token));
+        assert!(!token.is_expired());
+        assert!(!token.is_used());
+    }
+
+    #[test]
+    fn test_expired_and_used() {
+        let (mut token, _) = OneTimeToken::create(1, 2, TimeDelta::seconds(-1));
+        assert!(token.is_expired());
+
+        token.used_at = Some(Utc::now());
+        assert!(token.is_used());
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use super::scope::Scope;
+use crate::infrastructure::hash::{generate_secret, hash_token};
+
+pub const PERSONAL_TOKEN_PREFIX: &str = "blog_pat_";
+const PERSONAL_TOKEN_LEN: usize = 32;
+
+/// Long-lived token for automation. Only the hash is stored, the raw value is shown
+/// to the owner once.
+pub struct PersonalToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl PersonalToken {
+    pub fn create(
+        id: i64,
+        user_id: i64,
+        name: String,
+        scopes: Vec<Scope>,
+        ttl: Option<TimeDelta>,
+    ) -> (Self, String) {
+        let raw_token = format!("{PERSONAL_TOKEN_PREFIX}{}", generate_secret(PERSONAL_TOKEN_LEN));
+        let current = Utc::now();
+        let token = Self {
+            id,
+            user_id,
+            name,
+            token_hash: hash_token(&raw_token),
+            scopes,
+            created_at: current,
+            expires_at: ttl.map(|ttl| current + ttl),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        (token, raw_token)
+    }
+
+    pub fn is_personal_token(raw_token: &str) -> bool {
+        raw_token.starts_with(PERSONAL_TOKEN_PREFIX)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| expires_at > Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let (token, raw_token) =
+            PersonalToken::create(1, 2, "ci".to_string(), vec![Scope::PostsWrite], None);
+        assert!(PersonalToken::is_personal_token(&raw_token));
+        assert!(!PersonalToken::is_personal_token("eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9"));
+        assert_eq!(token.token_hash, hash_token(&raw_token));
+        assert_eq!(token.scopes, vec![Scope::PostsWrite]);
+        assert!(token.expires_at.is_none());
+        assert!(token.is_active());
+    }
+
+    #[test]
+    fn test_expired_and_revoked() {
+        let (mut token, _) =
+            PersonalToken::create(1, 2, "ci".to_string(), vec![], Some(TimeDelta::seconds(-1)));
+        assert!(!token.is_active());
+
+        token.expires_at = None;
+        token.revoked_at = Some(Utc::now());
+        assert!(!token.is_active());
+    }
+}
@@ -1,37 +1,135 @@
 use chrono::{DateTime, Utc};
 
+use super::content_format::ContentFormat;
+use super::post_status::PostStatus;
+use super::{slug, tag};
+
 pub struct Post {
     pub id: i64,
     pub title: String,
+    /// Derived from the title; the repository adds a numeric suffix when it's taken.
+    pub slug: String,
     pub content: String,
+    pub content_format: ContentFormat,
+    /// Sanitized HTML of the content, rendered on save. `None` for posts saved
+    /// before rendering was added, see [`Post::content_html`].
+    pub content_html: Option<String>,
     pub author_id: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub status: PostStatus,
+    /// Publication time, the planned one for a scheduled post.
+    pub published_at: Option<DateTime<Utc>>,
+    /// Incremented on every save, used to detect concurrent edits.
+    pub version: i64,
+    /// Set while the post is in the trash.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Normalized and sorted, see [`tag::normalize_all`].
+    pub tags: Vec<String>,
 }
 
 impl Post {
-    pub fn create(id: i64, title: String, content: String, author_id: i64) -> Self {
+    pub fn create(
+        id: i64,
+        title: String,
+        content: String,
+        author_id: i64,
+        tags: Vec<String>,
+    ) -> Self {
         let current = Utc::now();
+        let content_format = ContentFormat::default();
         Self {
             id,
+            slug: slug::slugify(&title),
             title,
+            content_html: Some(content_format.render(&content)),
             content,
+            content_format,
             author_id,
             created_at: current,
             updated_at: current,
+            status: PostStatus::Published,
+            published_at: Some(current),
+            version: 1,
+            deleted_at: None,
+            tags: tag::normalize_all(tags),
         }
     }
 
-    pub fn update(&mut self, new_title: Option<String>, new_content: Option<String>) {
-        if new_title.is_some() || new_content.is_some() {
-            self.updated_at = Utc::now();
+    pub fn is_published(&self) -> bool {
+        self.status == PostStatus::Published
+    }
+
+    pub fn is_in_trash(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn restore_from_trash(&mut self) {
+        self.deleted_at = None;
+    }
+
+    /// `publish_at` is used only for a scheduled post. A published post keeps its
+    /// original publication time.
+    pub fn set_status(&mut self, status: PostStatus, publish_at: Option<DateTime<Utc>>) {
+        let current = Utc::now();
+        self.published_at = match status {
+            PostStatus::Draft => None,
+            PostStatus::Published if self.is_published() => self.published_at.or(Some(current)),
+            PostStatus::Published => Some(current),
+            PostStatus::Scheduled => publish_at,
+        };
+        if self.status != status {
+            self.status = status;
+            self.updated_at = current;
         }
-        if let Some(title) = new_title {
+    }
+
+    /// Returns whether the title or the content changed.
+    pub fn update(&mut self, new_title: Option<String>, new_content: Option<String>) -> bool {
+        let mut changed = false;
+        if let Some(title) = new_title.filter(|title| *title != self.title) {
+            self.slug = slug::slugify(&title);
             self.title = title;
+            changed = true;
         }
-        if let Some(content) = new_content {
+        if let Some(content) = new_content.filter(|content| *content != self.content) {
+            self.content_html = Some(self.content_format.render(&content));
             self.content = content;
+            changed = true;
+        }
+        if changed {
+            self.updated_at = Utc::now();
+        }
+        changed
+    }
+
+    /// Returns whether the format changed, the content is rendered again then.
+    pub fn set_content_format(&mut self, content_format: ContentFormat) -> bool {
+        if content_format == self.content_format {
+            return false;
+        }
+        self.content_format = content_format;
+        self.content_html = Some(content_format.render(&self.content));
+        self.updated_at = Utc::now();
+        true
+    }
+
+    pub fn content_html(&self) -> String {
+        match &self.content_html {
+            Some(html) => html.clone(),
+            None => self.content_format.render(&self.content),
+        }
+    }
+
+    /// Replaces the tags, returns whether they changed.
+    pub fn set_tags(&mut self, tags: Vec<String>) -> bool {
+        let tags = tag::normalize_all(tags);
+        if tags == self.tags {
+            return false;
         }
+        self.tags = tags;
+        self.updated_at = Utc::now();
+        true
     }
 }
 
@@ -41,17 +139,78 @@ mod tests {
 
     #[test]
     fn test_create() {
-        let post = Post::create(5, "new_title".to_string(), "new_content".to_string(), 6);
+        let post = Post::create(5, "new_title".to_string(), "new_content".to_string(), 6, vec![]);
         assert_eq!(post.id, 5);
         assert_eq!(post.title, "new_title");
+        assert_eq!(post.slug, "new-title");
         assert_eq!(post.content, "new_content");
         assert_eq!(post.author_id, 6);
         assert_eq!(post.created_at, post.updated_at);
+        assert!(post.is_published());
+        assert_eq!(post.published_at, Some(post.created_at));
+        assert_eq!(post.version, 1);
+        assert!(!post.is_in_trash());
+        assert!(post.tags.is_empty());
+    }
+
+    #[test]
+    fn test_set_tags() {
+        let tags = vec!["Rust".to_string(), "web".to_string()];
+        let mut post = Post::create(5, "new_title".to_string(), "new_content".to_string(), 6, tags);
+        assert_eq!(post.tags, ["rust", "web"]);
+        assert_eq!(post.created_at, post.updated_at);
+
+        assert!(!post.set_tags(vec!["web".to_string(), "RUST".to_string()]));
+        assert_eq!(post.updated_at, post.created_at);
+        assert!(post.set_tags(vec!["web".to_string()]));
+        assert_eq!(post.tags, ["web"]);
+        assert!(post.updated_at > post.created_at);
+    }
+
+    #[test]
+    fn test_content_format() {
+        let mut post = Post::create(5, "new_title".to_string(), "*new*".to_string(), 6, vec![]);
+        assert_eq!(post.content_format, ContentFormat::Plain);
+        assert_eq!(post.content_html(), "<p>*new*</p>");
+
+        assert!(!post.set_content_format(ContentFormat::Plain));
+        assert_eq!(post.updated_at, post.created_at);
+        assert!(post.set_content_format(ContentFormat::Markdown));
+        assert_eq!(post.content_html(), "<p><em>new</em></p>\n");
+        assert!(post.updated_at > post.created_at);
+
+        post.update(None, Some("**updated**".to_string()));
+        assert_eq!(post.content_html(), "<p><strong>updated</strong></p>\n");
+
+        post.content_html = None;
+        assert_eq!(post.content_html(), "<p><strong>updated</strong></p>\n");
+    }
+
+    #[test]
+    fn test_set_status() {
+        let mut post = Post::create(5, "new_title".to_string(), "new_content".to_string(), 6, vec![]);
+        post.set_status(PostStatus::Published, None);
+        assert_eq!(post.published_at, Some(post.created_at));
+        assert_eq!(post.updated_at, post.created_at);
+
+        post.set_status(PostStatus::Draft, None);
+        assert!(!post.is_published());
+        assert!(post.published_at.is_none());
+        assert!(post.updated_at > post.created_at);
+
+        let publish_at = Utc::now() + chrono::TimeDelta::hours(1);
+        post.set_status(PostStatus::Scheduled, Some(publish_at));
+        assert_eq!(post.status, PostStatus::Scheduled);
+        assert_eq!(post.published_at, Some(publish_at));
+
+        post.set_status(PostStatus::Published, None);
+        assert!(post.is_published());
+        assert!(post.published_at.unwrap() < publish_at);
     }
 
     #[test]
     fn test_update() {
-        let mut post = Post::create(5, "new_title".to_string(), "new_content".to_string(), 6);
+        let mut post = Post::create(5, "new_title".to_string(), "new_content".to_string(), 6, vec![]);
         post.update(None, None);
 
         assert_eq!(post.id, 5);
@@ -63,12 +222,15 @@ mod tests {
         post.update(Some("updated_title".to_string()), None);
         assert_eq!(post.id, 5);
         assert_eq!(post.title, "updated_title");
+        assert_eq!(post.slug, "updated-title");
         assert_eq!(post.content, "new_content");
         assert_eq!(post.author_id, 6);
         assert!(post.updated_at > post.created_at);
 
         let prev_updated = post.updated_at;
-        post.update(None, Some("updated_content".to_string()));
+        assert!(!post.update(Some("updated_title".to_string()), None));
+        assert_eq!(post.updated_at, prev_updated);
+        assert!(post.update(None, Some("updated_content".to_string())));
         assert_eq!(post.id, 5);
         assert_eq!(post.title, "updated_title");
         assert_eq!(post.content, "updated_content");
@@ -0,0 +1,114 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+
+use super::error::AppError;
+use super::post::Post;
+
+/// Which side of the cursor post a page lies on. Feeds go from the newest post to the
+/// oldest, so `After` pages hold older posts and `Before` pages newer ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    After,
+    Before,
+}
+
+/// Position in a feed ordered by `(updated_at, id)` descending. Clients get it as an
+/// opaque string and only pass it back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostCursor {
+    pub updated_at: DateTime<Utc>,
+    pub id: i64,
+    pub direction: Direction,
+}
+
+impl PostCursor {
+    pub fn after(post: &Post) -> Self {
+        Self {
+            updated_at: post.updated_at,
+            id: post.id,
+            direction: Direction::After,
+        }
+    }
+
+    pub fn before(post: &Post) -> Self {
+        Self {
+            updated_at: post.updated_at,
+            id: post.id,
+            direction: Direction::Before,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let direction = match self.direction {
+            Direction::After => "a",
+            Direction::Before => "b",
+        };
+        let raw = format!(
+            "{direction}:{}:{}",
+            self.updated_at.timestamp_micros(),
+            self.id
+        );
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::InvalidRequest("invalid cursor".to_string());
+        let raw = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+
+        let mut parts = raw.splitn(3, ':');
+        let direction = match parts.next() {
+            Some("a") => Direction::After,
+            Some("b") => Direction::Before,
+            _ => return Err(invalid()),
+        };
+        let micros = parts
+            .next()
+            .and_then(|part| part.parse::<i64>().ok())
+            .ok_or_else(invalid)?;
+        let id = parts
+            .next()
+            .and_then(|part| part.parse::<i64>().ok())
+            .ok_or_else(invalid)?;
+        let updated_at = DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?;
+
+        Ok(Self {
+            updated_at,
+            id,
+            direction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let cursor = PostCursor {
+            updated_at: DateTime::from_timestamp_micros(1_774_000_000_123_456).unwrap(),
+            id: 42,
+            direction: Direction::Before,
+        };
+        let encoded = cursor.encode();
+        assert!(!encoded.contains(':'));
+        assert_eq!(PostCursor::decode(&encoded).unwrap(), cursor);
+
+        let after = PostCursor {
+            direction: Direction::After,
+            ..cursor.clone()
+        };
+        assert_ne!(after.encode(), encoded);
+        assert_eq!(PostCursor::decode(&after.encode()).unwrap(), after);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for cursor in ["", "not base64!", "eDox", &URL_SAFE_NO_PAD.encode("a:12:x")] {
+            assert!(PostCursor::decode(cursor).is_err(), "{cursor}");
+        }
+        assert!(PostCursor::decode(&URL_SAFE_NO_PAD.encode("c:12:3")).is_err());
+        assert!(PostCursor::decode(&URL_SAFE_NO_PAD.encode("a:12")).is_err());
+    }
+}
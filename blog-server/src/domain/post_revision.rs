@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+
+/// A saved version of the title and the content of a post. Revisions of a post are
+/// numbered from 1.
+pub struct PostRevision {
+    pub id: i64,
+    pub post_id: i64,
+    pub revision: i32,
+    pub title: String,
+    pub content: String,
+    /// `None` once the editor's account is deleted.
+    pub editor_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
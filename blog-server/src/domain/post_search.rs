@@ -0,0 +1,36 @@
+use super::content_format::escape_html;
+use super::post::Post;
+
+/// Marks Postgres puts around matched words in a snippet. Control characters are
+/// used so they can't be confused with the HTML-escaped text around them.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+pub struct SearchHit {
+    pub post: Post,
+    pub rank: f32,
+    /// Fragments of the content with raw match marks.
+    pub snippet: String,
+}
+
+/// Escapes the snippet for HTML and turns the match marks into `<mark>` tags.
+pub fn highlight(snippet: &str) -> String {
+    escape_html(snippet)
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let snippet = format!("use {MATCH_START}<b>{MATCH_END} & \"{MATCH_START}tags{MATCH_END}'");
+        assert_eq!(
+            highlight(&snippet),
+            "use <mark>&lt;b&gt;</mark> &amp; &quot;<mark>tags</mark>&#39;"
+        );
+        assert_eq!(highlight("plain text"), "plain text");
+    }
+}
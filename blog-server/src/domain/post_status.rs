@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type, encode::IsNull, error::BoxDynError};
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    #[default]
+    Published,
+    /// Published by the background publisher once `published_at` comes.
+    Scheduled,
+}
+
+impl PostStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Published => "published",
+            PostStatus::Scheduled => "scheduled",
+        }
+    }
+}
+
+impl Display for PostStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for PostStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(PostStatus::Draft),
+            "published" => Ok(PostStatus::Published),
+            "scheduled" => Ok(PostStatus::Scheduled),
+            _ => Err(AppError::InvalidRequest(format!("unknown post status: {s}"))),
+        }
+    }
+}
+
+impl Type<Postgres> for PostStatus {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for PostStatus {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl Decode<'_, Postgres> for PostStatus {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        let status = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(status.parse::<PostStatus>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("draft".parse::<PostStatus>().unwrap(), PostStatus::Draft);
+        assert_eq!("published".parse::<PostStatus>().unwrap(), PostStatus::Published);
+        assert_eq!("scheduled".parse::<PostStatus>().unwrap(), PostStatus::Scheduled);
+        assert!("hidden".parse::<PostStatus>().is_err());
+        assert_eq!(PostStatus::Scheduled.to_string(), "scheduled");
+    }
+}
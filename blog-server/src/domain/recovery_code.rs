@@ -0,0 +1,52 @@
+use crate::infrastructure::hash::{generate_secret, hash_token};
+
+pub const RECOVERY_CODES_COUNT: usize = 10;
+const RECOVERY_CODE_BYTES: usize = 5;
+
+/// Generates a fresh set of recovery codes formatted as `xxxxx-xxxxx`.
+/// Only their hashes are stored, the codes themselves are shown to the user once.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES_COUNT)
+        .map(|_| {
+            let code = generate_secret(RECOVERY_CODE_BYTES);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// Hash of a recovery code as typed by the user: case and separators are ignored.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hash_token(&normalized)
+}
+
+/// Tells recovery codes apart from 6-digit TOTP codes.
+pub fn is_recovery_code(code: &str) -> bool {
+    code.contains('-') || code.trim().len() > 6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODES_COUNT);
+        assert!(codes.iter().all(|code| code.len() == 11 && is_recovery_code(code)));
+        assert_ne!(codes[0], codes[1]);
+        assert!(!is_recovery_code("123456"));
+    }
+
+    #[test]
+    fn test_hash_ignores_formatting() {
+        let hash = hash_recovery_code("abcde-12345");
+        assert_eq!(hash, hash_recovery_code("ABCDE12345"));
+        assert_eq!(hash, hash_recovery_code(" abcde 12345 "));
+        assert_ne!(hash, hash_recovery_code("abcde-12346"));
+    }
+}
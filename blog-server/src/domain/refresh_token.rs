@@ -0,0 +1,79 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::infrastructure::hash::{generate_secret, hash_token};
+
+const REFRESH_TOKEN_LEN: usize = 32;
+const FAMILY_ID_LEN: usize = 16;
+
+pub struct RefreshToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub family_id: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl RefreshToken {
+    /// Returns the new token together with its raw value, which is never stored.
+    pub fn create(
+        id: i64,
+        user_id: i64,
+        family_id: Option<String>,
+        ttl: TimeDelta,
+    ) -> (Self, String) {
+        let raw_token = generate_secret(REFRESH_TOKEN_LEN);
+        let current = Utc::now();
+        let token = Self {
+            id,
+            user_id,
+            family_id: family_id.unwrap_or_else(|| generate_secret(FAMILY_ID_LEN)),
+            token_hash: hash_token(&raw_token),
+            expires_at: current + ttl,
+            created_at: current,
+            used_at: None,
+            revoked_at: None,
+        };
+        (token, raw_token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    pub fn is_consumed(&self) -> bool {
+        self.used_at.is_some() || self.revoked_at.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let (token, raw_token) = RefreshToken::create(1, 2, None, TimeDelta::days(1));
+        assert_eq!(token.id, 1);
+        assert_eq!(token.user_id, 2);
+        assert!(!token.family_id.is_empty());
+        assert_eq!(token.token_hash, hash_token(&raw_token));
+        assert!(!token.is_expired());
+        assert!(!token.is_consumed());
+
+        let (rotated, rotated_raw) =
+            RefreshToken::create(3, 2, Some(token.family_id.clone()), TimeDelta::days(1));
+        assert_eq!(rotated.family_id, token.family_id);
+        assert_ne!(rotated_raw, raw_token);
+    }
+
+    #[test]
+    fn test_expired_and_consumed() {
+        let (mut token, _) = RefreshToken::create(1, 2, None, TimeDelta::seconds(-1));
+        assert!(token.is_expired());
+
+        token.used_at = Some(Utc::now());
+        assert!(token.is_consumed());
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type, encode::IsNull, error::BoxDynError};
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn can_moderate(&self) -> bool {
+        matches!(self, Role::Moderator | Role::Admin)
+    }
+
+    pub fn can_manage_roles(&self) -> bool {
+        matches!(self, Role::Admin)
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(Role::User),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            _ => Err(AppError::InvalidRequest(format!("unknown role: {s}"))),
+        }
+    }
+}
+
+impl Type<Postgres> for Role {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for Role {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl Decode<'_, Postgres> for Role {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        let role = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(role.parse::<Role>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("user".parse::<Role>().unwrap(), Role::User);
+        assert_eq!("moderator".parse::<Role>().unwrap(), Role::Moderator);
+        assert_eq!("admin".parse::<Role>().unwrap(), Role::Admin);
+        assert!("root".parse::<Role>().is_err());
+        assert_eq!(Role::Moderator.to_string(), "moderator");
+    }
+
+    #[test]
+    fn test_permissions() {
+        assert!(!Role::User.can_moderate());
+        assert!(Role::Moderator.can_moderate());
+        assert!(Role::Admin.can_moderate());
+        assert!(!Role::Moderator.can_manage_roles());
+        assert!(Role::Admin.can_manage_roles());
+    }
+}
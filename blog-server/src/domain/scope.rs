@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type, encode::IsNull, error::BoxDynError};
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::error::AppError;
+
+/// Permission carried by a personal access token. Password logins are not scoped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "posts:read")]
+    PostsRead,
+    #[serde(rename = "posts:write")]
+    PostsWrite,
+    #[serde(rename = "comments:write")]
+    CommentsWrite,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::PostsRead => "posts:read",
+            Scope::PostsWrite => "posts:write",
+            Scope::CommentsWrite => "comments:write",
+        }
+    }
+
+    /// Parses requested scopes into a sorted list without repeats.
+    pub fn parse_all(scopes: &[String]) -> Result<Vec<Scope>, AppError> {
+        let mut scopes = scopes
+            .iter()
+            .map(|scope| scope.parse::<Scope>())
+            .collect::<Result<Vec<_>, _>>()?;
+        scopes.sort();
+        scopes.dedup();
+        Ok(scopes)
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "posts:read" => Ok(Scope::PostsRead),
+            "posts:write" => Ok(Scope::PostsWrite),
+            "comments:write" => Ok(Scope::CommentsWrite),
+            _ => Err(AppError::InvalidRequest(format!("unknown scope: {s}"))),
+        }
+    }
+}
+
+impl Type<Postgres> for Scope {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl PgHasArrayType for Scope {
+    fn array_type_info() -> PgTypeInfo {
+        <String as PgHasArrayType>::array_type_info()
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        <String as PgHasArrayType>::array_compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for Scope {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl Decode<'_, Postgres> for Scope {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        let scope = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(scope.parse::<Scope>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("posts:read".parse::<Scope>().unwrap(), Scope::PostsRead);
+        assert_eq!("posts:write".parse::<Scope>().unwrap(), Scope::PostsWrite);
+        assert_eq!("comments:write".parse::<Scope>().unwrap(), Scope::CommentsWrite);
+        assert!("posts:*".parse::<Scope>().is_err());
+        assert_eq!(Scope::PostsWrite.to_string(), "posts:write");
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let requested = ["posts:read", "posts:write", "posts:read"].map(String::from);
+        assert_eq!(
+            Scope::parse_all(&requested).unwrap(),
+            vec![Scope::PostsRead, Scope::PostsWrite]
+        );
+        assert!(Scope::parse_all(&["posts:read".to_string(), "all".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        assert_eq!(serde_json::to_string(&Scope::PostsRead).unwrap(), "\"posts:read\"");
+        let scopes: Vec<Scope> = serde_json::from_str(r#"["posts:write"]"#).unwrap();
+        assert_eq!(scopes, vec![Scope::PostsWrite]);
+    }
+}
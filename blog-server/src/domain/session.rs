@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+
+/// One login of a user. The session lives as long as the chain of refresh tokens
+/// issued from that login (`family_id`), and its id is carried by the access tokens.
+pub struct Session {
+    pub id: i64,
+    pub user_id: i64,
+    pub family_id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,128 @@
+const SLUG_MAX_LEN: usize = 80;
+
+/// Slug of a post without a single letter or digit in the title.
+const FALLBACK: &str = "post";
+
+fn transliterate(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' => "i",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'ю' => "yu",
+        'я' => "ya",
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a",
+        'æ' => "ae",
+        'ç' => "c",
+        'è' | 'é' | 'ê' | 'ë' => "e",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'ñ' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => "o",
+        'ù' | 'ú' | 'û' | 'ü' => "u",
+        'ý' | 'ÿ' => "y",
+        'ß' => "ss",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// Lowercase ASCII slug of a title: Cyrillic and accented Latin letters are
+/// transliterated, everything that's not a letter or a digit becomes a single `-`.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_dash = false;
+    for c in title.chars().flat_map(char::to_lowercase) {
+        let mut buf = [0; 4];
+        let part = if c.is_ascii_alphanumeric() {
+            Some(&*c.encode_utf8(&mut buf))
+        } else {
+            transliterate(c)
+        };
+        match part {
+            // Soft and hard signs don't separate words.
+            Some("") => {}
+            Some(part) => {
+                if pending_dash && !slug.is_empty() {
+                    slug.push('-');
+                }
+                pending_dash = false;
+                slug.push_str(part);
+            }
+            None => pending_dash = true,
+        }
+        if slug.len() >= SLUG_MAX_LEN {
+            break;
+        }
+    }
+    slug.truncate(SLUG_MAX_LEN);
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        FALLBACK.to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// `slug` itself if it's not taken, otherwise the first free `slug-2`, `slug-3`, ...
+pub fn first_free(slug: &str, taken: &[String]) -> String {
+    let is_taken = |candidate: &str| taken.iter().any(|slug| slug == candidate);
+    if !is_taken(slug) {
+        return slug.to_string();
+    }
+    (2..)
+        .map(|n| format!("{slug}-{n}"))
+        .find(|candidate| !is_taken(candidate))
+        .expect("a free suffix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Rust 2024: async -- traits "), "rust-2024-async-traits");
+        assert_eq!(slugify("Привет, мир"), "privet-mir");
+        assert_eq!(slugify("Подъезд и объём"), "podezd-i-obem");
+        assert_eq!(slugify("Crème brûlée"), "creme-brulee");
+        assert_eq!(slugify("?!"), "post");
+        assert_eq!(slugify("日本 Rust"), "rust");
+
+        let long = slugify(&"word ".repeat(40));
+        assert!(long.len() <= SLUG_MAX_LEN);
+        assert!(!long.ends_with('-'));
+    }
+
+    #[test]
+    fn test_first_free() {
+        assert_eq!(first_free("hello", &[]), "hello");
+        let taken = vec!["hello".to_string(), "hello-2".to_string(), "hello-4".to_string()];
+        assert_eq!(first_free("hello", &taken), "hello-3");
+        assert_eq!(first_free("hello-2", &taken), "hello-2-2");
+    }
+}
@@ -0,0 +1,36 @@
+/// Tags are matched case-insensitively, so they're kept trimmed and lowercased.
+pub fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Normalized tags of a post, sorted and without duplicates.
+pub fn normalize_all(names: Vec<String>) -> Vec<String> {
+    let mut tags: Vec<String> = names.iter().map(|name| normalize(name)).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+pub struct TagCount {
+    pub name: String,
+    /// Number of published posts with the tag.
+    pub post_count: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_all() {
+        let tags = vec![
+            " Rust ".to_string(),
+            "web".to_string(),
+            "rust".to_string(),
+            "Async".to_string(),
+        ];
+        assert_eq!(normalize_all(tags), ["async", "rust", "web"]);
+        assert!(normalize_all(Vec::new()).is_empty());
+        assert_eq!(normalize("  Веб "), "веб");
+    }
+}
@@ -5,7 +5,8 @@ use derive_more::Debug;
 use std::fmt::Display;
 
 use super::error::AppError;
-use crate::infrastructure::hash::{hash_password, verify_password};
+use super::role::Role;
+use crate::infrastructure::hash::{hash_password, needs_rehash, verify_password};
 
 #[derive(Debug)]
 pub struct User {
@@ -15,6 +16,12 @@ pub struct User {
     #[debug(skip)]
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
+    pub role: Role,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    #[debug(skip)]
+    pub totp_secret_enc: Option<String>,
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    pub totp_last_step: Option<i64>,
 }
 
 impl User {
@@ -38,9 +45,39 @@ impl User {
             email,
             password_hash,
             created_at: Utc::now(),
+            role: Role::User,
+            email_verified_at: None,
+            totp_secret_enc: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
         })
     }
 
+    pub fn set_password(&mut self, password: &str) -> Result<(), AppError> {
+        self.password_hash = match hash_password(password) {
+            Ok(val) => val,
+            Err(e) => {
+                error!("{e}");
+                return Err(AppError::InternalError("Can't hash password".to_string()));
+            }
+        };
+
+        Ok(())
+    }
+
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+
+    pub fn is_two_factor_enabled(&self) -> bool {
+        self.totp_enabled_at.is_some()
+    }
+
+    /// The hash was made with outdated parameters and should be replaced after login.
+    pub fn is_password_hash_outdated(&self) -> bool {
+        needs_rehash(&self.password_hash)
+    }
+
     pub fn verify_user(&self, password: &str) -> Result<(), AppError> {
         if let Err(e) = verify_password(password, &self.password_hash) {
             info!("Attempt to log with wrong credentials: {e} for user {}", self.username);
@@ -69,6 +106,9 @@ mod tests {
         assert_eq!(user.username, "name");
         assert_eq!(user.email, "mail");
         assert!(!user.password_hash.is_empty());
+        assert_eq!(user.role, Role::User);
+        assert!(!user.is_email_verified());
+        assert!(!user.is_two_factor_enabled());
     }
 
     #[test]
@@ -85,5 +125,14 @@ mod tests {
             }
         }
     }
-}
 
+    #[test]
+    fn test_set_password() {
+        let mut user = User::create(5, "name".to_string(), "mail".to_string(), "pass".to_string()).unwrap();
+        let old_hash = user.password_hash.clone();
+        user.set_password("new_pass").unwrap();
+        assert_ne!(user.password_hash, old_hash);
+        assert!(user.verify_user("new_pass").is_ok());
+        assert!(user.verify_user("pass").is_err());
+    }
+}
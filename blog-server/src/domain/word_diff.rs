@@ -0,0 +1,184 @@
+use serde::Serialize;
+
+/// Above this number of compared token pairs the changed middle part is reported as
+/// replaced as a whole, so that huge posts don't exhaust memory.
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl DiffOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiffOp::Equal => "equal",
+            DiffOp::Insert => "insert",
+            DiffOp::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffChunk {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Splits the text into words and the whitespace between them, so that the diff
+/// keeps the original spacing.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if prev_space.is_some_and(|prev| prev != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        prev_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn push(chunks: &mut Vec<DiffChunk>, op: DiffOp, token: &str) {
+    match chunks.last_mut() {
+        Some(last) if last.op == op => last.text.push_str(token),
+        _ => chunks.push(DiffChunk {
+            op,
+            text: token.to_string(),
+        }),
+    }
+}
+
+/// Word-level diff turning `old` into `new`.
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffChunk> {
+    let old = tokenize(old);
+    let new = tokenize(new);
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut chunks = Vec::new();
+    for token in &old[..prefix] {
+        push(&mut chunks, DiffOp::Equal, token);
+    }
+
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if (n + 1) * (m + 1) > MAX_TABLE_SIZE {
+        for token in old_mid {
+            push(&mut chunks, DiffOp::Delete, token);
+        }
+        for token in new_mid {
+            push(&mut chunks, DiffOp::Insert, token);
+        }
+    } else {
+        // lcs[i * (m + 1) + j] is the longest common subsequence of old_mid[i..] and new_mid[j..].
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                push(&mut chunks, DiffOp::Equal, old_mid[i]);
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                push(&mut chunks, DiffOp::Delete, old_mid[i]);
+                i += 1;
+            } else {
+                push(&mut chunks, DiffOp::Insert, new_mid[j]);
+                j += 1;
+            }
+        }
+    }
+
+    for token in &old[old.len() - suffix..] {
+        push(&mut chunks, DiffOp::Equal, token);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(op: DiffOp, text: &str) -> DiffChunk {
+        DiffChunk {
+            op,
+            text: text.to_string(),
+        }
+    }
+
+    fn apply(chunks: &[DiffChunk], op: DiffOp) -> String {
+        chunks
+            .iter()
+            .filter(|chunk| chunk.op == DiffOp::Equal || chunk.op == op)
+            .map(|chunk| chunk.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("a  bc\nd "), ["a", "  ", "bc", "\n", "d", " "]);
+        assert!(tokenize("").is_empty());
+    }
+
+    #[test]
+    fn test_word_diff() {
+        let diff = word_diff("the quick brown fox", "the slow brown dog");
+        assert_eq!(
+            diff,
+            [
+                chunk(DiffOp::Equal, "the "),
+                chunk(DiffOp::Delete, "quick"),
+                chunk(DiffOp::Insert, "slow"),
+                chunk(DiffOp::Equal, " brown "),
+                chunk(DiffOp::Delete, "fox"),
+                chunk(DiffOp::Insert, "dog"),
+            ]
+        );
+        assert_eq!(word_diff("same text", "same text"), [chunk(DiffOp::Equal, "same text")]);
+        assert_eq!(word_diff("", "new"), [chunk(DiffOp::Insert, "new")]);
+    }
+
+    #[test]
+    fn test_diff_restores_both_sides() {
+        let old = "Привет, мир! Это первая версия поста.\nВторая строка.";
+        let new = "Привет мир! Это вторая версия поста.\n\nВторая строка и хвост.";
+        let diff = word_diff(old, new);
+        assert_eq!(apply(&diff, DiffOp::Delete), old);
+        assert_eq!(apply(&diff, DiffOp::Insert), new);
+    }
+
+    #[test]
+    fn test_large_diff_falls_back_to_replacement() {
+        let old = (0..3000).map(|i| format!("a{i}")).collect::<Vec<_>>().join(" ");
+        let new = (0..3000).map(|i| format!("b{i}")).collect::<Vec<_>>().join(" ");
+        let diff = word_diff(&old, &new);
+        assert_eq!(apply(&diff, DiffOp::Delete), old);
+        assert_eq!(apply(&diff, DiffOp::Insert), new);
+    }
+}
@@ -1,5 +1,7 @@
 use anyhow::Result;
 use std::env;
+use std::str::FromStr;
+
 pub struct DbConfig {
     pub name: String,
     pub user: String,
@@ -16,12 +18,94 @@ pub struct LogConfig {
 
 pub struct SecretConfig {
     pub jwt_secret: String,
+    pub access_token_ttl_min: i64,
+    pub refresh_token_ttl_days: i64,
+    pub revocation_sync_sec: u64,
+    pub jwt_keys_manifest: Option<String>,
+    pub password_reset_ttl_min: i64,
+    pub totp_encryption_key: Option<String>,
+    pub password_pepper: Option<String>,
+}
+
+pub struct PasswordHashConfig {
+    pub algorithm: String,
+    pub version: u32,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+pub struct AccountConfig {
+    pub allow_unverified_posting: bool,
+    pub email_verification_ttl_hours: i64,
+    pub login_free_attempts: i32,
+    pub login_ip_free_attempts: i32,
+    pub login_lockout_base_sec: i64,
+    pub login_lockout_max_sec: i64,
+    pub login_attempts_window_min: i64,
+    pub totp_issuer: String,
+    pub login_challenge_ttl_min: i64,
+}
+
+pub struct MailConfig {
+    pub backend: String,
+    pub from: String,
+    pub public_url: String,
+    pub file_path: Option<String>,
+    /// Development only: log whole mails, tokens included, when there is no mail file.
+    pub log_body: bool,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_starttls: bool,
+    pub smtp_user: Option<String>,
+    pub smtp_pass: Option<String>,
+}
+
+pub struct OidcConfig {
+    /// Login with the identity provider is disabled without it.
+    pub issuer: Option<String>,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: Option<String>,
+    pub scopes: String,
+    pub login_ttl_min: i64,
+}
+
+pub struct PostConfig {
+    /// How often scheduled posts are checked for publication.
+    pub publish_check_sec: u64,
+    /// How long deleted posts stay restorable.
+    pub trash_retention_days: i64,
+    /// Postgres text search configuration new posts are indexed with.
+    pub search_language: String,
+}
+
+pub struct CommentConfig {
+    /// How long after posting a comment its author can still edit it.
+    pub edit_window_min: i64,
 }
 
 pub struct Config {
     pub db_config: DbConfig,
     pub log_config: LogConfig,
     pub secret_config: SecretConfig,
+    pub password_hash_config: PasswordHashConfig,
+    pub account_config: AccountConfig,
+    pub mail_config: MailConfig,
+    pub oidc_config: OidcConfig,
+    pub post_config: PostConfig,
+    pub comment_config: CommentConfig,
+}
+
+fn env_or<T>(key: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match env::var(key) {
+        Ok(val) => Ok(val.parse::<T>()?),
+        Err(_) => Ok(default),
+    }
 }
 
 impl Config {
@@ -40,7 +124,60 @@ impl Config {
                 level: env::var("LOG_LEVEL")?,
             },
             secret_config: SecretConfig {
-                jwt_secret: env::var("JWT_SECRET")?,
+                jwt_secret: env_or("JWT_SECRET", String::new())?,
+                access_token_ttl_min: env_or("JWT_ACCESS_TTL_MIN", 15)?,
+                refresh_token_ttl_days: env_or("JWT_REFRESH_TTL_DAYS", 30)?,
+                revocation_sync_sec: env_or("REVOCATION_SYNC_SEC", 30)?,
+                jwt_keys_manifest: env::var("JWT_KEYS_MANIFEST").ok(),
+                password_reset_ttl_min: env_or("PASSWORD_RESET_TTL_MIN", 30)?,
+                totp_encryption_key: env::var("TOTP_ENCRYPTION_KEY").ok(),
+                password_pepper: env::var("PASSWORD_PEPPER").ok(),
+            },
+            password_hash_config: PasswordHashConfig {
+                algorithm: env_or("PASSWORD_HASH_ALGORITHM", "argon2id".to_string())?,
+                version: env_or("PASSWORD_HASH_VERSION", 19)?,
+                memory_kib: env_or("PASSWORD_HASH_MEMORY_KIB", 19 * 1024)?,
+                iterations: env_or("PASSWORD_HASH_ITERATIONS", 2)?,
+                parallelism: env_or("PASSWORD_HASH_PARALLELISM", 1)?,
+            },
+            account_config: AccountConfig {
+                allow_unverified_posting: env_or("ALLOW_UNVERIFIED_POSTING", false)?,
+                email_verification_ttl_hours: env_or("EMAIL_VERIFICATION_TTL_HOURS", 24)?,
+                login_free_attempts: env_or("LOGIN_FREE_ATTEMPTS", 5)?,
+                login_ip_free_attempts: env_or("LOGIN_IP_FREE_ATTEMPTS", 20)?,
+                login_lockout_base_sec: env_or("LOGIN_LOCKOUT_BASE_SEC", 30)?,
+                login_lockout_max_sec: env_or("LOGIN_LOCKOUT_MAX_SEC", 3600)?,
+                login_attempts_window_min: env_or("LOGIN_ATTEMPTS_WINDOW_MIN", 15)?,
+                totp_issuer: env_or("TOTP_ISSUER", "blog".to_string())?,
+                login_challenge_ttl_min: env_or("LOGIN_CHALLENGE_TTL_MIN", 5)?,
+            },
+            mail_config: MailConfig {
+                backend: env_or("MAIL_BACKEND", "file".to_string())?,
+                from: env_or("MAIL_FROM", "blog@localhost".to_string())?,
+                public_url: env_or("PUBLIC_URL", "http://127.0.0.1:3000".to_string())?,
+                file_path: env::var("MAIL_FILE").ok(),
+                log_body: env_or("MAIL_LOG_BODY", false)?,
+                smtp_host: env_or("SMTP_HOST", String::new())?,
+                smtp_port: env_or("SMTP_PORT", 587)?,
+                smtp_starttls: env_or("SMTP_STARTTLS", true)?,
+                smtp_user: env::var("SMTP_USER").ok(),
+                smtp_pass: env::var("SMTP_PASS").ok(),
+            },
+            oidc_config: OidcConfig {
+                issuer: env::var("OIDC_ISSUER").ok(),
+                client_id: env_or("OIDC_CLIENT_ID", String::new())?,
+                client_secret: env::var("OIDC_CLIENT_SECRET").ok(),
+                redirect_url: env::var("OIDC_REDIRECT_URL").ok(),
+                scopes: env_or("OIDC_SCOPES", "openid email profile".to_string())?,
+                login_ttl_min: env_or("OIDC_LOGIN_TTL_MIN", 10)?,
+            },
+            post_config: PostConfig {
+                publish_check_sec: env_or("SCHEDULED_PUBLISH_CHECK_SEC", 30)?,
+                trash_retention_days: env_or("POST_TRASH_RETENTION_DAYS", 30)?,
+                search_language: env_or("SEARCH_LANGUAGE", "simple".to_string())?,
+            },
+            comment_config: CommentConfig {
+                edit_window_min: env_or("COMMENT_EDIT_WINDOW_MIN", 15)?,
             },
         })
     }
@@ -68,6 +205,17 @@ mod tests {
         set_env("DB_MIN_CONN", "5");
         set_env("LOG_LEVEL", "info");
         set_env("JWT_SECRET", "secret");
+        set_env("JWT_ACCESS_TTL_MIN", "5");
+        set_env("JWT_REFRESH_TTL_DAYS", "7");
+        set_env("REVOCATION_SYNC_SEC", "10");
+        set_env("PASSWORD_RESET_TTL_MIN", "20");
+        set_env("ALLOW_UNVERIFIED_POSTING", "true");
+        set_env("LOGIN_FREE_ATTEMPTS", "3");
+        set_env("PASSWORD_HASH_ITERATIONS", "3");
+        set_env("MAIL_BACKEND", "smtp");
+        set_env("SMTP_HOST", "smtp.example.com");
+        set_env("OIDC_ISSUER", "https://idp.example.com");
+        set_env("OIDC_CLIENT_ID", "blog");
 
         let config = Config::from_environment().unwrap();
         assert_eq!(config.db_config.name, "db");
@@ -79,5 +227,41 @@ mod tests {
         assert_eq!(config.db_config.min_connections, 5);
         assert_eq!(config.log_config.level, "info");
         assert_eq!(config.secret_config.jwt_secret, "secret");
+        assert_eq!(config.secret_config.access_token_ttl_min, 5);
+        assert_eq!(config.secret_config.refresh_token_ttl_days, 7);
+        assert_eq!(config.secret_config.revocation_sync_sec, 10);
+        assert_eq!(config.secret_config.password_reset_ttl_min, 20);
+        assert!(config.account_config.allow_unverified_posting);
+        assert_eq!(config.password_hash_config.algorithm, "argon2id");
+        assert_eq!(config.password_hash_config.version, 19);
+        assert_eq!(config.password_hash_config.memory_kib, 19 * 1024);
+        assert_eq!(config.password_hash_config.iterations, 3);
+        assert_eq!(config.account_config.email_verification_ttl_hours, 24);
+        assert_eq!(config.account_config.login_free_attempts, 3);
+        assert_eq!(config.account_config.login_lockout_max_sec, 3600);
+        assert_eq!(config.account_config.totp_issuer, "blog");
+        assert_eq!(config.account_config.login_challenge_ttl_min, 5);
+        assert!(!config.mail_config.log_body);
+        assert_eq!(config.mail_config.backend, "smtp");
+        assert_eq!(config.mail_config.smtp_host, "smtp.example.com");
+        assert_eq!(config.mail_config.smtp_port, 587);
+        assert_eq!(
+            config.oidc_config.issuer.as_deref(),
+            Some("https://idp.example.com")
+        );
+        assert_eq!(config.oidc_config.client_id, "blog");
+        assert_eq!(config.oidc_config.scopes, "openid email profile");
+        assert_eq!(config.oidc_config.login_ttl_min, 10);
+        assert_eq!(config.post_config.publish_check_sec, 30);
+        assert_eq!(config.post_config.trash_retention_days, 30);
+        assert_eq!(config.post_config.search_language, "simple");
+        assert_eq!(config.comment_config.edit_window_min, 15);
+    }
+
+    #[test]
+    fn test_env_or() {
+        assert_eq!(env_or("BLOG_TEST_UNSET_VAR", 42).unwrap(), 42);
+        set_env("BLOG_TEST_NUMBER_VAR", "not_a_number");
+        assert!(env_or("BLOG_TEST_NUMBER_VAR", 42).is_err());
     }
-}
\ No newline at end of file
+}
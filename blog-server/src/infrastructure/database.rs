@@ -13,7 +13,7 @@ pub async fn create_pool(db_config: &DbConfig) -> Result<PgPool, sqlx::Error> {
         .max_connections(db_config.max_connections)
         .min_connections(db_config.min_connections)
         .acquire_timeout(std::time::Duration::from_secs(5))
-        .connect(&db_url_from_params(&db_config))
+        .connect(&db_url_from_params(db_config))
         .await?;
 
     Ok(pool)
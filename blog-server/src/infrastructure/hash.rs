@@ -1,17 +1,146 @@
-use argon2::Argon2;
+use argon2::password_hash::rand_core::RngCore;
 use argon2::password_hash::{Error, PasswordHasher, PasswordHash, PasswordVerifier, SaltString, rand_core::OsRng};
+use argon2::{Algorithm, Argon2, KeyId, Params, ParamsBuilder, Version};
+use sha2::{Digest, Sha256};
+
+use std::sync::{LazyLock, OnceLock};
+
+use super::config::PasswordHashConfig;
+
+const PEPPER_ID_LEN: usize = 4;
+
+/// Algorithm, version and cost parameters new password hashes are made with.
+/// Hashes store their own parameters, so the old ones stay verifiable after a change.
+#[derive(Default)]
+pub struct PasswordHashing {
+    algorithm: Algorithm,
+    version: Version,
+    params: Params,
+    pepper: Option<Vec<u8>>,
+}
+
+impl PasswordHashing {
+    /// With a pepper, hashes get its short id in the `keyid` parameter. Hashes made
+    /// without a pepper stay verifiable and are reported by `needs_rehash`.
+    pub fn new(config: &PasswordHashConfig, pepper: Option<&str>) -> Result<Self, argon2::Error> {
+        let mut builder = ParamsBuilder::new();
+        builder
+            .m_cost(config.memory_kib)
+            .t_cost(config.iterations)
+            .p_cost(config.parallelism);
+        if let Some(pepper) = pepper {
+            builder.keyid(KeyId::new(&pepper_id(pepper))?);
+        }
+
+        Ok(Self {
+            algorithm: config.algorithm.parse()?,
+            version: Version::try_from(config.version)?,
+            params: builder.build()?,
+            pepper: pepper.map(|pepper| pepper.as_bytes().to_vec()),
+        })
+    }
+
+    fn argon2(&self) -> Result<Argon2<'_>, Error> {
+        match &self.pepper {
+            Some(pepper) => Ok(Argon2::new_with_secret(
+                pepper,
+                self.algorithm,
+                self.version,
+                self.params.clone(),
+            )?),
+            None => Ok(Argon2::new(self.algorithm, self.version, self.params.clone())),
+        }
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = self.argon2()?.hash_password(password.as_bytes(), &salt)?;
+        Ok(password_hash.to_string())
+    }
+
+    pub fn verify(&self, password: &str, hash: &str) -> Result<(), Error> {
+        let parsed_hash = PasswordHash::new(hash)?;
+        let keyid = Params::try_from(&parsed_hash)?.keyid().to_vec();
+        let argon2 = if keyid.is_empty() {
+            Argon2::default()
+        } else if keyid == self.params.keyid() {
+            self.argon2()?
+        } else {
+            // Made with a pepper the server doesn't have (any more).
+            return Err(Error::Password);
+        };
+        argon2.verify_password(password.as_bytes(), &parsed_hash)
+    }
+
+    /// Whether the hash was made with other parameters than the current ones.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed_hash) = PasswordHash::new(hash) else {
+            return true;
+        };
+        let Ok(params) = Params::try_from(&parsed_hash) else {
+            return true;
+        };
+
+        parsed_hash.algorithm != self.algorithm.ident()
+            || parsed_hash.version != Some(self.version.into())
+            || params.m_cost() != self.params.m_cost()
+            || params.t_cost() != self.params.t_cost()
+            || params.p_cost() != self.params.p_cost()
+            || params.keyid() != self.params.keyid()
+    }
+}
+
+fn pepper_id(pepper: &str) -> [u8; PEPPER_ID_LEN] {
+    let digest = Sha256::digest(pepper.as_bytes());
+    let mut id = [0u8; PEPPER_ID_LEN];
+    id.copy_from_slice(&digest[..PEPPER_ID_LEN]);
+    id
+}
+
+static PASSWORD_HASHING: OnceLock<PasswordHashing> = OnceLock::new();
+
+/// Has to be called at startup, before the first password is hashed.
+pub fn init_password_hashing(hashing: PasswordHashing) -> anyhow::Result<()> {
+    if PASSWORD_HASHING.set(hashing).is_err() {
+        anyhow::bail!("password hashing is already initialized");
+    }
+    Ok(())
+}
+
+fn password_hashing() -> &'static PasswordHashing {
+    PASSWORD_HASHING.get_or_init(PasswordHashing::default)
+}
 
 pub fn hash_password(password: &str) -> Result<String, Error> {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2.hash_password(password.as_bytes(), &salt)?;
-    Ok(password_hash.to_string())
+    password_hashing().hash(password)
 }
 
 pub fn verify_password(password: &str, hash: &str) -> Result<(), Error> {
-    let argon2 = Argon2::default();
-    let parsed_hash = PasswordHash::new(hash)?;
-    argon2.verify_password(password.as_bytes(), &parsed_hash)
+    password_hashing().verify(password, hash)
+}
+
+pub fn needs_rehash(hash: &str) -> bool {
+    password_hashing().needs_rehash(hash)
+}
+
+static DUMMY_HASH: LazyLock<String> = LazyLock::new(|| {
+    hash_password(&generate_secret(16)).expect("Can't hash dummy password")
+});
+
+/// Spends the same time as `verify_password` for a real account, so that a failed
+/// login doesn't reveal whether the user exists.
+pub fn dummy_verify_password(password: &str) {
+    let _ = verify_password(password, &DUMMY_HASH);
+}
+
+pub fn generate_secret(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
 #[cfg(test)]
@@ -23,4 +152,78 @@ mod tests {
         let hash = hash_password("pass").unwrap();
         assert!(verify_password("pass", &hash).is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generate_secret() {
+        let first = generate_secret(32);
+        let second = generate_secret(32);
+        assert_eq!(first.len(), 64);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_hash_token() {
+        let hash = hash_token("token");
+        assert_eq!(hash, hash_token("token"));
+        assert_ne!(hash, hash_token("other_token"));
+        assert_eq!(hash.len(), 64);
+    }
+
+    fn hash_config(memory_kib: u32, iterations: u32) -> PasswordHashConfig {
+        PasswordHashConfig {
+            algorithm: "argon2id".to_string(),
+            version: 19,
+            memory_kib,
+            iterations,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let hashing = PasswordHashing::new(&hash_config(1024, 1), None).unwrap();
+        let hash = hashing.hash("pass").unwrap();
+        assert!(!hashing.needs_rehash(&hash));
+
+        let stronger = PasswordHashing::new(&hash_config(2048, 2), None).unwrap();
+        assert!(stronger.needs_rehash(&hash));
+        assert!(stronger.verify("pass", &hash).is_ok());
+        assert!(stronger.needs_rehash("not a hash"));
+    }
+
+    #[test]
+    fn test_pepper() {
+        let plain = PasswordHashing::new(&hash_config(1024, 1), None).unwrap();
+        let peppered = PasswordHashing::new(&hash_config(1024, 1), Some("pepper")).unwrap();
+        let other = PasswordHashing::new(&hash_config(1024, 1), Some("other")).unwrap();
+
+        let hash = peppered.hash("pass").unwrap();
+        assert!(peppered.verify("pass", &hash).is_ok());
+        assert!(peppered.verify("wrong", &hash).is_err());
+        assert!(plain.verify("pass", &hash).is_err());
+        assert!(other.verify("pass", &hash).is_err());
+        assert!(!peppered.needs_rehash(&hash));
+
+        let plain_hash = plain.hash("pass").unwrap();
+        assert!(peppered.verify("pass", &plain_hash).is_ok());
+        assert!(peppered.needs_rehash(&plain_hash));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut config = hash_config(1024, 1);
+        config.algorithm = "md5".to_string();
+        assert!(PasswordHashing::new(&config, None).is_err());
+
+        let mut config = hash_config(1024, 1);
+        config.version = 17;
+        assert!(PasswordHashing::new(&config, None).is_err());
+        assert!(PasswordHashing::new(&hash_config(1, 1), None).is_err());
+    }
+
+    #[test]
+    fn test_dummy_verify_password() {
+        dummy_verify_password("pass");
+        assert!(verify_password("pass", &DUMMY_HASH).is_err());
+    }
+}
@@ -1,38 +1,94 @@
-use chrono::{TimeDelta, Utc};
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use chrono::{DateTime, TimeDelta, Utc};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Header, Validation, decode, decode_header, encode};
 use serde::{Deserialize, Serialize};
-use tracing::error;
+use tracing::{error, info};
 
 use super::config::SecretConfig;
+use super::hash::generate_secret;
+use super::jwt_keys::{KeyRing, SigningKey};
 use crate::domain::error::AppError;
+use crate::domain::personal_token::PersonalToken;
+use crate::domain::role::Role;
+use crate::domain::scope::Scope;
+use crate::domain::user::User;
 
-#[derive(Serialize, Deserialize)]
+const JTI_LEN: usize = 16;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub username: String,
     pub email: String,
     pub id: i64,
+    #[serde(default)]
+    pub role: Role,
+    pub jti: String,
     exp: usize,
+    /// Present only for personal access tokens, which are limited to these scopes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<Scope>>,
+    /// Session the access token was issued for; tokens of revoked sessions are rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<i64>,
+}
+
+impl Claims {
+    pub fn for_personal_token(user: &User, token: &PersonalToken) -> Self {
+        Self {
+            username: user.username.clone(),
+            email: user.email.clone(),
+            id: user.id,
+            role: user.role,
+            jti: format!("pat-{}", token.id),
+            exp: token
+                .expires_at
+                .map_or(0, |expires_at| expires_at.timestamp() as usize),
+            scopes: Some(token.scopes.clone()),
+            sid: None,
+        }
+    }
+
+    pub fn is_personal_token(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.exp as i64, 0).unwrap_or_default()
+    }
 }
 
 pub struct JwtService {
-    enc_key: EncodingKey,
-    dec_key: DecodingKey,
-    header: Header,
-    validation: Validation,
+    key_ring: KeyRing,
+    access_token_ttl: TimeDelta,
 }
 
 impl JwtService {
-    pub fn new(secret_config: &SecretConfig) -> Self {
-        let header = Header::new(Algorithm::HS256);
-        let validation = Validation::new(Algorithm::HS256);
-        let enc_key = EncodingKey::from_secret(secret_config.jwt_secret.as_bytes());
-        let dec_key = DecodingKey::from_secret(secret_config.jwt_secret.as_bytes());
-        Self {
-            enc_key,
-            dec_key,
-            header,
-            validation,
-        }
+    pub fn new(secret_config: &SecretConfig) -> anyhow::Result<Self> {
+        let access_token_ttl = TimeDelta::minutes(secret_config.access_token_ttl_min);
+        let key_ring = match &secret_config.jwt_keys_manifest {
+            Some(manifest) => {
+                info!("Loading jwt signing keys from {manifest}");
+                KeyRing::from_manifest(manifest, access_token_ttl)?
+            }
+            None => {
+                if secret_config.jwt_secret.is_empty() {
+                    anyhow::bail!("Either JWT_KEYS_MANIFEST or JWT_SECRET must be set");
+                }
+                KeyRing::new(vec![SigningKey::hmac(&secret_config.jwt_secret)], access_token_ttl)?
+            }
+        };
+        Ok(Self {
+            key_ring,
+            access_token_ttl,
+        })
+    }
+
+    pub fn access_token_ttl(&self) -> TimeDelta {
+        self.access_token_ttl
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        self.key_ring.jwks(Utc::now())
     }
 
     pub fn generate_token(
@@ -40,8 +96,10 @@ impl JwtService {
         username: &str,
         email: &str,
         user_id: i64,
+        role: Role,
+        session_id: Option<i64>,
     ) -> Result<String, AppError> {
-        let expiration = if let Some(val) = Utc::now().checked_add_signed(TimeDelta::hours(24)) {
+        let expiration = if let Some(val) = Utc::now().checked_add_signed(self.access_token_ttl) {
             val.timestamp()
         } else {
             return Err(AppError::InternalError(
@@ -52,9 +110,26 @@ impl JwtService {
             username: username.to_string(),
             email: email.to_string(),
             id: user_id,
+            role,
+            jti: generate_secret(JTI_LEN),
             exp: expiration as usize,
+            scopes: None,
+            sid: session_id,
+        };
+        let Some(signing_key) = self.key_ring.signing_key(Utc::now()) else {
+            error!("No active jwt signing key");
+            return Err(AppError::InternalError(
+                "Can't create jwt token".to_string(),
+            ));
+        };
+        let Some(enc_key) = signing_key.enc_key.as_ref() else {
+            return Err(AppError::InternalError(
+                "Can't create jwt token".to_string(),
+            ));
         };
-        let token = match encode(&self.header, &claims, &self.enc_key) {
+        let mut header = Header::new(signing_key.algorithm);
+        header.kid = Some(signing_key.kid.clone());
+        let token = match encode(&header, &claims, enc_key) {
             Ok(val) => val,
             Err(e) => {
                 error!("{e}");
@@ -67,7 +142,12 @@ impl JwtService {
     }
 
     pub fn verify_token(&self, token: &str) -> Option<Claims> {
-        match decode::<Claims>(token, &self.dec_key, &self.validation) {
+        let header = decode_header(token).ok()?;
+        let key = self
+            .key_ring
+            .verification_key(header.kid.as_deref(), Utc::now())?;
+        let validation = Validation::new(key.algorithm);
+        match decode::<Claims>(token, &key.dec_key, &validation) {
             Ok(data) => Some(data.claims),
             Err(_) => None,
         }
@@ -78,13 +158,53 @@ impl JwtService {
 mod tests {
     use super::*;
 
+    fn secret_config(jwt_secret: &str, jwt_keys_manifest: Option<&str>) -> SecretConfig {
+        SecretConfig {
+            jwt_secret: jwt_secret.to_string(),
+            access_token_ttl_min: 15,
+            refresh_token_ttl_days: 30,
+            revocation_sync_sec: 30,
+            jwt_keys_manifest: jwt_keys_manifest.map(str::to_string),
+            password_reset_ttl_min: 30,
+            totp_encryption_key: None,
+            password_pepper: None,
+        }
+    }
+
     #[test]
     fn test_generate_verify() {
-        let jwt_service = JwtService::new(&SecretConfig { jwt_secret: "secret".to_string() });
-        let token = jwt_service.generate_token("user", "mail", 5).unwrap();
+        let jwt_service = JwtService::new(&secret_config("secret", None))
+        .unwrap();
+        let token = jwt_service
+            .generate_token("user", "mail", 5, Role::Moderator, Some(7))
+            .unwrap();
         let claims = jwt_service.verify_token(&token).unwrap();
         assert_eq!(claims.username, "user");
         assert_eq!(claims.email, "mail");
         assert_eq!(claims.id, 5);
+        assert_eq!(claims.role, Role::Moderator);
+        assert_eq!(claims.sid, Some(7));
+        assert!(claims.expires_at() > Utc::now());
+
+        let other_token = jwt_service.generate_token("user", "mail", 5, Role::Moderator, None).unwrap();
+        let other_claims = jwt_service.verify_token(&other_token).unwrap();
+        assert_ne!(claims.jti, other_claims.jti);
+    }
+
+    #[test]
+    fn test_generate_verify_with_manifest() {
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/src/infrastructure/testdata/keys.json");
+        let jwt_service = JwtService::new(&secret_config("", Some(manifest)))
+        .unwrap();
+        let token = jwt_service.generate_token("user", "mail", 5, Role::Moderator, None).unwrap();
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("2026-02-ed25519"));
+        assert_eq!(jwt_service.verify_token(&token).unwrap().id, 5);
+        assert_eq!(jwt_service.jwks().keys.len(), 1);
+
+        let hmac_service = JwtService::new(&secret_config("secret", None))
+        .unwrap();
+        let hmac_token = hmac_service.generate_token("user", "mail", 5, Role::Moderator, None).unwrap();
+        assert!(jwt_service.verify_token(&hmac_token).is_none());
     }
 }
@@ -0,0 +1,299 @@
+use anyhow::{Context, Result, bail};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, TimeDelta, Utc};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use rsa::RsaPublicKey;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use serde::Deserialize;
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const LEGACY_KID: &str = "hs256";
+
+/// One entry of the `JWT_KEYS_MANIFEST` file. Key paths are resolved relative to the
+/// manifest, and `private_key` may be omitted for keys that are only used for verification.
+#[derive(Deserialize)]
+pub struct KeyManifestEntry {
+    pub kid: String,
+    pub algorithm: String,
+    pub private_key: Option<PathBuf>,
+    pub public_key: PathBuf,
+    pub active_from: DateTime<Utc>,
+}
+
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub enc_key: Option<EncodingKey>,
+    pub dec_key: DecodingKey,
+    jwk: Option<Jwk>,
+    active_from: DateTime<Utc>,
+}
+
+fn is_rsa(algorithm: Algorithm) -> bool {
+    matches!(
+        algorithm,
+        Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512
+    )
+}
+
+fn key_algorithm(algorithm: Algorithm) -> Result<KeyAlgorithm> {
+    let key_algorithm = match algorithm {
+        Algorithm::RS256 => KeyAlgorithm::RS256,
+        Algorithm::RS384 => KeyAlgorithm::RS384,
+        Algorithm::RS512 => KeyAlgorithm::RS512,
+        Algorithm::PS256 => KeyAlgorithm::PS256,
+        Algorithm::PS384 => KeyAlgorithm::PS384,
+        Algorithm::PS512 => KeyAlgorithm::PS512,
+        Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+        _ => bail!("Unsupported jwt signing algorithm: {algorithm:?}"),
+    };
+    Ok(key_algorithm)
+}
+
+fn public_jwk(kid: &str, algorithm: Algorithm, public_pem: &[u8]) -> Result<Jwk> {
+    let params = if is_rsa(algorithm) {
+        let pem = std::str::from_utf8(public_pem)?;
+        let public_key = RsaPublicKey::from_public_key_pem(pem)?;
+        AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+        })
+    } else {
+        let dec_key = DecodingKey::from_ed_pem(public_pem)?;
+        AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(dec_key.as_bytes()),
+        })
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm(algorithm)?),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: params,
+    })
+}
+
+impl SigningKey {
+    pub fn hmac(secret: &str) -> Self {
+        Self {
+            kid: LEGACY_KID.to_string(),
+            algorithm: Algorithm::HS256,
+            enc_key: Some(EncodingKey::from_secret(secret.as_bytes())),
+            dec_key: DecodingKey::from_secret(secret.as_bytes()),
+            jwk: None,
+            active_from: DateTime::UNIX_EPOCH,
+        }
+    }
+
+    pub fn from_manifest_entry(entry: &KeyManifestEntry, base_dir: &Path) -> Result<Self> {
+        let algorithm = Algorithm::from_str(&entry.algorithm)?;
+        key_algorithm(algorithm)?;
+
+        let public_path = base_dir.join(&entry.public_key);
+        let public_pem = std::fs::read(&public_path)
+            .with_context(|| format!("Can't read public key {}", public_path.display()))?;
+        let dec_key = if is_rsa(algorithm) {
+            DecodingKey::from_rsa_pem(&public_pem)?
+        } else {
+            DecodingKey::from_ed_pem(&public_pem)?
+        };
+
+        let enc_key = match &entry.private_key {
+            Some(private_key) => {
+                let private_path = base_dir.join(private_key);
+                let private_pem = std::fs::read(&private_path).with_context(|| {
+                    format!("Can't read private key {}", private_path.display())
+                })?;
+                let enc_key = if is_rsa(algorithm) {
+                    EncodingKey::from_rsa_pem(&private_pem)?
+                } else {
+                    EncodingKey::from_ed_pem(&private_pem)?
+                };
+                Some(enc_key)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            kid: entry.kid.clone(),
+            algorithm,
+            enc_key,
+            dec_key,
+            jwk: Some(public_jwk(&entry.kid, algorithm, &public_pem)?),
+            active_from: entry.active_from,
+        })
+    }
+}
+
+/// Set of signing keys ordered by activation time. The newest active key signs new
+/// tokens; a key stays valid for verification until its successor has been active for
+/// `retire_after`, so that tokens it signed can expire naturally.
+pub struct KeyRing {
+    keys: Vec<SigningKey>,
+    retire_after: TimeDelta,
+}
+
+impl KeyRing {
+    pub fn new(mut keys: Vec<SigningKey>, retire_after: TimeDelta) -> Result<Self> {
+        if keys.is_empty() {
+            bail!("Jwt key ring is empty");
+        }
+        keys.sort_by_key(|key| key.active_from);
+        Ok(Self { keys, retire_after })
+    }
+
+    pub fn from_manifest(path: &str, retire_after: TimeDelta) -> Result<Self> {
+        let manifest = std::fs::read_to_string(path)
+            .with_context(|| format!("Can't read jwt keys manifest {path}"))?;
+        let entries: Vec<KeyManifestEntry> = serde_json::from_str(&manifest)?;
+        let base_dir = Path::new(path).parent().unwrap_or(Path::new("."));
+        let keys = entries
+            .iter()
+            .map(|entry| SigningKey::from_manifest_entry(entry, base_dir))
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(keys, retire_after)
+    }
+
+    fn is_retired(&self, idx: usize, now: DateTime<Utc>) -> bool {
+        self.keys[idx + 1..]
+            .iter()
+            .find(|key| key.enc_key.is_some())
+            .is_some_and(|successor| successor.active_from + self.retire_after <= now)
+    }
+
+    pub fn signing_key(&self, now: DateTime<Utc>) -> Option<&SigningKey> {
+        self.keys
+            .iter()
+            .rev()
+            .find(|key| key.active_from <= now && key.enc_key.is_some())
+    }
+
+    pub fn verification_key(&self, kid: Option<&str>, now: DateTime<Utc>) -> Option<&SigningKey> {
+        let kid = kid.unwrap_or(LEGACY_KID);
+        self.keys
+            .iter()
+            .enumerate()
+            .find(|(idx, key)| key.kid == kid && key.active_from <= now && !self.is_retired(*idx, now))
+            .map(|(_, key)| key)
+    }
+
+    /// Public keys other services need to verify our tokens, including keys scheduled
+    /// for future activation.
+    pub fn jwks(&self, now: DateTime<Utc>) -> JwkSet {
+        let keys = self
+            .keys
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.is_retired(*idx, now))
+            .filter_map(|(_, key)| key.jwk.clone())
+            .collect();
+        JwkSet { keys }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testdata_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/infrastructure/testdata")
+    }
+
+    fn entry(kid: &str, algorithm: &str, file: &str, active_from: DateTime<Utc>) -> KeyManifestEntry {
+        KeyManifestEntry {
+            kid: kid.to_string(),
+            algorithm: algorithm.to_string(),
+            private_key: Some(PathBuf::from(format!("{file}.pem"))),
+            public_key: PathBuf::from(format!("{file}.pub.pem")),
+            active_from,
+        }
+    }
+
+    #[test]
+    fn test_load_keys() {
+        let now = Utc::now();
+        let rsa = SigningKey::from_manifest_entry(
+            &entry("rsa", "RS256", "rsa", now),
+            &testdata_dir(),
+        )
+        .unwrap();
+        assert_eq!(rsa.algorithm, Algorithm::RS256);
+        assert!(rsa.enc_key.is_some());
+        assert!(matches!(rsa.jwk.as_ref().unwrap().algorithm, AlgorithmParameters::RSA(_)));
+
+        let ed = SigningKey::from_manifest_entry(
+            &entry("ed", "EdDSA", "ed25519", now),
+            &testdata_dir(),
+        )
+        .unwrap();
+        assert_eq!(ed.algorithm, Algorithm::EdDSA);
+        assert!(matches!(
+            ed.jwk.as_ref().unwrap().algorithm,
+            AlgorithmParameters::OctetKeyPair(_)
+        ));
+
+        assert!(
+            SigningKey::from_manifest_entry(&entry("hs", "HS256", "rsa", now), &testdata_dir())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rotation_schedule() {
+        let now = Utc::now();
+        let keys = [
+            entry("old", "EdDSA", "ed25519", now - TimeDelta::days(60)),
+            entry("current", "RS256", "rsa", now - TimeDelta::days(30)),
+            entry("recent", "EdDSA", "ed25519", now - TimeDelta::minutes(5)),
+            entry("next", "RS256", "rsa", now + TimeDelta::days(30)),
+        ]
+        .iter()
+        .map(|entry| SigningKey::from_manifest_entry(entry, &testdata_dir()).unwrap())
+        .collect();
+        let ring = KeyRing::new(keys, TimeDelta::minutes(15)).unwrap();
+
+        assert_eq!(ring.signing_key(now).unwrap().kid, "recent");
+        assert!(ring.verification_key(Some("old"), now).is_none());
+        assert!(ring.verification_key(Some("current"), now).is_some());
+        assert!(ring.verification_key(Some("next"), now).is_none());
+        assert!(ring.verification_key(Some("unknown"), now).is_none());
+
+        let kids: Vec<String> = ring
+            .jwks(now)
+            .keys
+            .into_iter()
+            .filter_map(|jwk| jwk.common.key_id)
+            .collect();
+        assert_eq!(kids, vec!["current", "recent", "next"]);
+
+        let later = now + TimeDelta::minutes(20);
+        assert!(ring.verification_key(Some("current"), later).is_none());
+    }
+
+    #[test]
+    fn test_legacy_hmac() {
+        let ring = KeyRing::new(vec![SigningKey::hmac("secret")], TimeDelta::minutes(15)).unwrap();
+        let now = Utc::now();
+        assert_eq!(ring.signing_key(now).unwrap().algorithm, Algorithm::HS256);
+        assert!(ring.verification_key(None, now).is_some());
+        assert!(ring.jwks(now).keys.is_empty());
+    }
+}
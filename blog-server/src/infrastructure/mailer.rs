@@ -0,0 +1,198 @@
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use lettre::message::{Mailbox, header::ContentType};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info};
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::config::MailConfig;
+use crate::domain::error::AppError;
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), AppError>;
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &MailConfig) -> Result<Self> {
+        if config.smtp_host.is_empty() {
+            bail!("SMTP_HOST is required for smtp mail backend");
+        }
+
+        let mut builder = if config.smtp_starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+        }
+        .port(config.smtp_port);
+
+        if let (Some(user), Some(pass)) = (&config.smtp_user, &config.smtp_pass) {
+            builder = builder.credentials(Credentials::new(user.clone(), pass.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config.from.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), AppError> {
+        let to = mail
+            .to
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::InvalidRequest(format!("bad email address: {e}")))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(mail.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body)
+            .map_err(|e| {
+                error!("{e}");
+                AppError::InternalError("Can't build mail".to_string())
+            })?;
+
+        if let Err(e) = self.transport.send(message).await {
+            error!("{e}");
+            return Err(AppError::InternalError("Can't send mail".to_string()));
+        }
+
+        info!("Mail sent to {}", mail.to);
+        Ok(())
+    }
+}
+
+/// Offline backend: appends mails to a file. Without a file mails are dropped, and
+/// only the recipient and subject are logged unless `log_body` is set: the bodies
+/// carry password reset and verification tokens.
+pub struct FileMailer {
+    path: Option<PathBuf>,
+    log_body: bool,
+}
+
+impl FileMailer {
+    pub fn new(path: Option<PathBuf>, log_body: bool) -> Self {
+        Self { path, log_body }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<(), AppError> {
+        let Some(path) = &self.path else {
+            if self.log_body {
+                info!("Mail to {}: {}\n{}", mail.to, mail.subject, mail.body);
+            } else {
+                info!(
+                    "Mail to {}: {} (not delivered, MAIL_FILE is not set)",
+                    mail.to, mail.subject
+                );
+            }
+            return Ok(());
+        };
+
+        let content = format!(
+            "To: {}\nSubject: {}\n\n{}\n\n",
+            mail.to, mail.subject, mail.body
+        );
+        let written = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(content.as_bytes()).await?;
+            file.flush().await
+        };
+
+        if let Err(e) = written.await {
+            error!("Can't write mail to {}: {e}", path.display());
+            return Err(AppError::InternalError("Can't send mail".to_string()));
+        }
+
+        info!("Mail to {} written to {}", mail.to, path.display());
+        Ok(())
+    }
+}
+
+pub fn create_mailer(config: &MailConfig) -> Result<Arc<dyn Mailer>> {
+    let mailer: Arc<dyn Mailer> = match config.backend.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(config)?),
+        "file" => Arc::new(FileMailer::new(
+            config.file_path.as_ref().map(PathBuf::from),
+            config.log_body,
+        )),
+        backend => bail!("Unknown mail backend: {backend}"),
+    };
+    Ok(mailer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_mailer() {
+        let path = std::env::temp_dir().join(format!("blog-mail-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mailer = FileMailer::new(Some(path.clone()), false);
+        let mail = Mail {
+            to: "user@example.com".to_string(),
+            subject: "Subject".to_string(),
+            body: "Body text".to_string(),
+        };
+        mailer.send(mail.clone()).await.unwrap();
+        mailer.send(mail).await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.matches("To: user@example.com").count(), 2);
+        assert!(content.contains("Subject: Subject"));
+        assert!(content.contains("Body text"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_mailer() {
+        let mut config = MailConfig {
+            backend: "file".to_string(),
+            from: "blog@localhost".to_string(),
+            public_url: String::new(),
+            file_path: None,
+            log_body: false,
+            smtp_host: String::new(),
+            smtp_port: 587,
+            smtp_starttls: true,
+            smtp_user: None,
+            smtp_pass: None,
+        };
+        assert!(create_mailer(&config).is_ok());
+
+        config.backend = "smtp".to_string();
+        assert!(create_mailer(&config).is_err());
+        config.smtp_host = "localhost".to_string();
+        assert!(create_mailer(&config).is_ok());
+
+        config.backend = "pigeon".to_string();
+        assert!(create_mailer(&config).is_err());
+    }
+}
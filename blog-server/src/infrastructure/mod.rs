@@ -2,24 +2,118 @@ pub mod config;
 pub mod database;
 pub mod hash;
 pub mod jwt;
+pub mod jwt_keys;
 pub mod logging;
+pub mod mailer;
+pub mod oidc;
+pub mod revocation;
+pub mod secret_box;
+pub mod totp;
 
 use anyhow::Result;
 use dotenv::dotenv;
 
+use chrono::TimeDelta;
+
 use std::sync::Arc;
+use std::time::Duration;
 
-use super::application::{auth_service::AuthService, blog_service::BlogService};
-use super::data::{post_repository::PostRepository, user_repository::UserRepository};
+use super::application::{
+    account_service::AccountService, auth_service::AuthService, blog_service::BlogService,
+    comment_service::CommentService,
+    email_verification_service::EmailVerificationService, login_guard::LoginGuard,
+    oidc_service::OidcService, password_reset_service::PasswordResetService,
+    personal_token_service::PersonalTokenService, session_service::SessionService,
+    two_factor_service::TwoFactorService,
+};
+use super::data::{
+    comment_repository::CommentRepository,
+    email_verification_repository::EmailVerificationRepository,
+    linked_identity_repository::LinkedIdentityRepository,
+    login_challenge_repository::LoginChallengeRepository,
+    login_throttle_repository::LoginThrottleRepository,
+    oidc_login_repository::OidcLoginRepository,
+    password_reset_repository::PasswordResetRepository,
+    personal_token_repository::PersonalTokenRepository, post_repository::PostRepository,
+    post_revision_repository::PostRevisionRepository,
+    recovery_code_repository::RecoveryCodeRepository,
+    refresh_token_repository::RefreshTokenRepository,
+    revoked_token_repository::RevokedTokenRepository, session_repository::SessionRepository,
+    tag_repository::TagRepository, user_repository::UserRepository,
+};
+use super::domain::personal_token::PersonalToken;
 use config::Config;
 use database::{create_pool, run_migrations};
-use jwt::JwtService;
+use jwt::{Claims, JwtService};
 use logging::init_logging;
+use mailer::create_mailer;
+use oidc::OidcClient;
+use secret_box::SecretBox;
+
 pub struct AppState {
     pub config: Config,
     pub jwt_service: Arc<JwtService>,
     pub auth_service: Arc<AuthService>,
     pub blog_service: Arc<BlogService>,
+    pub comment_service: Arc<CommentService>,
+    pub password_reset_service: Arc<PasswordResetService>,
+    pub email_verification_service: Arc<EmailVerificationService>,
+    pub two_factor_service: Arc<TwoFactorService>,
+    pub personal_token_service: Arc<PersonalTokenService>,
+    pub account_service: Arc<AccountService>,
+    pub oidc_service: Arc<OidcService>,
+    pub session_service: Arc<SessionService>,
+}
+
+impl AppState {
+    /// Accepts both access tokens and personal access tokens.
+    pub async fn authenticate(&self, token: &str) -> Option<Claims> {
+        if PersonalToken::is_personal_token(token) {
+            return self.personal_token_service.verify_token(token).await;
+        }
+        let claims = self.auth_service.verify_access_token(token)?;
+        if let Some(session_id) = claims.sid {
+            self.session_service.touch(session_id).await;
+        }
+        Some(claims)
+    }
+}
+
+fn spawn_auth_maintenance(
+    auth_service: Arc<AuthService>,
+    oidc_service: Arc<OidcService>,
+    period: Duration,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = auth_service.sync_revocations().await {
+                tracing::error!("Can't sync token revocations: {e}");
+            }
+            if let Err(e) = auth_service.purge_login_throttles().await {
+                tracing::error!("Can't purge login throttles: {e}");
+            }
+            if let Err(e) = oidc_service.purge_expired_logins().await {
+                tracing::error!("Can't purge OIDC logins: {e}");
+            }
+        }
+    });
+}
+
+fn spawn_post_maintenance(blog_service: Arc<BlogService>, period: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = blog_service.publish_scheduled().await {
+                tracing::error!("Can't publish scheduled posts: {e}");
+            }
+            if let Err(e) = blog_service.purge_trash().await {
+                tracing::error!("Can't purge deleted posts: {e}");
+            }
+        }
+    });
 }
 
 pub async fn init() -> Result<AppState> {
@@ -31,15 +125,144 @@ pub async fn init() -> Result<AppState> {
     tracing::info!("Run migrations...");
     run_migrations(&db_pool).await?;
     tracing::info!("Migration finished");
-    let jwt_service = Arc::new(JwtService::new(&config.secret_config));
+    let jwt_service = Arc::new(JwtService::new(&config.secret_config)?);
     let post_repo = Arc::new(PostRepository::new(db_pool.clone()));
+    let revision_repo = Arc::new(PostRevisionRepository::new(db_pool.clone()));
+    let tag_repo = Arc::new(TagRepository::new(db_pool.clone()));
+    let comment_repo = Arc::new(CommentRepository::new(db_pool.clone()));
     let user_repo = Arc::new(UserRepository::new(db_pool.clone()));
-    let auth_service = Arc::new(AuthService::new(jwt_service.clone(), user_repo.clone()));
-    let blog_service = Arc::new(BlogService::new(post_repo.clone()));
+    let refresh_repo = Arc::new(RefreshTokenRepository::new(db_pool.clone()));
+    let revoked_repo = Arc::new(RevokedTokenRepository::new(db_pool.clone()));
+    let reset_repo = Arc::new(PasswordResetRepository::new(db_pool.clone()));
+    let verification_repo = Arc::new(EmailVerificationRepository::new(db_pool.clone()));
+    let throttle_repo = Arc::new(LoginThrottleRepository::new(db_pool.clone()));
+    let login_guard = Arc::new(LoginGuard::new(throttle_repo.clone(), &config.account_config));
+    let recovery_repo = Arc::new(RecoveryCodeRepository::new(db_pool.clone()));
+    let challenge_repo = Arc::new(LoginChallengeRepository::new(db_pool.clone()));
+    let personal_token_repo = Arc::new(PersonalTokenRepository::new(db_pool.clone()));
+    let identity_repo = Arc::new(LinkedIdentityRepository::new(db_pool.clone()));
+    let oidc_login_repo = Arc::new(OidcLoginRepository::new(db_pool.clone()));
+    let session_repo = Arc::new(SessionRepository::new(db_pool.clone()));
+    let password_hashing = hash::PasswordHashing::new(
+        &config.password_hash_config,
+        config.secret_config.password_pepper.as_deref(),
+    )
+    .map_err(|e| anyhow::anyhow!("Invalid password hash config: {e}"))?;
+    hash::init_password_hashing(password_hashing)?;
+    hash::dummy_verify_password("");
+    let mailer = create_mailer(&config.mail_config)?;
+    let email_verification_service = Arc::new(EmailVerificationService::new(
+        user_repo.clone(),
+        verification_repo.clone(),
+        mailer.clone(),
+        TimeDelta::hours(config.account_config.email_verification_ttl_hours),
+        config.mail_config.public_url.clone(),
+    ));
+    let secret_box = match &config.secret_config.totp_encryption_key {
+        Some(key) => Some(SecretBox::new(key)?),
+        None => {
+            tracing::warn!("TOTP_ENCRYPTION_KEY is not set, two-factor enrolment is disabled");
+            None
+        }
+    };
+    let two_factor_service = Arc::new(TwoFactorService::new(
+        user_repo.clone(),
+        recovery_repo.clone(),
+        challenge_repo.clone(),
+        secret_box,
+        config.account_config.totp_issuer.clone(),
+        TimeDelta::minutes(config.account_config.login_challenge_ttl_min),
+    ));
+    let session_service = Arc::new(SessionService::new(
+        session_repo.clone(),
+        refresh_repo.clone(),
+        jwt_service.access_token_ttl(),
+    ));
+    let auth_service = Arc::new(AuthService::new(
+        jwt_service.clone(),
+        user_repo.clone(),
+        refresh_repo.clone(),
+        revoked_repo.clone(),
+        email_verification_service.clone(),
+        login_guard.clone(),
+        two_factor_service.clone(),
+        session_service.clone(),
+        TimeDelta::days(config.secret_config.refresh_token_ttl_days),
+    ));
+    auth_service.sync_revocations().await?;
+    let blog_service = Arc::new(BlogService::new(
+        post_repo.clone(),
+        revision_repo.clone(),
+        tag_repo.clone(),
+        user_repo.clone(),
+        config.account_config.allow_unverified_posting,
+        TimeDelta::days(config.post_config.trash_retention_days),
+        config.post_config.search_language.clone(),
+    ));
+    let comment_service = Arc::new(CommentService::new(
+        comment_repo.clone(),
+        post_repo.clone(),
+        user_repo.clone(),
+        config.account_config.allow_unverified_posting,
+        TimeDelta::minutes(config.comment_config.edit_window_min),
+    ));
+    let password_reset_service = Arc::new(PasswordResetService::new(
+        user_repo.clone(),
+        reset_repo.clone(),
+        session_service.clone(),
+        mailer.clone(),
+        TimeDelta::minutes(config.secret_config.password_reset_ttl_min),
+    ));
+    let personal_token_service = Arc::new(PersonalTokenService::new(
+        user_repo.clone(),
+        personal_token_repo.clone(),
+    ));
+    let account_service = Arc::new(AccountService::new(
+        user_repo.clone(),
+        reset_repo.clone(),
+        auth_service.clone(),
+        session_service.clone(),
+        email_verification_service.clone(),
+        login_guard.clone(),
+    ));
+    let oidc_client = match &config.oidc_config.issuer {
+        Some(issuer) => {
+            let redirect_url = config.oidc_config.redirect_url.clone().unwrap_or_else(|| {
+                format!("{}/api/auth/oidc/callback", config.mail_config.public_url)
+            });
+            Some(OidcClient::new(&config.oidc_config, issuer, redirect_url)?)
+        }
+        None => None,
+    };
+    let oidc_service = Arc::new(OidcService::new(
+        user_repo.clone(),
+        identity_repo.clone(),
+        oidc_login_repo.clone(),
+        auth_service.clone(),
+        oidc_client,
+        TimeDelta::minutes(config.oidc_config.login_ttl_min),
+    ));
+    spawn_auth_maintenance(
+        auth_service.clone(),
+        oidc_service.clone(),
+        Duration::from_secs(config.secret_config.revocation_sync_sec),
+    );
+    spawn_post_maintenance(
+        blog_service.clone(),
+        Duration::from_secs(config.post_config.publish_check_sec),
+    );
     Ok(AppState {
         config,
         jwt_service,
         auth_service,
         blog_service,
+        comment_service,
+        password_reset_service,
+        email_verification_service,
+        two_factor_service,
+        personal_token_service,
+        account_service,
+        oidc_service,
+        session_service,
     })
 }
@@ -0,0 +1,544 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use reqwest::Url;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+use tracing::{error, info};
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use super::config::OidcConfig;
+use crate::domain::error::AppError;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Code challenge for the `S256` PKCE method.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Checks that the callback came back to the browser that started the login, which
+/// keeps the state in a cookie. Without it anyone could start a login, finish it at the
+/// provider as themselves and have a victim open the callback url.
+pub fn check_state_binding(state: &str, browser_state: Option<&str>) -> Result<(), AppError> {
+    let bound = browser_state.is_some_and(|browser_state| {
+        Sha256::digest(browser_state.as_bytes()) == Sha256::digest(state.as_bytes())
+    });
+    if !bound {
+        info!("OIDC callback without the state cookie of its login");
+        return Err(AppError::Unauthorized(
+            "login was started in another browser".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Clone)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResp {
+    id_token: Option<String>,
+}
+
+/// Claims of a verified ID token that the blog cares about.
+#[derive(Deserialize, Debug)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+}
+
+/// Relying party side of the OpenID Connect authorization code flow. The provider
+/// metadata is discovered on first use, the signing keys are refetched whenever a
+/// token names a key that isn't known yet.
+pub struct OidcClient {
+    issuer: String,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_url: String,
+    scopes: String,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    jwks: RwLock<JwkSet>,
+}
+
+impl OidcClient {
+    pub fn new(config: &OidcConfig, issuer: &str, redirect_url: String) -> anyhow::Result<Self> {
+        if config.client_id.is_empty() {
+            anyhow::bail!("OIDC_CLIENT_ID is required when OIDC_ISSUER is set");
+        }
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            // The token endpoint must answer directly, redirects only matter for the browser.
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        Ok(Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            redirect_url,
+            scopes: config.scopes.clone(),
+            http,
+            metadata: OnceCell::new(),
+            jwks: RwLock::new(JwkSet { keys: Vec::new() }),
+        })
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, AppError> {
+        let resp = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status());
+        match resp {
+            Ok(resp) => resp.json::<T>().await.map_err(provider_error),
+            Err(e) => Err(provider_error(e)),
+        }
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, AppError> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!("{}/.well-known/openid-configuration", self.issuer);
+                let metadata: ProviderMetadata = self.get_json(&url).await?;
+                if metadata.issuer.trim_end_matches('/') != self.issuer {
+                    error!("Identity provider reports issuer {}", metadata.issuer);
+                    return Err(AppError::InternalError("identity provider error".to_string()));
+                }
+                info!("Discovered identity provider {}", self.issuer);
+                Ok(metadata)
+            })
+            .await
+    }
+
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str,
+    ) -> Result<String, AppError> {
+        let metadata = self.metadata().await?;
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", &self.client_id),
+                ("redirect_uri", &self.redirect_url),
+                ("scope", &self.scopes),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        );
+        match url {
+            Ok(url) => Ok(url.to_string()),
+            Err(e) => {
+                error!("Invalid authorization endpoint: {e}");
+                Err(AppError::InternalError("identity provider error".to_string()))
+            }
+        }
+    }
+
+    /// Redeems the authorization code and returns the raw ID token.
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str) -> Result<String, AppError> {
+        let metadata = self.metadata().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_url),
+            ("code_verifier", code_verifier),
+        ];
+        let mut req = self.http.post(&metadata.token_endpoint);
+        match &self.client_secret {
+            Some(secret) => req = req.basic_auth(&self.client_id, Some(secret)),
+            None => form.push(("client_id", &self.client_id)),
+        }
+
+        let resp = match req.form(&form).send().await {
+            Ok(resp) => resp,
+            Err(e) => return Err(provider_error(e)),
+        };
+        if resp.status().is_client_error() {
+            info!("Identity provider rejected authorization code: {}", resp.status());
+            return Err(AppError::Unauthorized("invalid authorization code".to_string()));
+        }
+        let token_resp: TokenResp = match resp.error_for_status() {
+            Ok(resp) => resp.json().await.map_err(provider_error)?,
+            Err(e) => return Err(provider_error(e)),
+        };
+
+        token_resp.id_token.ok_or_else(|| {
+            error!("Identity provider didn't return an ID token");
+            AppError::InternalError("identity provider error".to_string())
+        })
+    }
+
+    fn cached_key(&self, kid: &str) -> Option<Result<DecodingKey, jsonwebtoken::errors::Error>> {
+        let jwks = self.jwks.read().unwrap_or_else(|e| e.into_inner());
+        jwks.find(kid).map(DecodingKey::from_jwk)
+    }
+
+    async fn decoding_key(&self, kid: &str) -> Result<DecodingKey, AppError> {
+        if self.cached_key(kid).is_none() {
+            let metadata = self.metadata().await?;
+            let jwks: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+            *self.jwks.write().unwrap_or_else(|e| e.into_inner()) = jwks;
+        }
+
+        match self.cached_key(kid) {
+            Some(Ok(key)) => Ok(key),
+            Some(Err(e)) => {
+                error!("Unusable identity provider key {kid}: {e}");
+                Err(AppError::InternalError("identity provider error".to_string()))
+            }
+            None => {
+                info!("ID token signed with unknown key {kid}");
+                Err(AppError::Unauthorized("invalid id token".to_string()))
+            }
+        }
+    }
+
+    pub async fn verify_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims, AppError> {
+        let invalid = |reason: String| {
+            info!("Rejected ID token: {reason}");
+            AppError::Unauthorized("invalid id token".to_string())
+        };
+
+        let header = decode_header(id_token).map_err(|e| invalid(e.to_string()))?;
+        // Symmetric algorithms would let anyone with the client secret forge tokens.
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(invalid(format!("algorithm {:?}", header.alg)));
+        }
+        let kid = header.kid.ok_or_else(|| invalid("no key id".to_string()))?;
+        let key = self.decoding_key(&kid).await?;
+
+        let metadata = self.metadata().await?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.client_id]);
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| invalid(e.to_string()))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(invalid("nonce mismatch".to_string()));
+        }
+        Ok(claims)
+    }
+}
+
+fn provider_error(e: reqwest::Error) -> AppError {
+    error!("Identity provider request failed: {e}");
+    AppError::InternalError("identity provider error".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::jwt_keys::{KeyManifestEntry, KeyRing, SigningKey};
+    use actix_web::{App, HttpResponse, HttpServer, web};
+    use chrono::{TimeDelta, Utc};
+    use jsonwebtoken::{Header, encode};
+    use serde::Serialize;
+
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    const CLIENT_ID: &str = "blog";
+    const CLIENT_SECRET: &str = "blog-secret";
+
+    struct PendingCode {
+        nonce: String,
+        code_challenge: String,
+    }
+
+    /// Minimal identity provider: the authorize endpoint approves every request.
+    struct MockProvider {
+        issuer: String,
+        keys: KeyRing,
+        codes: Mutex<HashMap<String, PendingCode>>,
+    }
+
+    #[derive(Serialize)]
+    struct MockIdToken {
+        iss: String,
+        sub: String,
+        aud: String,
+        exp: i64,
+        iat: i64,
+        nonce: String,
+        email: String,
+        email_verified: bool,
+        preferred_username: String,
+    }
+
+    #[derive(Deserialize)]
+    struct AuthorizeReq {
+        client_id: String,
+        redirect_uri: String,
+        state: String,
+        nonce: String,
+        code_challenge: String,
+        code_challenge_method: String,
+    }
+
+    #[derive(Deserialize)]
+    struct TokenReq {
+        grant_type: String,
+        code: String,
+        code_verifier: String,
+    }
+
+    async fn discovery(provider: web::Data<MockProvider>) -> HttpResponse {
+        HttpResponse::Ok().json(serde_json::json!({
+            "issuer": provider.issuer,
+            "authorization_endpoint": format!("{}/authorize", provider.issuer),
+            "token_endpoint": format!("{}/token", provider.issuer),
+            "jwks_uri": format!("{}/jwks", provider.issuer),
+        }))
+    }
+
+    async fn jwks(provider: web::Data<MockProvider>) -> HttpResponse {
+        HttpResponse::Ok().json(provider.keys.jwks(Utc::now()))
+    }
+
+    async fn authorize(
+        provider: web::Data<MockProvider>,
+        req: web::Query<AuthorizeReq>,
+    ) -> HttpResponse {
+        if req.client_id != CLIENT_ID || req.code_challenge_method != "S256" {
+            return HttpResponse::BadRequest().finish();
+        }
+        let code = crate::infrastructure::hash::generate_secret(8);
+        provider.codes.lock().unwrap().insert(
+            code.clone(),
+            PendingCode {
+                nonce: req.nonce.clone(),
+                code_challenge: req.code_challenge.clone(),
+            },
+        );
+        let location =
+            Url::parse_with_params(&req.redirect_uri, &[("code", &code), ("state", &req.state)])
+                .unwrap();
+        HttpResponse::Found()
+            .insert_header(("Location", location.to_string()))
+            .finish()
+    }
+
+    async fn token(
+        provider: web::Data<MockProvider>,
+        http_req: actix_web::HttpRequest,
+        req: web::Form<TokenReq>,
+    ) -> HttpResponse {
+        let expected_auth = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(format!("{CLIENT_ID}:{CLIENT_SECRET}"))
+        );
+        let auth = http_req.headers().get("Authorization").and_then(|v| v.to_str().ok());
+        if auth != Some(expected_auth.as_str()) {
+            return HttpResponse::Unauthorized().json(serde_json::json!({"error": "invalid_client"}));
+        }
+
+        let mut codes = provider.codes.lock().unwrap();
+        let valid = req.grant_type == "authorization_code"
+            && codes
+                .get(&req.code)
+                .is_some_and(|pending| pending.code_challenge == pkce_challenge(&req.code_verifier));
+        if !valid {
+            return HttpResponse::BadRequest().json(serde_json::json!({"error": "invalid_grant"}));
+        }
+        let pending = codes.remove(&req.code).unwrap();
+
+        let now = Utc::now();
+        let key = provider.keys.signing_key(now).unwrap();
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        let claims = MockIdToken {
+            iss: provider.issuer.clone(),
+            sub: "staff-1".to_string(),
+            aud: CLIENT_ID.to_string(),
+            exp: (now + TimeDelta::minutes(5)).timestamp(),
+            iat: now.timestamp(),
+            nonce: pending.nonce,
+            email: "staff@example.com".to_string(),
+            email_verified: true,
+            preferred_username: "staff".to_string(),
+        };
+        let id_token = encode(&header, &claims, key.enc_key.as_ref().unwrap()).unwrap();
+        HttpResponse::Ok().json(serde_json::json!({
+            "access_token": "mock-access-token",
+            "token_type": "Bearer",
+            "expires_in": 300,
+            "id_token": id_token,
+        }))
+    }
+
+    fn provider_keys() -> KeyRing {
+        let entry = KeyManifestEntry {
+            kid: "mock-rsa".to_string(),
+            algorithm: "RS256".to_string(),
+            private_key: Some(PathBuf::from("rsa.pem")),
+            public_key: PathBuf::from("rsa.pub.pem"),
+            active_from: Utc::now() - TimeDelta::days(1),
+        };
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/infrastructure/testdata");
+        let key = SigningKey::from_manifest_entry(&entry, &testdata).unwrap();
+        KeyRing::new(vec![key], TimeDelta::days(1)).unwrap()
+    }
+
+    /// Starts the provider on a free local port and returns its issuer url.
+    fn start_mock_provider() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let provider = web::Data::new(MockProvider {
+            issuer: issuer.clone(),
+            keys: provider_keys(),
+            codes: Mutex::new(HashMap::new()),
+        });
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(provider.clone())
+                .route("/.well-known/openid-configuration", web::get().to(discovery))
+                .route("/jwks", web::get().to(jwks))
+                .route("/authorize", web::get().to(authorize))
+                .route("/token", web::post().to(token))
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+        issuer
+    }
+
+    fn oidc_config() -> OidcConfig {
+        OidcConfig {
+            issuer: None,
+            client_id: CLIENT_ID.to_string(),
+            client_secret: Some(CLIENT_SECRET.to_string()),
+            redirect_url: None,
+            scopes: "openid email profile".to_string(),
+            login_ttl_min: 10,
+        }
+    }
+
+    /// Follows the authorization url like a browser would and returns the code.
+    async fn authorize_code(client: &OidcClient, state: &str, nonce: &str, verifier: &str) -> String {
+        let url = client
+            .authorization_url(state, nonce, &pkce_challenge(verifier))
+            .await
+            .unwrap();
+        let resp = client.http.get(url).send().await.unwrap();
+        assert_eq!(resp.status(), 302);
+        let location = Url::parse(resp.headers()["Location"].to_str().unwrap()).unwrap();
+        assert!(location.as_str().starts_with("http://blog.test/callback"));
+        let params: HashMap<_, _> = location.query_pairs().into_owned().collect();
+        assert_eq!(params["state"], state);
+        params["code"].clone()
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // Example from RFC 7636, appendix B.
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_authorization_code_flow() {
+        let issuer = start_mock_provider();
+        let client =
+            OidcClient::new(&oidc_config(), &issuer, "http://blog.test/callback".to_string())
+                .unwrap();
+        let verifier = "verifier-of-at-least-forty-three-characters-long";
+
+        let code = authorize_code(&client, "state-1", "nonce-1", verifier).await;
+        // The callback is accepted only in the browser holding the state cookie.
+        assert!(check_state_binding("state-1", Some("state-1")).is_ok());
+        assert!(matches!(
+            check_state_binding("state-1", Some("state-of-another-login")),
+            Err(AppError::Unauthorized(_))
+        ));
+        assert!(check_state_binding("state-1", None).is_err());
+        assert!(matches!(
+            client.exchange_code(&code, "wrong-verifier").await,
+            Err(AppError::Unauthorized(_))
+        ));
+        let id_token = client.exchange_code(&code, verifier).await.unwrap();
+        // The code can be redeemed once.
+        assert!(client.exchange_code(&code, verifier).await.is_err());
+
+        let claims = client.verify_id_token(&id_token, "nonce-1").await.unwrap();
+        assert_eq!(claims.iss, issuer);
+        assert_eq!(claims.sub, "staff-1");
+        assert_eq!(claims.email.as_deref(), Some("staff@example.com"));
+        assert!(claims.email_verified);
+        assert_eq!(claims.preferred_username.as_deref(), Some("staff"));
+
+        assert!(matches!(
+            client.verify_id_token(&id_token, "other-nonce").await,
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_foreign_tokens() {
+        let issuer = start_mock_provider();
+        let client =
+            OidcClient::new(&oidc_config(), &issuer, "http://blog.test/callback".to_string())
+                .unwrap();
+        let verifier = "verifier-of-at-least-forty-three-characters-long";
+        let code = authorize_code(&client, "state-1", "nonce-1", verifier).await;
+        let id_token = client.exchange_code(&code, verifier).await.unwrap();
+
+        // Same provider, but the token was issued to another client.
+        let mut other_config = oidc_config();
+        other_config.client_id = "other".to_string();
+        let other =
+            OidcClient::new(&other_config, &issuer, "http://blog.test/callback".to_string())
+                .unwrap();
+        assert!(matches!(
+            other.verify_id_token(&id_token, "nonce-1").await,
+            Err(AppError::Unauthorized(_))
+        ));
+
+        let mut parts: Vec<&str> = id_token.split('.').collect();
+        let forged_payload = URL_SAFE_NO_PAD.encode(r#"{"sub":"admin"}"#);
+        parts[1] = &forged_payload;
+        assert!(matches!(
+            client.verify_id_token(&parts.join("."), "nonce-1").await,
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+}
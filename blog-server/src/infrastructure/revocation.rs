@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::RwLock;
+
+/// In-memory view of revoked token ids (`revoked_tokens`) or session ids (`sessions`),
+/// so that token checks on every request don't hit the database. An entry is kept
+/// until the last token it affects has expired.
+pub struct RevocationCache<K = String> {
+    entries: RwLock<HashMap<K, DateTime<Utc>>>,
+}
+
+impl<K> Default for RevocationCache<K> {
+    fn default() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash> RevocationCache<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, key: K, expires_at: DateTime<Utc>) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(key, expires_at);
+        }
+    }
+
+    pub fn is_revoked<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match self.entries.read() {
+            Ok(entries) => entries.contains_key(key),
+            // A poisoned lock means the cache state is unknown, so reject the token.
+            Err(_) => true,
+        }
+    }
+
+    /// Adds a database snapshot to the cache. Entries missing from the snapshot are
+    /// kept: a revocation made while the snapshot was loading must not be lost, and
+    /// expired entries are dropped by [`Self::purge_expired`].
+    pub fn merge(&self, revoked: Vec<(K, DateTime<Utc>)>) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.extend(revoked);
+        }
+    }
+
+    pub fn purge_expired(&self) {
+        let current = Utc::now();
+        if let Ok(mut entries) = self.entries.write() {
+            entries.retain(|_, expires_at| *expires_at > current);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_insert_and_check() {
+        let cache = RevocationCache::new();
+        assert!(!cache.is_revoked("jti"));
+        cache.insert("jti".to_string(), Utc::now() + TimeDelta::minutes(5));
+        assert!(cache.is_revoked("jti"));
+        assert!(!cache.is_revoked("other_jti"));
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let cache = RevocationCache::new();
+        cache.insert("expired".to_string(), Utc::now() - TimeDelta::minutes(1));
+        cache.insert("active".to_string(), Utc::now() + TimeDelta::minutes(5));
+        cache.purge_expired();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.is_revoked("active"));
+        assert!(!cache.is_revoked("expired"));
+    }
+
+    #[test]
+    fn test_merge_keeps_newer_entries() {
+        let cache = RevocationCache::new();
+        cache.insert("logged_out".to_string(), Utc::now() + TimeDelta::minutes(5));
+        cache.merge(vec![("synced".to_string(), Utc::now() + TimeDelta::minutes(5))]);
+        assert!(cache.is_revoked("logged_out"));
+        assert!(cache.is_revoked("synced"));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_session_ids() {
+        let cache = RevocationCache::<i64>::new();
+        cache.insert(7, Utc::now() + TimeDelta::minutes(5));
+        assert!(cache.is_revoked(&7));
+        assert!(!cache.is_revoked(&8));
+    }
+}
@@ -0,0 +1,73 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Result, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts small secrets (such as TOTP seeds) before they are stored in the database.
+/// The output is base64 of `nonce || ciphertext`.
+pub struct SecretBox {
+    cipher: Aes256Gcm,
+}
+
+impl SecretBox {
+    /// `hex_key` is a 32 bytes AES-256 key in hex.
+    pub fn new(hex_key: &str) -> Result<Self> {
+        let key = hex::decode(hex_key)?;
+        let Ok(cipher) = Aes256Gcm::new_from_slice(&key) else {
+            bail!("Encryption key must be 32 bytes long, got {}", key.len());
+        };
+        Ok(Self { cipher })
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let Ok(ciphertext) = self.cipher.encrypt(&nonce, plaintext) else {
+            bail!("Can't encrypt secret");
+        };
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(STANDARD.encode(sealed))
+    }
+
+    pub fn decrypt(&self, sealed: &str) -> Result<Vec<u8>> {
+        let sealed = STANDARD.decode(sealed)?;
+        if sealed.len() < NONCE_LEN {
+            bail!("Encrypted secret is too short");
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::from(<[u8; NONCE_LEN]>::try_from(nonce)?);
+        let Ok(plaintext) = self.cipher.decrypt(&nonce, ciphertext) else {
+            bail!("Can't decrypt secret");
+        };
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let secret_box = SecretBox::new(KEY).unwrap();
+        let sealed = secret_box.encrypt(b"secret").unwrap();
+        assert_ne!(sealed, secret_box.encrypt(b"secret").unwrap());
+        assert_eq!(secret_box.decrypt(&sealed).unwrap(), b"secret");
+
+        let other_box = SecretBox::new(&KEY.replace("00", "ff")).unwrap();
+        assert!(other_box.decrypt(&sealed).is_err());
+        assert!(secret_box.decrypt("AAAA").is_err());
+    }
+
+    #[test]
+    fn test_bad_key() {
+        assert!(SecretBox::new("0011").is_err());
+        assert!(SecretBox::new("not hex").is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use totp_rs::{Algorithm, TOTP};
+
+const SECRET_LEN: usize = 20;
+const DIGITS: usize = 6;
+const STEP_SEC: u64 = 30;
+/// Accepted clock drift between the server and the authenticator app, in steps.
+const SKEW_STEPS: u64 = 1;
+
+pub fn generate_totp_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+fn totp(secret: &[u8], issuer: &str, account: &str) -> Result<TOTP> {
+    Ok(TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        0,
+        STEP_SEC,
+        secret.to_vec(),
+        Some(issuer.to_string()),
+        account.to_string(),
+    )?)
+}
+
+/// Returns the base32 secret for manual entry together with the `otpauth://` URI.
+pub fn totp_provisioning(secret: &[u8], issuer: &str, account: &str) -> Result<(String, String)> {
+    let totp = totp(secret, issuer, account)?;
+    Ok((totp.get_secret_base32(), totp.get_url()))
+}
+
+/// Returns the time step the code belongs to, so that callers can reject replays.
+pub fn totp_matching_step(secret: &[u8], code: &str, unix_time: u64) -> Result<Option<u64>> {
+    let totp = totp(secret, "", "")?;
+    let current_step = unix_time / STEP_SEC;
+    let first_step = current_step.saturating_sub(SKEW_STEPS);
+    let matching_step = (first_step..=current_step + SKEW_STEPS)
+        .find(|step| totp.check(code, step * STEP_SEC));
+    Ok(matching_step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provisioning() {
+        let secret = generate_totp_secret();
+        let (base32, uri) = totp_provisioning(&secret, "blog", "alice").unwrap();
+        assert!(uri.starts_with("otpauth://totp/blog:alice?"));
+        assert!(uri.contains(&format!("secret={base32}")));
+        assert!(totp_provisioning(&secret, "blog", "ali:ce").is_err());
+    }
+
+    #[test]
+    fn test_matching_step() {
+        let secret = generate_totp_secret();
+        let now = 1_700_000_000;
+        let code = totp(&secret, "", "").unwrap().generate(now);
+        let step = now / STEP_SEC;
+
+        assert_eq!(totp_matching_step(&secret, &code, now).unwrap(), Some(step));
+        assert_eq!(totp_matching_step(&secret, &code, now + STEP_SEC).unwrap(), Some(step));
+        assert_eq!(totp_matching_step(&secret, &code, now + 3 * STEP_SEC).unwrap(), None);
+        assert_eq!(totp_matching_step(&secret, "000000x", now).unwrap(), None);
+    }
+}
@@ -9,10 +9,11 @@ use tonic::transport::Server;
 use actix_cors::Cors;
 use actix_web::{App, HttpServer, guard, web};
 use anyhow::{Result, bail};
-use tracing;
 use tracing_actix_web::TracingLogger;
 
-use presentation::grpc_service::{BlogGrpcService, proto::blog_service_server::BlogServiceServer};
+use presentation::grpc_service::proto::blog_service_server::BlogServiceServer;
+use presentation::grpc_service::proto::v2::blog_service_server::BlogServiceServer as BlogServiceServerV2;
+use presentation::grpc_service::{AuthInterceptor, BlogGrpcService};
 use presentation::http_handlers::*;
 use presentation::middleware;
 
@@ -28,11 +29,19 @@ async fn main() -> Result<()> {
 
     let grpc_addr = "0.0.0.0:50051".parse()?;
     let grpc_service = BlogGrpcService::new(app_state.clone());
+    let grpc_app_state = app_state.clone();
 
     tokio::spawn(async move {
         tracing::info!("Blog gRPC server starting on {}", grpc_addr);
         Server::builder()
-            .add_service(BlogServiceServer::new(grpc_service))
+            .add_service(AuthInterceptor::new(
+                BlogServiceServer::new(grpc_service.clone()),
+                grpc_app_state.clone(),
+            ))
+            .add_service(AuthInterceptor::new(
+                BlogServiceServerV2::new(grpc_service),
+                grpc_app_state,
+            ))
             .serve(grpc_addr)
             .await
             .unwrap();
@@ -51,12 +60,65 @@ async fn main() -> Result<()> {
             .app_data(app_state.clone())
             .wrap(cors)
             .wrap(TracingLogger::default())
+            .route("/.well-known/jwks.json", web::get().to(jwks))
             .service(
                 web::scope("/api")
                     .service(
                         web::scope("/auth")
                             .route("/register", web::post().to(register))
-                            .route("/login", web::post().to(login)),
+                            .route("/login", web::post().to(login))
+                            .route("/refresh", web::post().to(refresh))
+                            .route("/2fa/verify", web::post().to(verify_two_factor))
+                            .route("/oidc/login", web::get().to(start_oidc_login))
+                            .route("/oidc/callback", web::get().to(finish_oidc_login))
+                            .service(
+                                web::resource("/2fa/enroll")
+                                    .wrap(middleware::Jwt)
+                                    .route(web::post().to(enroll_totp)),
+                            )
+                            .service(
+                                web::resource("/2fa/confirm")
+                                    .wrap(middleware::Jwt)
+                                    .route(web::post().to(confirm_totp)),
+                            )
+                            .service(
+                                web::resource("/2fa/disable")
+                                    .wrap(middleware::Jwt)
+                                    .route(web::post().to(disable_totp)),
+                            )
+                            .route(
+                                "/password-reset/request",
+                                web::post().to(request_password_reset),
+                            )
+                            .route(
+                                "/password-reset/confirm",
+                                web::post().to(confirm_password_reset),
+                            )
+                            .route("/verify-email", web::get().to(verify_email))
+                            .service(
+                                web::resource("/verify-email/resend")
+                                    .wrap(middleware::Jwt)
+                                    .route(web::post().to(resend_verification)),
+                            )
+                            .service(
+                                web::resource("/logout")
+                                    .wrap(middleware::Jwt)
+                                    .route(web::post().to(logout)),
+                            ),
+                    )
+                    .service(
+                        web::scope("/users")
+                            .wrap(middleware::Jwt)
+                            .route("/{id}/role", web::put().to(set_user_role))
+                            .route("/me", web::delete().to(delete_account))
+                            .route("/me/password", web::put().to(change_password))
+                            .route("/me/email", web::put().to(change_email))
+                            .route("/me/tokens", web::post().to(create_personal_token))
+                            .route("/me/tokens", web::get().to(list_personal_tokens))
+                            .route("/me/tokens/{id}", web::delete().to(revoke_personal_token))
+                            .route("/me/sessions", web::get().to(list_sessions))
+                            .route("/me/sessions", web::delete().to(revoke_all_sessions))
+                            .route("/me/sessions/{id}", web::delete().to(revoke_session)),
                     )
                     .service(
                         web::scope("/posts")
@@ -64,14 +126,39 @@ async fn main() -> Result<()> {
                             .wrap(middleware::Jwt)
                             .route("", web::post().to(create_post))
                             .route("/{id}", web::put().to(update_post))
-                            .route("/{id}", web::delete().to(delete_post)),
+                            .route("/{id}", web::delete().to(delete_post))
+                            .route("/{id}/restore/{revision}", web::post().to(restore_revision))
+                            .route("/trash/{id}/restore", web::post().to(restore_post))
+                            .route("/trash/{id}", web::delete().to(purge_post))
+                            .route("/{id}/comments", web::post().to(add_comment))
+                            .route("/{id}/comments/{comment_id}", web::put().to(update_comment))
+                            .route("/{id}/comments/{comment_id}", web::delete().to(delete_comment)),
                         )
+                    .service(
+                        web::scope("/posts/trash")
+                            .guard(guard::Get())
+                            .wrap(middleware::Jwt)
+                            .route("", web::get().to(get_trash)),
+                    )
+                    .service(
+                        web::scope("/posts/{id}/revisions")
+                            .guard(guard::Get())
+                            .wrap(middleware::Jwt)
+                            .route("", web::get().to(list_revisions))
+                            .route("/diff", web::get().to(diff_revisions))
+                            .route("/{revision}", web::get().to(get_revision)),
+                    )
                     .service(
                         web::scope("/posts")
                             .guard(guard::Get())
+                            .wrap(middleware::OptionalJwt)
                             .route("", web::get().to(get_posts))
+                            .route("/search", web::get().to(search_posts))
+                            .route("/by-slug/{slug}", web::get().to(get_post_by_slug))
+                            .route("/{id}/comments", web::get().to(list_comments))
                             .route("/{id}", web::get().to(get_post)),
                     )
+                    .route("/tags", web::get().to(list_tags))
             )
     })
     .bind("0.0.0.0:3000")?
@@ -0,0 +1,66 @@
+use actix_web::web;
+use tonic::Status;
+use tonic::body::Body;
+use tonic::codegen::{Context, Future, Pin, Poll, Service, http};
+use tonic::server::NamedService;
+
+use crate::infrastructure::AppState;
+
+/// Authenticates gRPC calls by the `authorization: Bearer <token>` metadata and puts
+/// the verified `Claims` into the request extensions. Calls without the metadata pass
+/// through, so that public calls and v1 messages with `reg_user` keep working.
+#[derive(Clone)]
+pub struct AuthInterceptor<S> {
+    inner: S,
+    app_state: web::Data<AppState>,
+}
+
+impl<S> AuthInterceptor<S> {
+    pub fn new(inner: S, app_state: web::Data<AppState>) -> Self {
+        Self { inner, app_state }
+    }
+}
+
+fn bearer_token<B>(req: &http::Request<B>) -> Option<String> {
+    req.headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .map(str::to_owned)
+}
+
+impl<S, B> Service<http::Request<B>> for AuthInterceptor<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        // The ready service has to be the one that handles the call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let app_state = self.app_state.clone();
+
+        Box::pin(async move {
+            if let Some(token) = bearer_token(&req) {
+                let Some(claims) = app_state.authenticate(&token).await else {
+                    return Ok(Status::unauthenticated("invalid credentials").into_http());
+                };
+                req.extensions_mut().insert(claims);
+            }
+            inner.call(req).await
+        })
+    }
+}
+
+impl<S: NamedService> NamedService for AuthInterceptor<S> {
+    const NAME: &'static str = S::NAME;
+}
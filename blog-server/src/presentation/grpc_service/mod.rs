@@ -1,13 +1,30 @@
-pub mod proto;
+mod auth_interceptor;
+mod v2;
+
+pub mod proto {
+    include!("proto.rs");
+
+    pub mod v2 {
+        include!("proto.v2.rs");
+    }
+}
 
 use actix_web::web;
 use proto::*;
 use tonic::{Request, Response, Status};
 
-use crate::application::{auth_service, blog_service};
+use crate::application::{
+    auth_service, blog_service, comment_service, email_verification_service,
+    password_reset_service, personal_token_service, session_service, two_factor_service,
+};
 use crate::domain::error::AppError;
+use crate::domain::word_diff;
 use crate::infrastructure::AppState;
+use crate::infrastructure::jwt::Claims;
 use crate::presentation::grpc_service::proto::blog_service_server::BlogService;
+use crate::presentation::grpc_service::proto::v2::blog_service_server::BlogService as BlogServiceV2;
+
+pub use auth_interceptor::AuthInterceptor;
 
 impl From<AppError> for Status {
     fn from(value: AppError) -> Self {
@@ -15,7 +32,16 @@ impl From<AppError> for Status {
             AppError::AlreadyExists(reason) => Self::already_exists(reason),
             AppError::UserNotFound(reason) => Self::not_found(reason),
             AppError::PostNotFound(reason) => Self::not_found(reason),
+            AppError::RevisionNotFound(reason) => Self::not_found(reason),
+            AppError::CommentNotFound(reason) => Self::not_found(reason),
+            AppError::VersionConflict(reason) => Self::failed_precondition(reason),
+            AppError::TokenNotFound(reason) => Self::not_found(reason),
+            AppError::SessionNotFound(reason) => Self::not_found(reason),
             AppError::Unauthorized(reason) => Self::unauthenticated(reason),
+            AppError::Forbidden(reason) => Self::permission_denied(reason),
+            AppError::InvalidRequest(reason) => Self::invalid_argument(reason),
+            AppError::TooManyAttempts(reason) => Self::resource_exhausted(reason),
+            AppError::Validation(fields) => Self::invalid_argument(fields.to_string()),
             AppError::InternalError(reason) => Self::internal(reason),
         }
     }
@@ -30,10 +56,138 @@ impl From<blog_service::PostInfo> for PostInfo {
             author_id: value.author_id,
             created_at: value.created_at,
             updated_at: value.updated_at,
+            status: value.status.to_string(),
+            published_at: value.published_at,
+            version: value.version,
+            deleted_at: value.deleted_at,
+            tags: value.tags,
+            slug: value.slug,
+            content_format: value.content_format.to_string(),
+            content_html: value.content_html,
+        }
+    }
+}
+
+impl From<comment_service::CommentInfo> for Comment {
+    fn from(value: comment_service::CommentInfo) -> Self {
+        Self {
+            id: value.id,
+            post_id: value.post_id,
+            parent_id: value.parent_id,
+            author_id: value.author_id,
+            content: value.content,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            depth: value.depth as i64,
+            deleted: value.deleted,
+        }
+    }
+}
+
+impl From<blog_service::SearchHitInfo> for SearchHit {
+    fn from(value: blog_service::SearchHitInfo) -> Self {
+        Self {
+            post: Some(value.post.into()),
+            rank: value.rank,
+            snippet: value.snippet,
+        }
+    }
+}
+
+impl From<blog_service::TagInfo> for TagCount {
+    fn from(value: blog_service::TagInfo) -> Self {
+        Self {
+            name: value.name,
+            post_count: value.post_count,
+        }
+    }
+}
+
+impl From<auth_service::RegisteredUser> for RegisteredUser {
+    fn from(value: auth_service::RegisteredUser) -> Self {
+        Self {
+            token: value.token,
+            refresh_token: value.refresh_token,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<auth_service::LoginResp> for RegisteredUser {
+    fn from(value: auth_service::LoginResp) -> Self {
+        match value {
+            auth_service::LoginResp::Authenticated(reg_user) => RegisteredUser::from(reg_user),
+            auth_service::LoginResp::TwoFactorRequired(challenge) => Self {
+                challenge_token: challenge.challenge_token,
+                challenge_expires_in: challenge.expires_in,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<personal_token_service::PersonalTokenInfo> for PersonalToken {
+    fn from(value: personal_token_service::PersonalTokenInfo) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            scopes: value.scopes.iter().map(|scope| scope.to_string()).collect(),
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+            token: value.token,
         }
     }
 }
 
+impl From<session_service::SessionInfo> for Session {
+    fn from(value: session_service::SessionInfo) -> Self {
+        Self {
+            id: value.id,
+            user_agent: value.user_agent,
+            ip_address: value.ip_address,
+            created_at: value.created_at,
+            last_seen_at: value.last_seen_at,
+            current: value.current,
+        }
+    }
+}
+
+impl From<blog_service::RevisionInfo> for PostRevision {
+    fn from(value: blog_service::RevisionInfo) -> Self {
+        Self {
+            post_id: value.post_id,
+            revision: value.revision,
+            title: value.title,
+            content: value.content,
+            editor_id: value.editor_id,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<word_diff::DiffChunk> for DiffChunk {
+    fn from(value: word_diff::DiffChunk) -> Self {
+        Self {
+            op: value.op.as_str().to_string(),
+            text: value.text,
+        }
+    }
+}
+
+impl From<blog_service::RevisionDiff> for PostRevisionDiff {
+    fn from(value: blog_service::RevisionDiff) -> Self {
+        Self {
+            post_id: value.post_id,
+            from: value.from,
+            to: value.to,
+            title: value.title.into_iter().map(DiffChunk::from).collect(),
+            content: value.content.into_iter().map(DiffChunk::from).collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct BlogGrpcService {
     app_state: web::Data<AppState>,
 }
@@ -42,6 +196,60 @@ impl BlogGrpcService {
     pub fn new(app_state: web::Data<AppState>) -> Self {
         Self { app_state }
     }
+
+    /// v1 calls may carry the token either in the metadata or in `reg_user`.
+    async fn legacy_claims<T>(
+        &self,
+        in_req: &Request<T>,
+        reg_user: Option<&RegisteredUser>,
+    ) -> Result<Claims, Status> {
+        if let Some(claims) = in_req.extensions().get::<Claims>() {
+            return Ok(claims.clone());
+        }
+
+        let Some(reg_user) = reg_user else {
+            return Err(Status::failed_precondition("token not present"));
+        };
+
+        self.app_state
+            .authenticate(&reg_user.token)
+            .await
+            .ok_or_else(|| Status::unauthenticated("Invalid token"))
+    }
+}
+
+fn client_info<T>(in_req: &Request<T>) -> session_service::ClientInfo {
+    let user_agent = in_req
+        .metadata()
+        .get("user-agent")
+        .and_then(|value| value.to_str().ok());
+    session_service::ClientInfo::new(in_req.remote_addr().map(|addr| addr.ip()), user_agent)
+}
+
+/// Reader of a public call, if the call was made with a token.
+fn viewer<T>(in_req: &Request<T>) -> Option<blog_service::AuthUser> {
+    in_req
+        .extensions()
+        .get::<Claims>()
+        .cloned()
+        .map(blog_service::AuthUser::from)
+}
+
+fn authorized<T>(claims: Claims, message: T) -> Request<T> {
+    let mut out_req = Request::new(message);
+    out_req.extensions_mut().insert(claims);
+    out_req
+}
+
+/// Like `authorized`, but keeps the metadata and the peer address of the original call.
+fn authorized_from<T, U>(
+    claims: Claims,
+    in_req: Request<T>,
+    message: impl FnOnce(T) -> U,
+) -> Request<U> {
+    let mut out_req = in_req.map(message);
+    out_req.extensions_mut().insert(claims);
+    out_req
 }
 
 #[tonic::async_trait]
@@ -50,72 +258,216 @@ impl BlogService for BlogGrpcService {
         &self,
         in_req: Request<RegisterRequest>,
     ) -> Result<Response<RegisteredUser>, Status> {
+        let client = client_info(&in_req);
         let in_req = in_req.into_inner();
         let auth_service = self.app_state.auth_service.clone();
-        let mut reg_request = auth_service::RegisterUserReq::default();
-        reg_request.username = in_req.username;
-        reg_request.email = in_req.email;
-        reg_request.password = in_req.password;
-        let reg_user = auth_service.register(reg_request).await?;
-        Ok(Response::new(RegisteredUser {
-            token: reg_user.token,
-        }))
+        let reg_request = auth_service::RegisterUserReq {
+            username: in_req.username,
+            email: in_req.email,
+            password: in_req.password,
+        };
+        let reg_user = auth_service.register(reg_request, client).await?;
+        Ok(Response::new(RegisteredUser::from(reg_user)))
     }
 
     async fn login(
         &self,
         in_req: Request<LoginRequest>,
     ) -> Result<Response<RegisteredUser>, Status> {
+        let client = client_info(&in_req);
         let in_req = in_req.into_inner();
         let auth_service = self.app_state.auth_service.clone();
-        let mut log_request = auth_service::LoginUserReq::default();
-        log_request.username = in_req.username;
-        log_request.password = in_req.password;
-        let reg_user = auth_service.login(log_request).await?;
-        Ok(Response::new(RegisteredUser {
-            token: reg_user.token,
-        }))
+        let log_request = auth_service::LoginUserReq {
+            username: in_req.username,
+            password: in_req.password,
+        };
+        let reg_user = auth_service.login(log_request, client).await?;
+        Ok(Response::new(RegisteredUser::from(reg_user)))
     }
 
-    async fn create_post(
+    async fn refresh(
         &self,
-        in_req: Request<CreatePostRequest>,
-    ) -> Result<Response<PostInfo>, Status> {
+        in_req: Request<RefreshRequest>,
+    ) -> Result<Response<RegisteredUser>, Status> {
+        let client = client_info(&in_req);
         let in_req = in_req.into_inner();
-        
-        let Some(reg_user) = in_req.reg_user else {
-            return Err(Status::failed_precondition("token not present"));
+        let auth_service = self.app_state.auth_service.clone();
+        let refresh_request = auth_service::RefreshTokenReq {
+            refresh_token: in_req.refresh_token,
         };
+        let reg_user = auth_service.refresh(refresh_request, client).await?;
+        Ok(Response::new(RegisteredUser::from(reg_user)))
+    }
 
-        let Some(claims) = self.app_state.jwt_service.verify_token(&reg_user.token) else {
-            return Err(Status::unauthenticated("Invalid token"));
+    async fn verify_two_factor(
+        &self,
+        in_req: Request<VerifyTwoFactorRequest>,
+    ) -> Result<Response<RegisteredUser>, Status> {
+        let client = client_info(&in_req);
+        let in_req = in_req.into_inner();
+        let auth_service = self.app_state.auth_service.clone();
+        let verify_request = two_factor_service::VerifyTwoFactorReq {
+            challenge_token: in_req.challenge_token,
+            code: in_req.code,
         };
+        let reg_user = auth_service
+            .verify_two_factor(verify_request, client)
+            .await?;
+        Ok(Response::new(RegisteredUser::from(reg_user)))
+    }
+
+    async fn enroll_totp(
+        &self,
+        in_req: Request<EnrollTotpRequest>,
+    ) -> Result<Response<TotpEnrollment>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::enroll_totp(self, authorized(claims, proto::v2::EnrollTotpRequest {})).await
+    }
 
-        let Some(in_new_post) = in_req.new_post else {
-            return Err(Status::failed_precondition("new post not present"));
+    async fn confirm_totp(
+        &self,
+        in_req: Request<TotpCodeRequest>,
+    ) -> Result<Response<RecoveryCodes>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::confirm_totp(
+            self,
+            authorized(claims, proto::v2::TotpCodeRequest { code: in_req.code }),
+        )
+        .await
+    }
+
+    async fn disable_totp(
+        &self,
+        in_req: Request<TotpCodeRequest>,
+    ) -> Result<Response<DisableTotpResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::disable_totp(
+            self,
+            authorized(claims, proto::v2::TotpCodeRequest { code: in_req.code }),
+        )
+        .await
+    }
+
+    async fn logout(
+        &self,
+        in_req: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        let logout_request = proto::v2::LogoutRequest {
+            refresh_token: in_req.reg_user.map(|reg_user| reg_user.refresh_token),
         };
+        BlogServiceV2::logout(self, authorized(claims, logout_request)).await
+    }
 
-        let blog_service = self.app_state.blog_service.clone();
-        let mut auth_user = blog_service::AuthUser::default();
-        auth_user.id = claims.id;
-        auth_user.username = claims.username;
-        auth_user.email = claims.email;
+    async fn request_password_reset(
+        &self,
+        in_req: Request<PasswordResetRequest>,
+    ) -> Result<Response<PasswordResetResponse>, Status> {
+        let in_req = in_req.into_inner();
+        let password_reset_service = self.app_state.password_reset_service.clone();
+        let reset_request = password_reset_service::PasswordResetReq {
+            email: in_req.email,
+        };
+        password_reset_service.request_reset(reset_request).await?;
+        Ok(Response::new(PasswordResetResponse {}))
+    }
 
-        let mut new_post = blog_service::NewPost::default();
-        new_post.title = in_new_post.title;
-        new_post.content = in_new_post.content;
+    async fn confirm_password_reset(
+        &self,
+        in_req: Request<PasswordResetConfirmRequest>,
+    ) -> Result<Response<PasswordResetResponse>, Status> {
+        let in_req = in_req.into_inner();
+        let password_reset_service = self.app_state.password_reset_service.clone();
+        let confirm_request = password_reset_service::PasswordResetConfirmReq {
+            token: in_req.token,
+            new_password: in_req.new_password,
+        };
+        password_reset_service
+            .confirm_reset(confirm_request)
+            .await?;
+        Ok(Response::new(PasswordResetResponse {}))
+    }
 
-        let out_post_info = blog_service.create_post(auth_user, new_post).await?;
-        Ok(Response::new(PostInfo::from(out_post_info)))
+    async fn verify_email(
+        &self,
+        in_req: Request<VerifyEmailRequest>,
+    ) -> Result<Response<VerifyEmailResponse>, Status> {
+        let in_req = in_req.into_inner();
+        let email_verification_service = self.app_state.email_verification_service.clone();
+        let verify_request = email_verification_service::VerifyEmailReq {
+            token: in_req.token,
+        };
+        email_verification_service
+            .verify_email(verify_request)
+            .await?;
+        Ok(Response::new(VerifyEmailResponse {}))
+    }
+
+    async fn resend_verification(
+        &self,
+        in_req: Request<ResendVerificationRequest>,
+    ) -> Result<Response<VerifyEmailResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::resend_verification(
+            self,
+            authorized(claims, proto::v2::ResendVerificationRequest {}),
+        )
+        .await
+    }
+
+    async fn create_post(
+        &self,
+        in_req: Request<CreatePostRequest>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::create_post(
+            self,
+            authorized(
+                claims,
+                proto::v2::CreatePostRequest {
+                    new_post: in_req.new_post,
+                },
+            ),
+        )
+        .await
     }
 
     async fn get_post(&self, in_req: Request<PostId>) -> Result<Response<PostInfo>, Status> {
+        let viewer = viewer(&in_req);
         let in_req = in_req.into_inner();
         let blog_service = self.app_state.blog_service.clone();
-        let mut post_id = blog_service::PostId::default();
-        post_id.id = in_req.id;
+        let post_id = blog_service::PostId { id: in_req.id };
 
-        let out_post_info = blog_service.get_post(post_id).await?;
+        let out_post_info = blog_service.get_post(viewer, post_id).await?;
+        Ok(Response::new(PostInfo::from(out_post_info)))
+    }
+
+    async fn get_post_by_slug(
+        &self,
+        in_req: Request<PostSlug>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let viewer = viewer(&in_req);
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let post_slug = blog_service::PostSlug { slug: in_req.slug };
+
+        let out_post_info = blog_service.get_post_by_slug(viewer, post_slug).await?;
         Ok(Response::new(PostInfo::from(out_post_info)))
     }
 
@@ -123,92 +475,509 @@ impl BlogService for BlogGrpcService {
         &self,
         in_req: Request<UpdatePostRequest>,
     ) -> Result<Response<PostInfo>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
         let in_req = in_req.into_inner();
-        let Some(reg_user) = in_req.reg_user else {
-            return Err(Status::failed_precondition("token not present"));
-        };
+        BlogServiceV2::update_post(
+            self,
+            authorized(
+                claims,
+                proto::v2::UpdatePostRequest {
+                    update_post: in_req.update_post,
+                    post_id: in_req.post_id,
+                },
+            ),
+        )
+        .await
+    }
 
-        let Some(claims) = self.app_state.jwt_service.verify_token(&reg_user.token) else {
-            return Err(Status::unauthenticated("Invalid token"));
-        };
+    async fn delete_post(
+        &self,
+        in_req: Request<DeletePostRequest>,
+    ) -> Result<Response<DeletePostResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::delete_post(
+            self,
+            authorized(
+                claims,
+                proto::v2::DeletePostRequest {
+                    post_id: in_req.post_id,
+                },
+            ),
+        )
+        .await
+    }
 
-        let Some(in_update_post) = in_req.update_post else {
-            return Err(Status::failed_precondition("new post not present"));
+    async fn get_posts(
+        &self,
+        in_req: Request<GetPostsReq>,
+    ) -> Result<Response<GetPostsResponse>, Status> {
+        let viewer = viewer(&in_req);
+        let in_req = in_req.into_inner();
+
+        let blog_service = self.app_state.blog_service.clone();
+        let get_posts_req = blog_service::GetPostsReq {
+            offset: Some(in_req.offset),
+            limit: Some(in_req.limit),
+            tag: in_req.tag,
+            cursor: in_req.cursor,
+            with_total: in_req.with_total,
         };
 
-        let Some(in_post_id) = in_req.post_id else {
-            return Err(Status::failed_precondition("post id not present"));
+        let out_post_info = blog_service.get_posts(viewer, get_posts_req).await?;
+
+        let posts: Vec<PostInfo> = out_post_info
+            .posts
+            .into_iter()
+            .map(PostInfo::from)
+            .collect();
+        let res = GetPostsResponse {
+            offset: out_post_info.offset,
+            limit: out_post_info.limit,
+            posts_info: posts,
+            next_cursor: out_post_info.next_cursor,
+            prev_cursor: out_post_info.prev_cursor,
+            total: out_post_info.total,
         };
+        Ok(Response::new(res))
+    }
 
+    async fn list_tags(
+        &self,
+        _in_req: Request<ListTagsRequest>,
+    ) -> Result<Response<TagList>, Status> {
         let blog_service = self.app_state.blog_service.clone();
-        let mut auth_user = blog_service::AuthUser::default();
-        auth_user.id = claims.id;
-        auth_user.username = claims.username;
-        auth_user.email = claims.email;
+        let tags = blog_service.list_tags().await?;
+        Ok(Response::new(TagList {
+            tags: tags.into_iter().map(TagCount::from).collect(),
+        }))
+    }
 
-        let mut update_post = blog_service::UpdatePost::default();
-        update_post.title = in_update_post.title;
-        update_post.content = in_update_post.content;
+    async fn search_posts(
+        &self,
+        in_req: Request<SearchPostsRequest>,
+    ) -> Result<Response<SearchPostsResponse>, Status> {
+        let viewer = viewer(&in_req);
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let query = blog_service::SearchPostsReq {
+            q: in_req.query,
+            lang: in_req.lang,
+            offset: Some(in_req.offset),
+            limit: Some(in_req.limit),
+        };
+        let found = blog_service.search_posts(viewer, query).await?;
+        Ok(Response::new(SearchPostsResponse {
+            offset: found.offset,
+            limit: found.limit,
+            hits: found.hits.into_iter().map(SearchHit::from).collect(),
+        }))
+    }
 
-        let mut post_id = blog_service::PostId::default();
-        post_id.id = in_post_id.id;
-        let out_post_info = blog_service
-            .update_post(auth_user, post_id, update_post)
+    async fn set_user_role(
+        &self,
+        in_req: Request<SetUserRoleRequest>,
+    ) -> Result<Response<SetUserRoleResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
             .await?;
-        Ok(Response::new(PostInfo::from(out_post_info)))
+        let in_req = in_req.into_inner();
+        BlogServiceV2::set_user_role(
+            self,
+            authorized(
+                claims,
+                proto::v2::SetUserRoleRequest {
+                    user_id: in_req.user_id,
+                    role: in_req.role,
+                },
+            ),
+        )
+        .await
     }
 
-    async fn delete_post(
+    async fn create_personal_token(
         &self,
-        in_req: Request<DeletePostRequest>,
-    ) -> Result<Response<DeletePostResponse>, Status> {
+        in_req: Request<CreatePersonalTokenRequest>,
+    ) -> Result<Response<PersonalToken>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
         let in_req = in_req.into_inner();
-        let Some(reg_user) = in_req.reg_user else {
-            return Err(Status::failed_precondition("token not present"));
-        };
+        BlogServiceV2::create_personal_token(
+            self,
+            authorized(
+                claims,
+                proto::v2::CreatePersonalTokenRequest {
+                    name: in_req.name,
+                    scopes: in_req.scopes,
+                    expires_in_days: in_req.expires_in_days,
+                },
+            ),
+        )
+        .await
+    }
 
-        let Some(claims) = self.app_state.jwt_service.verify_token(&reg_user.token) else {
-            return Err(Status::unauthenticated("Invalid token"));
-        };
+    async fn list_personal_tokens(
+        &self,
+        in_req: Request<ListPersonalTokensRequest>,
+    ) -> Result<Response<PersonalTokenList>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::list_personal_tokens(
+            self,
+            authorized(claims, proto::v2::ListPersonalTokensRequest {}),
+        )
+        .await
+    }
 
-        let Some(in_post_id) = in_req.post_id else {
-            return Err(Status::failed_precondition("post id not present"));
-        };
+    async fn revoke_personal_token(
+        &self,
+        in_req: Request<RevokePersonalTokenRequest>,
+    ) -> Result<Response<RevokePersonalTokenResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::revoke_personal_token(
+            self,
+            authorized(
+                claims,
+                proto::v2::RevokePersonalTokenRequest { id: in_req.id },
+            ),
+        )
+        .await
+    }
 
-        let blog_service = self.app_state.blog_service.clone();
-        let mut auth_user = blog_service::AuthUser::default();
-        auth_user.id = claims.id;
-        auth_user.username = claims.username;
-        auth_user.email = claims.email;
+    async fn change_password(
+        &self,
+        in_req: Request<ChangePasswordRequest>,
+    ) -> Result<Response<AccountResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::change_password(
+            self,
+            authorized_from(claims, in_req, |in_req| proto::v2::ChangePasswordRequest {
+                current_password: in_req.current_password,
+                new_password: in_req.new_password,
+            }),
+        )
+        .await
+    }
 
-        let mut post_id = blog_service::PostId::default();
-        post_id.id = in_post_id.id;
-        blog_service.delete_post(auth_user, post_id).await?;
-        Ok(Response::new(DeletePostResponse {}))
+    async fn change_email(
+        &self,
+        in_req: Request<ChangeEmailRequest>,
+    ) -> Result<Response<AccountResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::change_email(
+            self,
+            authorized_from(claims, in_req, |in_req| proto::v2::ChangeEmailRequest {
+                email: in_req.email,
+                password: in_req.password,
+            }),
+        )
+        .await
     }
 
-    async fn get_posts(
+    async fn delete_account(
         &self,
-        in_req: Request<GetPostsReq>,
+        in_req: Request<DeleteAccountRequest>,
+    ) -> Result<Response<AccountResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::delete_account(
+            self,
+            authorized_from(claims, in_req, |in_req| proto::v2::DeleteAccountRequest {
+                password: in_req.password,
+                reassign_posts_to: in_req.reassign_posts_to,
+            }),
+        )
+        .await
+    }
+
+    async fn list_sessions(
+        &self,
+        in_req: Request<ListSessionsRequest>,
+    ) -> Result<Response<SessionList>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::list_sessions(self, authorized(claims, proto::v2::ListSessionsRequest {}))
+            .await
+    }
+
+    async fn revoke_session(
+        &self,
+        in_req: Request<RevokeSessionRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::revoke_session(
+            self,
+            authorized(claims, proto::v2::RevokeSessionRequest { id: in_req.id }),
+        )
+        .await
+    }
+
+    async fn revoke_all_sessions(
+        &self,
+        in_req: Request<RevokeAllSessionsRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        BlogServiceV2::revoke_all_sessions(
+            self,
+            authorized(claims, proto::v2::RevokeAllSessionsRequest {}),
+        )
+        .await
+    }
+
+    async fn list_post_revisions(
+        &self,
+        in_req: Request<ListPostRevisionsRequest>,
+    ) -> Result<Response<PostRevisionList>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::list_post_revisions(
+            self,
+            authorized(
+                claims,
+                proto::v2::ListPostRevisionsRequest {
+                    post_id: in_req.post_id,
+                },
+            ),
+        )
+        .await
+    }
+
+    async fn get_post_revision(
+        &self,
+        in_req: Request<GetPostRevisionRequest>,
+    ) -> Result<Response<PostRevision>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::get_post_revision(
+            self,
+            authorized(
+                claims,
+                proto::v2::GetPostRevisionRequest {
+                    post_id: in_req.post_id,
+                    revision: in_req.revision,
+                },
+            ),
+        )
+        .await
+    }
+
+    async fn diff_post_revisions(
+        &self,
+        in_req: Request<DiffPostRevisionsRequest>,
+    ) -> Result<Response<PostRevisionDiff>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::diff_post_revisions(
+            self,
+            authorized(
+                claims,
+                proto::v2::DiffPostRevisionsRequest {
+                    post_id: in_req.post_id,
+                    from: in_req.from,
+                    to: in_req.to,
+                },
+            ),
+        )
+        .await
+    }
+
+    async fn restore_post_revision(
+        &self,
+        in_req: Request<RestorePostRevisionRequest>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::restore_post_revision(
+            self,
+            authorized(
+                claims,
+                proto::v2::RestorePostRevisionRequest {
+                    post_id: in_req.post_id,
+                    revision: in_req.revision,
+                    expected_version: in_req.expected_version,
+                },
+            ),
+        )
+        .await
+    }
+
+    async fn list_trash(
+        &self,
+        in_req: Request<ListTrashRequest>,
     ) -> Result<Response<GetPostsResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
         let in_req = in_req.into_inner();
+        BlogServiceV2::list_trash(
+            self,
+            authorized(
+                claims,
+                proto::v2::ListTrashRequest {
+                    offset: in_req.offset,
+                    limit: in_req.limit,
+                },
+            ),
+        )
+        .await
+    }
 
-        let blog_service = self.app_state.blog_service.clone();
-        let mut get_posts_req = blog_service::GetPostsReq::default();
-        get_posts_req.offset = Some(in_req.offset);
-        get_posts_req.limit = Some(in_req.limit);
+    async fn restore_post(
+        &self,
+        in_req: Request<RestorePostRequest>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::restore_post(
+            self,
+            authorized(
+                claims,
+                proto::v2::RestorePostRequest {
+                    post_id: in_req.post_id,
+                },
+            ),
+        )
+        .await
+    }
 
-        let out_post_info = blog_service.get_posts(get_posts_req).await?;
+    async fn purge_post(
+        &self,
+        in_req: Request<PurgePostRequest>,
+    ) -> Result<Response<DeletePostResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::purge_post(
+            self,
+            authorized(
+                claims,
+                proto::v2::PurgePostRequest {
+                    post_id: in_req.post_id,
+                },
+            ),
+        )
+        .await
+    }
 
-        let mut res = GetPostsResponse::default();
-        res.offset = out_post_info.offset;
-        res.limit = out_post_info.limit;
-        let posts: Vec<PostInfo> = out_post_info
-            .posts
-            .into_iter()
-            .map(|item| PostInfo::from(item))
-            .collect();
-        res.posts_info = posts;
-        Ok(Response::new(res))
+    async fn list_comments(
+        &self,
+        in_req: Request<PostId>,
+    ) -> Result<Response<CommentList>, Status> {
+        let viewer = viewer(&in_req);
+        let in_req = in_req.into_inner();
+        let comment_service = self.app_state.comment_service.clone();
+        let post_id = blog_service::PostId { id: in_req.id };
+        let comments = comment_service.list_comments(viewer, post_id).await?;
+        Ok(Response::new(CommentList {
+            comments: comments.into_iter().map(Comment::from).collect(),
+        }))
+    }
+
+    async fn add_comment(
+        &self,
+        in_req: Request<AddCommentRequest>,
+    ) -> Result<Response<Comment>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::add_comment(
+            self,
+            authorized(
+                claims,
+                proto::v2::AddCommentRequest {
+                    post_id: in_req.post_id,
+                    content: in_req.content,
+                    parent_id: in_req.parent_id,
+                },
+            ),
+        )
+        .await
+    }
+
+    async fn update_comment(
+        &self,
+        in_req: Request<UpdateCommentRequest>,
+    ) -> Result<Response<Comment>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::update_comment(
+            self,
+            authorized(
+                claims,
+                proto::v2::UpdateCommentRequest {
+                    post_id: in_req.post_id,
+                    comment_id: in_req.comment_id,
+                    content: in_req.content,
+                },
+            ),
+        )
+        .await
+    }
+
+    async fn delete_comment(
+        &self,
+        in_req: Request<DeleteCommentRequest>,
+    ) -> Result<Response<DeleteCommentResponse>, Status> {
+        let claims = self
+            .legacy_claims(&in_req, in_req.get_ref().reg_user.as_ref())
+            .await?;
+        let in_req = in_req.into_inner();
+        BlogServiceV2::delete_comment(
+            self,
+            authorized(
+                claims,
+                proto::v2::DeleteCommentRequest {
+                    post_id: in_req.post_id,
+                    comment_id: in_req.comment_id,
+                },
+            ),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_conflict_is_failed_precondition() {
+        let status = Status::from(AppError::VersionConflict("stale".to_string()));
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(status.message(), "stale");
     }
 }
@@ -0,0 +1,596 @@
+use chrono::{DateTime, Utc};
+use tonic::{Request, Response, Status};
+
+use super::proto::blog_service_server::BlogService as BlogServiceV1;
+use super::proto::v2::blog_service_server::BlogService;
+use super::proto::v2::*;
+use super::proto::{
+    AccountResponse, Comment, CommentList, DeleteCommentResponse, DeletePostResponse,
+    DisableTotpResponse, GetPostsReq, GetPostsResponse, ListTagsRequest, LoginRequest,
+    LogoutResponse, PasswordResetConfirmRequest, PasswordResetRequest, PasswordResetResponse,
+    PersonalToken, PersonalTokenList, PostId, PostInfo, PostRevision, PostRevisionDiff,
+    PostRevisionList, PostSlug, RecoveryCodes, RefreshRequest, RegisterRequest, RegisteredUser,
+    RevokePersonalTokenResponse, RevokeSessionResponse, SearchPostsRequest, SearchPostsResponse,
+    Session, SessionList, SetUserRoleResponse, TagList, TotpEnrollment, VerifyEmailRequest,
+    VerifyEmailResponse, VerifyTwoFactorRequest,
+};
+use super::{BlogGrpcService, client_info};
+use crate::application::{
+    account_service, auth_service, blog_service, comment_service, personal_token_service,
+    two_factor_service,
+};
+use crate::domain::content_format::ContentFormat;
+use crate::domain::post_status::PostStatus;
+use crate::infrastructure::jwt::Claims;
+
+/// Claims put into the request by the auth interceptor.
+fn claims<T>(in_req: &Request<T>) -> Result<Claims, Status> {
+    in_req
+        .extensions()
+        .get::<Claims>()
+        .cloned()
+        .ok_or_else(|| Status::unauthenticated("missing bearer"))
+}
+
+fn auth_user<T>(in_req: &Request<T>) -> Result<blog_service::AuthUser, Status> {
+    claims(in_req).map(blog_service::AuthUser::from)
+}
+
+/// Parses the status and the publication time of a post.
+fn publication(
+    status: Option<String>,
+    publish_at: Option<String>,
+) -> Result<(Option<PostStatus>, Option<DateTime<Utc>>), Status> {
+    let status = status
+        .map(|status| status.parse::<PostStatus>())
+        .transpose()?;
+    let publish_at = publish_at
+        .map(|publish_at| DateTime::parse_from_rfc3339(&publish_at))
+        .transpose()
+        .map_err(|_| Status::invalid_argument("publish_at must be an RFC 3339 time"))?
+        .map(|publish_at| publish_at.with_timezone(&Utc));
+    Ok((status, publish_at))
+}
+
+fn content_format(content_format: Option<String>) -> Result<Option<ContentFormat>, Status> {
+    Ok(content_format
+        .map(|content_format| content_format.parse::<ContentFormat>())
+        .transpose()?)
+}
+
+#[tonic::async_trait]
+impl BlogService for BlogGrpcService {
+    async fn register(
+        &self,
+        in_req: Request<RegisterRequest>,
+    ) -> Result<Response<RegisteredUser>, Status> {
+        BlogServiceV1::register(self, in_req).await
+    }
+
+    async fn login(
+        &self,
+        in_req: Request<LoginRequest>,
+    ) -> Result<Response<RegisteredUser>, Status> {
+        BlogServiceV1::login(self, in_req).await
+    }
+
+    async fn refresh(
+        &self,
+        in_req: Request<RefreshRequest>,
+    ) -> Result<Response<RegisteredUser>, Status> {
+        BlogServiceV1::refresh(self, in_req).await
+    }
+
+    async fn verify_two_factor(
+        &self,
+        in_req: Request<VerifyTwoFactorRequest>,
+    ) -> Result<Response<RegisteredUser>, Status> {
+        BlogServiceV1::verify_two_factor(self, in_req).await
+    }
+
+    async fn enroll_totp(
+        &self,
+        in_req: Request<EnrollTotpRequest>,
+    ) -> Result<Response<TotpEnrollment>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let two_factor_service = self.app_state.two_factor_service.clone();
+        let enrollment = two_factor_service.enroll(auth_user).await?;
+        Ok(Response::new(TotpEnrollment {
+            secret: enrollment.secret,
+            otpauth_uri: enrollment.otpauth_uri,
+        }))
+    }
+
+    async fn confirm_totp(
+        &self,
+        in_req: Request<TotpCodeRequest>,
+    ) -> Result<Response<RecoveryCodes>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let two_factor_service = self.app_state.two_factor_service.clone();
+        let code_request = two_factor_service::TotpCodeReq { code: in_req.code };
+        let codes = two_factor_service.confirm(auth_user, code_request).await?;
+        Ok(Response::new(RecoveryCodes {
+            recovery_codes: codes.recovery_codes,
+        }))
+    }
+
+    async fn disable_totp(
+        &self,
+        in_req: Request<TotpCodeRequest>,
+    ) -> Result<Response<DisableTotpResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let two_factor_service = self.app_state.two_factor_service.clone();
+        let code_request = two_factor_service::TotpCodeReq { code: in_req.code };
+        two_factor_service.disable(auth_user, code_request).await?;
+        Ok(Response::new(DisableTotpResponse {}))
+    }
+
+    async fn logout(
+        &self,
+        in_req: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutResponse>, Status> {
+        let claims = claims(&in_req)?;
+        let in_req = in_req.into_inner();
+        let auth_service = self.app_state.auth_service.clone();
+        let logout_request = auth_service::LogoutReq {
+            refresh_token: in_req.refresh_token.filter(|token| !token.is_empty()),
+        };
+        auth_service.logout(claims, logout_request).await?;
+        Ok(Response::new(LogoutResponse {}))
+    }
+
+    async fn request_password_reset(
+        &self,
+        in_req: Request<PasswordResetRequest>,
+    ) -> Result<Response<PasswordResetResponse>, Status> {
+        BlogServiceV1::request_password_reset(self, in_req).await
+    }
+
+    async fn confirm_password_reset(
+        &self,
+        in_req: Request<PasswordResetConfirmRequest>,
+    ) -> Result<Response<PasswordResetResponse>, Status> {
+        BlogServiceV1::confirm_password_reset(self, in_req).await
+    }
+
+    async fn verify_email(
+        &self,
+        in_req: Request<VerifyEmailRequest>,
+    ) -> Result<Response<VerifyEmailResponse>, Status> {
+        BlogServiceV1::verify_email(self, in_req).await
+    }
+
+    async fn resend_verification(
+        &self,
+        in_req: Request<ResendVerificationRequest>,
+    ) -> Result<Response<VerifyEmailResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let email_verification_service = self.app_state.email_verification_service.clone();
+        email_verification_service
+            .resend_verification(auth_user)
+            .await?;
+        Ok(Response::new(VerifyEmailResponse {}))
+    }
+
+    async fn create_post(
+        &self,
+        in_req: Request<CreatePostRequest>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let Some(in_new_post) = in_req.new_post else {
+            return Err(Status::failed_precondition("new post not present"));
+        };
+
+        let (status, publish_at) = publication(in_new_post.status, in_new_post.publish_at)?;
+        let blog_service = self.app_state.blog_service.clone();
+        let new_post = blog_service::NewPost {
+            title: in_new_post.title,
+            content: in_new_post.content,
+            status,
+            publish_at,
+            tags: in_new_post.tags,
+            content_format: content_format(in_new_post.content_format)?,
+        };
+
+        let out_post_info = blog_service.create_post(auth_user, new_post).await?;
+        Ok(Response::new(PostInfo::from(out_post_info)))
+    }
+
+    async fn get_post(&self, in_req: Request<PostId>) -> Result<Response<PostInfo>, Status> {
+        BlogServiceV1::get_post(self, in_req).await
+    }
+
+    async fn get_post_by_slug(
+        &self,
+        in_req: Request<PostSlug>,
+    ) -> Result<Response<PostInfo>, Status> {
+        BlogServiceV1::get_post_by_slug(self, in_req).await
+    }
+
+    async fn update_post(
+        &self,
+        in_req: Request<UpdatePostRequest>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let Some(in_update_post) = in_req.update_post else {
+            return Err(Status::failed_precondition("new post not present"));
+        };
+
+        let Some(in_post_id) = in_req.post_id else {
+            return Err(Status::failed_precondition("post id not present"));
+        };
+
+        let (status, publish_at) = publication(in_update_post.status, in_update_post.publish_at)?;
+        let blog_service = self.app_state.blog_service.clone();
+        let update_post = blog_service::UpdatePost {
+            title: in_update_post.title,
+            content: in_update_post.content,
+            status,
+            publish_at,
+            tags: in_update_post.tags.map(|tags| tags.tags),
+            content_format: content_format(in_update_post.content_format)?,
+            expected_version: in_update_post.expected_version,
+        };
+
+        let post_id = blog_service::PostId { id: in_post_id.id };
+        let out_post_info = blog_service
+            .update_post(auth_user, post_id, update_post)
+            .await?;
+        Ok(Response::new(PostInfo::from(out_post_info)))
+    }
+
+    async fn delete_post(
+        &self,
+        in_req: Request<DeletePostRequest>,
+    ) -> Result<Response<DeletePostResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let Some(in_post_id) = in_req.post_id else {
+            return Err(Status::failed_precondition("post id not present"));
+        };
+
+        let blog_service = self.app_state.blog_service.clone();
+        let post_id = blog_service::PostId { id: in_post_id.id };
+        blog_service.delete_post(auth_user, post_id).await?;
+        Ok(Response::new(DeletePostResponse {}))
+    }
+
+    async fn get_posts(
+        &self,
+        in_req: Request<GetPostsReq>,
+    ) -> Result<Response<GetPostsResponse>, Status> {
+        BlogServiceV1::get_posts(self, in_req).await
+    }
+
+    async fn set_user_role(
+        &self,
+        in_req: Request<SetUserRoleRequest>,
+    ) -> Result<Response<SetUserRoleResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let auth_service = self.app_state.auth_service.clone();
+        let role_request = auth_service::SetUserRoleReq { role: in_req.role };
+        auth_service
+            .set_user_role(auth_user, in_req.user_id, role_request)
+            .await?;
+        Ok(Response::new(SetUserRoleResponse {}))
+    }
+
+    async fn create_personal_token(
+        &self,
+        in_req: Request<CreatePersonalTokenRequest>,
+    ) -> Result<Response<PersonalToken>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let personal_token_service = self.app_state.personal_token_service.clone();
+        let create_request = personal_token_service::CreatePersonalTokenReq {
+            name: in_req.name,
+            scopes: in_req.scopes,
+            expires_in_days: in_req.expires_in_days,
+        };
+        let token_info = personal_token_service
+            .create_token(auth_user, create_request)
+            .await?;
+        Ok(Response::new(PersonalToken::from(token_info)))
+    }
+
+    async fn list_personal_tokens(
+        &self,
+        in_req: Request<ListPersonalTokensRequest>,
+    ) -> Result<Response<PersonalTokenList>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let personal_token_service = self.app_state.personal_token_service.clone();
+        let tokens = personal_token_service.list_tokens(auth_user).await?;
+        Ok(Response::new(PersonalTokenList {
+            tokens: tokens.into_iter().map(PersonalToken::from).collect(),
+        }))
+    }
+
+    async fn revoke_personal_token(
+        &self,
+        in_req: Request<RevokePersonalTokenRequest>,
+    ) -> Result<Response<RevokePersonalTokenResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let personal_token_service = self.app_state.personal_token_service.clone();
+        personal_token_service
+            .revoke_token(auth_user, in_req.id)
+            .await?;
+        Ok(Response::new(RevokePersonalTokenResponse {}))
+    }
+
+    async fn change_password(
+        &self,
+        in_req: Request<ChangePasswordRequest>,
+    ) -> Result<Response<AccountResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let client = client_info(&in_req);
+        let in_req = in_req.into_inner();
+        let account_service = self.app_state.account_service.clone();
+        let change_request = account_service::ChangePasswordReq {
+            current_password: in_req.current_password,
+            new_password: in_req.new_password,
+        };
+        account_service
+            .change_password(auth_user, change_request, client)
+            .await?;
+        Ok(Response::new(AccountResponse {}))
+    }
+
+    async fn change_email(
+        &self,
+        in_req: Request<ChangeEmailRequest>,
+    ) -> Result<Response<AccountResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let client = client_info(&in_req);
+        let in_req = in_req.into_inner();
+        let account_service = self.app_state.account_service.clone();
+        let change_request = account_service::ChangeEmailReq {
+            email: in_req.email,
+            password: in_req.password,
+        };
+        account_service
+            .change_email(auth_user, change_request, client)
+            .await?;
+        Ok(Response::new(AccountResponse {}))
+    }
+
+    async fn delete_account(
+        &self,
+        in_req: Request<DeleteAccountRequest>,
+    ) -> Result<Response<AccountResponse>, Status> {
+        let claims = claims(&in_req)?;
+        let client = client_info(&in_req);
+        let in_req = in_req.into_inner();
+        let account_service = self.app_state.account_service.clone();
+        let delete_request = account_service::DeleteAccountReq {
+            password: in_req.password,
+            reassign_posts_to: in_req.reassign_posts_to,
+        };
+        account_service
+            .delete_account(claims, delete_request, client)
+            .await?;
+        Ok(Response::new(AccountResponse {}))
+    }
+
+    async fn list_sessions(
+        &self,
+        in_req: Request<ListSessionsRequest>,
+    ) -> Result<Response<SessionList>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let session_service = self.app_state.session_service.clone();
+        let sessions = session_service.list_sessions(auth_user).await?;
+        Ok(Response::new(SessionList {
+            sessions: sessions.into_iter().map(Session::from).collect(),
+        }))
+    }
+
+    async fn revoke_session(
+        &self,
+        in_req: Request<RevokeSessionRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let session_service = self.app_state.session_service.clone();
+        session_service.revoke_session(auth_user, in_req.id).await?;
+        Ok(Response::new(RevokeSessionResponse {}))
+    }
+
+    async fn revoke_all_sessions(
+        &self,
+        in_req: Request<RevokeAllSessionsRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let session_service = self.app_state.session_service.clone();
+        session_service.revoke_all_sessions(auth_user).await?;
+        Ok(Response::new(RevokeSessionResponse {}))
+    }
+
+    async fn list_post_revisions(
+        &self,
+        in_req: Request<ListPostRevisionsRequest>,
+    ) -> Result<Response<PostRevisionList>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let post_id = blog_service::PostId { id: in_req.post_id };
+        let revisions = blog_service.list_revisions(auth_user, post_id).await?;
+        Ok(Response::new(PostRevisionList {
+            revisions: revisions.into_iter().map(PostRevision::from).collect(),
+        }))
+    }
+
+    async fn get_post_revision(
+        &self,
+        in_req: Request<GetPostRevisionRequest>,
+    ) -> Result<Response<PostRevision>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let revision_id = blog_service::RevisionId {
+            id: in_req.post_id,
+            revision: in_req.revision,
+        };
+        let revision = blog_service.get_revision(auth_user, revision_id).await?;
+        Ok(Response::new(PostRevision::from(revision)))
+    }
+
+    async fn diff_post_revisions(
+        &self,
+        in_req: Request<DiffPostRevisionsRequest>,
+    ) -> Result<Response<PostRevisionDiff>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let post_id = blog_service::PostId { id: in_req.post_id };
+        let diff_request = blog_service::RevisionDiffReq {
+            from: in_req.from,
+            to: in_req.to,
+        };
+        let diff = blog_service
+            .diff_revisions(auth_user, post_id, diff_request)
+            .await?;
+        Ok(Response::new(PostRevisionDiff::from(diff)))
+    }
+
+    async fn restore_post_revision(
+        &self,
+        in_req: Request<RestorePostRevisionRequest>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let revision_id = blog_service::RevisionId {
+            id: in_req.post_id,
+            revision: in_req.revision,
+        };
+        let post_info = blog_service
+            .restore_revision(auth_user, revision_id, in_req.expected_version)
+            .await?;
+        Ok(Response::new(PostInfo::from(post_info)))
+    }
+    async fn list_trash(
+        &self,
+        in_req: Request<ListTrashRequest>,
+    ) -> Result<Response<GetPostsResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let query = blog_service::GetPostsReq {
+            offset: Some(in_req.offset),
+            limit: Some(in_req.limit),
+            ..Default::default()
+        };
+        let trash = blog_service.get_trash(auth_user, query).await?;
+        Ok(Response::new(GetPostsResponse {
+            offset: trash.offset,
+            limit: trash.limit,
+            posts_info: trash.posts.into_iter().map(PostInfo::from).collect(),
+            ..Default::default()
+        }))
+    }
+
+    async fn restore_post(
+        &self,
+        in_req: Request<RestorePostRequest>,
+    ) -> Result<Response<PostInfo>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let post_id = blog_service::PostId { id: in_req.post_id };
+        let post_info = blog_service.restore_post(auth_user, post_id).await?;
+        Ok(Response::new(PostInfo::from(post_info)))
+    }
+
+    async fn purge_post(
+        &self,
+        in_req: Request<PurgePostRequest>,
+    ) -> Result<Response<DeletePostResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let blog_service = self.app_state.blog_service.clone();
+        let post_id = blog_service::PostId { id: in_req.post_id };
+        blog_service.purge_post(auth_user, post_id).await?;
+        Ok(Response::new(DeletePostResponse {}))
+    }
+
+    async fn list_tags(
+        &self,
+        in_req: Request<ListTagsRequest>,
+    ) -> Result<Response<TagList>, Status> {
+        BlogServiceV1::list_tags(self, in_req).await
+    }
+
+    async fn search_posts(
+        &self,
+        in_req: Request<SearchPostsRequest>,
+    ) -> Result<Response<SearchPostsResponse>, Status> {
+        BlogServiceV1::search_posts(self, in_req).await
+    }
+
+    async fn list_comments(
+        &self,
+        in_req: Request<PostId>,
+    ) -> Result<Response<CommentList>, Status> {
+        BlogServiceV1::list_comments(self, in_req).await
+    }
+
+    async fn add_comment(
+        &self,
+        in_req: Request<AddCommentRequest>,
+    ) -> Result<Response<Comment>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let comment_service = self.app_state.comment_service.clone();
+        let post_id = blog_service::PostId { id: in_req.post_id };
+        let new_comment = comment_service::NewComment {
+            content: in_req.content,
+            parent_id: in_req.parent_id,
+        };
+        let comment = comment_service
+            .add_comment(auth_user, post_id, new_comment)
+            .await?;
+        Ok(Response::new(Comment::from(comment)))
+    }
+
+    async fn update_comment(
+        &self,
+        in_req: Request<UpdateCommentRequest>,
+    ) -> Result<Response<Comment>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let comment_service = self.app_state.comment_service.clone();
+        let comment_id = comment_service::CommentId {
+            id: in_req.post_id,
+            comment_id: in_req.comment_id,
+        };
+        let update_comment = comment_service::UpdateComment {
+            content: in_req.content,
+        };
+        let comment = comment_service
+            .update_comment(auth_user, comment_id, update_comment)
+            .await?;
+        Ok(Response::new(Comment::from(comment)))
+    }
+
+    async fn delete_comment(
+        &self,
+        in_req: Request<DeleteCommentRequest>,
+    ) -> Result<Response<DeleteCommentResponse>, Status> {
+        let auth_user = auth_user(&in_req)?;
+        let in_req = in_req.into_inner();
+        let comment_service = self.app_state.comment_service.clone();
+        let comment_id = comment_service::CommentId {
+            id: in_req.post_id,
+            comment_id: in_req.comment_id,
+        };
+        comment_service
+            .delete_comment(auth_user, comment_id)
+            .await?;
+        Ok(Response::new(DeleteCommentResponse {}))
+    }
+}
@@ -1,32 +1,243 @@
-use actix_web::{HttpResponse, Result, http::StatusCode, web};
+use actix_web::cookie::{Cookie, SameSite, time::Duration};
+use actix_web::http::header::{self, EntityTag, Header, IfMatch};
+use actix_web::{HttpRequest, HttpResponse, Result, http::StatusCode, web};
 
+use crate::application::account_service::*;
 use crate::application::auth_service::*;
 use crate::application::blog_service::*;
+use crate::application::comment_service::*;
+use crate::application::email_verification_service::*;
+use crate::application::oidc_service::*;
+use crate::application::password_reset_service::*;
+use crate::application::personal_token_service::*;
+use crate::application::session_service::*;
+use crate::application::two_factor_service::*;
 use crate::domain::error::AppError;
 use crate::infrastructure::AppState;
+use crate::infrastructure::jwt::Claims;
+
+/// Keeps the state of an OIDC login in the browser that started it.
+const OIDC_STATE_COOKIE: &str = "oidc_state";
+const OIDC_COOKIE_PATH: &str = "/api/auth/oidc";
+
+fn client_info(http_req: &HttpRequest) -> ClientInfo {
+    let user_agent = http_req
+        .headers()
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|h| h.to_str().ok());
+    ClientInfo::new(http_req.peer_addr().map(|addr| addr.ip()), user_agent)
+}
+
+fn etag(post_info: &PostInfo) -> header::ETag {
+    header::ETag(EntityTag::new_strong(post_info.version.to_string()))
+}
+
+/// Post version required by the `If-Match` header, `None` for a missing header or `*`.
+fn expected_version(http_req: &HttpRequest) -> Result<Option<i64>, AppError> {
+    if !http_req.headers().contains_key(header::IF_MATCH) {
+        return Ok(None);
+    }
+    let tags = match IfMatch::parse(http_req) {
+        Ok(IfMatch::Any) => return Ok(None),
+        Ok(IfMatch::Items(tags)) => tags,
+        Err(_) => return Err(AppError::InvalidRequest("malformed If-Match header".to_string())),
+    };
+    let [tag] = tags.as_slice() else {
+        return Err(AppError::InvalidRequest(
+            "If-Match must hold a single entity tag".to_string(),
+        ));
+    };
+    // Weak tags never match under the strong comparison If-Match requires.
+    match tag.tag().parse::<i64>() {
+        Ok(version) if !tag.weak => Ok(Some(version)),
+        _ => Err(AppError::VersionConflict(format!(
+            "If-Match {tag} doesn't match any post version"
+        ))),
+    }
+}
 
 pub async fn register(
+    http_req: HttpRequest,
     new_user: web::Json<RegisterUserReq>,
     app_state: web::Data<AppState>,
 ) -> Result<HttpResponse, AppError> {
     let req = new_user.into_inner();
     let auth_service = app_state.auth_service.clone();
-    let resp_data = auth_service.register(req).await?;
+    let resp_data = auth_service.register(req, client_info(&http_req)).await?;
     Ok(HttpResponse::Ok()
         .status(StatusCode::CREATED)
         .json(resp_data))
 }
 
 pub async fn login(
+    http_req: HttpRequest,
     user: web::Json<LoginUserReq>,
     app_state: web::Data<AppState>,
 ) -> Result<HttpResponse, AppError> {
     let req = user.into_inner();
     let auth_service = app_state.auth_service.clone();
-    let resp_data = auth_service.login(req).await?;
+    let resp_data = auth_service.login(req, client_info(&http_req)).await?;
+    Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
+}
+
+pub async fn verify_two_factor(
+    http_req: HttpRequest,
+    verify_req: web::Json<VerifyTwoFactorReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let auth_service = app_state.auth_service.clone();
+    let resp_data = auth_service
+        .verify_two_factor(verify_req.into_inner(), client_info(&http_req))
+        .await?;
+    Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
+}
+
+pub async fn enroll_totp(
+    auth_user: AuthUser,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let two_factor_service = app_state.two_factor_service.clone();
+    let resp_data = two_factor_service.enroll(auth_user).await?;
+    Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
+}
+
+pub async fn confirm_totp(
+    auth_user: AuthUser,
+    code_req: web::Json<TotpCodeReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let two_factor_service = app_state.two_factor_service.clone();
+    let resp_data = two_factor_service
+        .confirm(auth_user, code_req.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
+}
+
+pub async fn disable_totp(
+    auth_user: AuthUser,
+    code_req: web::Json<TotpCodeReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let two_factor_service = app_state.two_factor_service.clone();
+    two_factor_service
+        .disable(auth_user, code_req.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn refresh(
+    http_req: HttpRequest,
+    refresh_req: web::Json<RefreshTokenReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let req = refresh_req.into_inner();
+    let auth_service = app_state.auth_service.clone();
+    let resp_data = auth_service.refresh(req, client_info(&http_req)).await?;
     Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
 }
 
+pub async fn logout(
+    claims: web::ReqData<Claims>,
+    logout_req: Option<web::Json<LogoutReq>>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let req = logout_req.map(|req| req.into_inner()).unwrap_or_default();
+    let auth_service = app_state.auth_service.clone();
+    auth_service.logout(claims.into_inner(), req).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn start_oidc_login(
+    http_req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let oidc_service = app_state.oidc_service.clone();
+    let (location, state) = oidc_service.start_login().await?;
+    let state_cookie = Cookie::build(OIDC_STATE_COOKIE, state)
+        .path(OIDC_COOKIE_PATH)
+        .http_only(true)
+        .secure(http_req.connection_info().scheme() == "https")
+        // Lax still sends the cookie on the provider's top-level redirect back.
+        .same_site(SameSite::Lax)
+        .max_age(Duration::seconds(oidc_service.login_ttl().num_seconds()))
+        .finish();
+    Ok(HttpResponse::Found()
+        .insert_header((actix_web::http::header::LOCATION, location))
+        .cookie(state_cookie)
+        .finish())
+}
+
+pub async fn finish_oidc_login(
+    http_req: HttpRequest,
+    callback: web::Query<OidcCallbackReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let oidc_service = app_state.oidc_service.clone();
+    let browser_state = http_req
+        .cookie(OIDC_STATE_COOKIE)
+        .map(|cookie| cookie.value().to_string());
+    let resp_data = oidc_service
+        .finish_login(callback.into_inner(), browser_state, client_info(&http_req))
+        .await?;
+
+    let mut state_cookie = Cookie::build(OIDC_STATE_COOKIE, "")
+        .path(OIDC_COOKIE_PATH)
+        .finish();
+    state_cookie.make_removal();
+    Ok(HttpResponse::Ok().cookie(state_cookie).json(resp_data))
+}
+
+pub async fn request_password_reset(
+    reset_req: web::Json<PasswordResetReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let password_reset_service = app_state.password_reset_service.clone();
+    password_reset_service
+        .request_reset(reset_req.into_inner())
+        .await?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+pub async fn confirm_password_reset(
+    confirm_req: web::Json<PasswordResetConfirmReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let password_reset_service = app_state.password_reset_service.clone();
+    password_reset_service
+        .confirm_reset(confirm_req.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn verify_email(
+    verify_req: web::Query<VerifyEmailReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let email_verification_service = app_state.email_verification_service.clone();
+    email_verification_service
+        .verify_email(verify_req.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "verified": true })))
+}
+
+pub async fn resend_verification(
+    auth_user: AuthUser,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let email_verification_service = app_state.email_verification_service.clone();
+    email_verification_service
+        .resend_verification(auth_user)
+        .await?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+pub async fn jwks(app_state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let resp_data = app_state.jwt_service.jwks();
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .json(resp_data))
+}
+
 pub async fn create_post(
     auth_user: AuthUser,
     new_post: web::Json<NewPost>,
@@ -37,22 +248,48 @@ pub async fn create_post(
     let resp_data = blog_service.create_post(auth_user, new_post).await?;
     Ok(HttpResponse::Ok()
         .status(StatusCode::CREATED)
+        .insert_header(etag(&resp_data))
         .json(resp_data))
 }
 
 pub async fn get_post(
+    viewer: Option<AuthUser>,
     post_id: web::Path<PostId>,
     app_state: web::Data<AppState>,
 ) -> Result<HttpResponse, AppError> {
     let blog_service = app_state.blog_service.clone();
     let post_id = post_id.into_inner();
-    let resp_data = blog_service.get_post(post_id).await?;
+    let resp_data = blog_service.get_post(viewer, post_id).await?;
     Ok(HttpResponse::Ok()
         .status(StatusCode::CREATED)
+        .insert_header(etag(&resp_data))
+        .json(resp_data))
+}
+
+/// An outdated slug of a renamed post redirects to the current one.
+pub async fn get_post_by_slug(
+    http_req: HttpRequest,
+    viewer: Option<AuthUser>,
+    post_slug: web::Path<PostSlug>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let post_slug = post_slug.into_inner();
+    let requested = post_slug.slug.clone();
+    let resp_data = blog_service.get_post_by_slug(viewer, post_slug).await?;
+    if resp_data.slug != requested {
+        let (parent, _) = http_req.path().rsplit_once('/').unwrap_or_default();
+        return Ok(HttpResponse::MovedPermanently()
+            .insert_header((header::LOCATION, format!("{parent}/{}", resp_data.slug)))
+            .finish());
+    }
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(&resp_data))
         .json(resp_data))
 }
 
 pub async fn update_post(
+    http_req: HttpRequest,
     auth_user: AuthUser,
     post_id: web::Path<PostId>,
     update_post: web::Json<UpdatePost>,
@@ -60,11 +297,15 @@ pub async fn update_post(
 ) -> Result<HttpResponse, AppError> {
     let blog_service = app_state.blog_service.clone();
     let post_id = post_id.into_inner();
-    let update_post = update_post.into_inner();
+    let mut update_post = update_post.into_inner();
+    update_post.expected_version = expected_version(&http_req)?;
     let resp_data = blog_service
         .update_post(auth_user, post_id, update_post)
         .await?;
-    Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
+    Ok(HttpResponse::Ok()
+        .status(StatusCode::OK)
+        .insert_header(etag(&resp_data))
+        .json(resp_data))
 }
 
 pub async fn delete_post(
@@ -74,18 +315,334 @@ pub async fn delete_post(
 ) -> Result<HttpResponse, AppError> {
     let blog_service = app_state.blog_service.clone();
     let post_id = post_id.into_inner();
-    let resp_data = blog_service.delete_post(auth_user, post_id).await?;
+    blog_service.delete_post(auth_user, post_id).await?;
     Ok(HttpResponse::Ok()
         .status(StatusCode::NO_CONTENT)
-        .json(resp_data))
+        .json(()))
 }
 
 pub async fn get_posts(
+    viewer: Option<AuthUser>,
     pagination_query: web::Query<GetPostsReq>,
     app_state: web::Data<AppState>,
 ) -> Result<HttpResponse, AppError> {
     let blog_service = app_state.blog_service.clone();
     let query = pagination_query.into_inner();
-    let resp_data = blog_service.get_posts(query).await?;
+    let resp_data = blog_service.get_posts(viewer, query).await?;
+    Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
+}
+
+pub async fn search_posts(
+    viewer: Option<AuthUser>,
+    query: web::Query<SearchPostsReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service
+        .search_posts(viewer, query.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(resp_data))
+}
+
+pub async fn list_tags(app_state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service.list_tags().await?;
+    Ok(HttpResponse::Ok().json(resp_data))
+}
+
+pub async fn get_trash(
+    auth_user: AuthUser,
+    query: web::Query<GetPostsReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service
+        .get_trash(auth_user, query.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(resp_data))
+}
+
+pub async fn restore_post(
+    auth_user: AuthUser,
+    post_id: web::Path<PostId>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service
+        .restore_post(auth_user, post_id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(&resp_data))
+        .json(resp_data))
+}
+
+pub async fn purge_post(
+    auth_user: AuthUser,
+    post_id: web::Path<PostId>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    blog_service
+        .purge_post(auth_user, post_id.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn list_comments(
+    viewer: Option<AuthUser>,
+    post_id: web::Path<PostId>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let comment_service = app_state.comment_service.clone();
+    let comments = comment_service
+        .list_comments(viewer, post_id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(comments))
+}
+
+pub async fn add_comment(
+    auth_user: AuthUser,
+    post_id: web::Path<PostId>,
+    new_comment: web::Json<NewComment>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let comment_service = app_state.comment_service.clone();
+    let comment = comment_service
+        .add_comment(auth_user, post_id.into_inner(), new_comment.into_inner())
+        .await?;
+    Ok(HttpResponse::Created().json(comment))
+}
+
+pub async fn update_comment(
+    auth_user: AuthUser,
+    comment_id: web::Path<CommentId>,
+    update_comment: web::Json<UpdateComment>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let comment_service = app_state.comment_service.clone();
+    let comment = comment_service
+        .update_comment(
+            auth_user,
+            comment_id.into_inner(),
+            update_comment.into_inner(),
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(comment))
+}
+
+pub async fn delete_comment(
+    auth_user: AuthUser,
+    comment_id: web::Path<CommentId>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let comment_service = app_state.comment_service.clone();
+    comment_service
+        .delete_comment(auth_user, comment_id.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn list_revisions(
+    auth_user: AuthUser,
+    post_id: web::Path<PostId>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service
+        .list_revisions(auth_user, post_id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(resp_data))
+}
+
+pub async fn get_revision(
+    auth_user: AuthUser,
+    revision_id: web::Path<RevisionId>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service
+        .get_revision(auth_user, revision_id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(resp_data))
+}
+
+pub async fn diff_revisions(
+    auth_user: AuthUser,
+    post_id: web::Path<PostId>,
+    diff_query: web::Query<RevisionDiffReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service
+        .diff_revisions(auth_user, post_id.into_inner(), diff_query.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(resp_data))
+}
+
+pub async fn restore_revision(
+    http_req: HttpRequest,
+    auth_user: AuthUser,
+    revision_id: web::Path<RevisionId>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let blog_service = app_state.blog_service.clone();
+    let resp_data = blog_service
+        .restore_revision(auth_user, revision_id.into_inner(), expected_version(&http_req)?)
+        .await?;
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(&resp_data))
+        .json(resp_data))
+}
+
+pub async fn set_user_role(
+    auth_user: AuthUser,
+    user_id: web::Path<i64>,
+    role_req: web::Json<SetUserRoleReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let auth_service = app_state.auth_service.clone();
+    auth_service
+        .set_user_role(auth_user, user_id.into_inner(), role_req.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn create_personal_token(
+    auth_user: AuthUser,
+    create_req: web::Json<CreatePersonalTokenReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let personal_token_service = app_state.personal_token_service.clone();
+    let resp_data = personal_token_service
+        .create_token(auth_user, create_req.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok()
+        .status(StatusCode::CREATED)
+        .json(resp_data))
+}
+
+pub async fn list_personal_tokens(
+    auth_user: AuthUser,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let personal_token_service = app_state.personal_token_service.clone();
+    let resp_data = personal_token_service.list_tokens(auth_user).await?;
+    Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
+}
+
+pub async fn revoke_personal_token(
+    auth_user: AuthUser,
+    token_id: web::Path<i64>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let personal_token_service = app_state.personal_token_service.clone();
+    personal_token_service
+        .revoke_token(auth_user, token_id.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn list_sessions(
+    auth_user: AuthUser,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let session_service = app_state.session_service.clone();
+    let resp_data = session_service.list_sessions(auth_user).await?;
     Ok(HttpResponse::Ok().status(StatusCode::OK).json(resp_data))
 }
+
+pub async fn revoke_session(
+    auth_user: AuthUser,
+    session_id: web::Path<i64>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let session_service = app_state.session_service.clone();
+    session_service
+        .revoke_session(auth_user, session_id.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn revoke_all_sessions(
+    auth_user: AuthUser,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let session_service = app_state.session_service.clone();
+    session_service.revoke_all_sessions(auth_user).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn change_password(
+    http_req: HttpRequest,
+    auth_user: AuthUser,
+    change_req: web::Json<ChangePasswordReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let account_service = app_state.account_service.clone();
+    account_service
+        .change_password(auth_user, change_req.into_inner(), client_info(&http_req))
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn change_email(
+    http_req: HttpRequest,
+    auth_user: AuthUser,
+    change_req: web::Json<ChangeEmailReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let account_service = app_state.account_service.clone();
+    account_service
+        .change_email(auth_user, change_req.into_inner(), client_info(&http_req))
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn delete_account(
+    http_req: HttpRequest,
+    claims: web::ReqData<Claims>,
+    delete_req: web::Json<DeleteAccountReq>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let account_service = app_state.account_service.clone();
+    account_service
+        .delete_account(claims.into_inner(), delete_req.into_inner(), client_info(&http_req))
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use actix_web::test::TestRequest;
+
+    fn if_match(value: &str) -> Result<Option<i64>, AppError> {
+        let http_req = TestRequest::default()
+            .insert_header((header::IF_MATCH, value))
+            .to_http_request();
+        expected_version(&http_req)
+    }
+
+    #[test]
+    fn test_expected_version() {
+        let http_req = TestRequest::default().to_http_request();
+        assert_eq!(expected_version(&http_req).unwrap(), None);
+        assert_eq!(if_match("*").unwrap(), None);
+        assert_eq!(if_match("\"7\"").unwrap(), Some(7));
+    }
+
+    #[test]
+    fn test_expected_version_rejects_other_tags() {
+        assert!(matches!(if_match("\"1\", \"2\""), Err(AppError::InvalidRequest(_))));
+        assert!(matches!(if_match("7"), Err(AppError::InvalidRequest(_))));
+        assert!(matches!(if_match("W/\"7\""), Err(AppError::VersionConflict(_))));
+        assert!(matches!(if_match("\"draft\""), Err(AppError::VersionConflict(_))));
+    }
+
+    #[test]
+    fn test_version_conflict_is_precondition_failed() {
+        let err = AppError::VersionConflict("stale".to_string());
+        assert_eq!(err.error_response().status(), StatusCode::PRECONDITION_FAILED);
+    }
+}
@@ -7,6 +7,7 @@ use actix_web::{
 use std::future::{Future, Ready, ready};
 use std::{
     pin::Pin,
+    rc::Rc,
     task::{Context, Poll},
 };
 
@@ -27,11 +28,38 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(JwtAuth { service }))
+        ready(Ok(JwtAuth {
+            service: Rc::new(service),
+            required: true,
+        }))
     }
 }
+
+/// Like `Jwt`, but lets requests without a bearer through as anonymous.
+pub struct OptionalJwt;
+
+impl<S, B> Transform<S, ServiceRequest> for OptionalJwt
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = JwtAuth<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(JwtAuth {
+            service: Rc::new(service),
+            required: false,
+        }))
+    }
+}
+
 pub struct JwtAuth<S> {
-    service: S,
+    service: Rc<S>,
+    required: bool,
 }
 
 impl<S, B> Service<ServiceRequest> for JwtAuth<S>
@@ -56,29 +84,32 @@ where
             .map(str::to_owned);
 
         let Some(token) = token else {
+            if !self.required {
+                return Box::pin(self.service.call(req));
+            }
             return Box::pin(async move {
                 Err(error::AppError::Unauthorized("missing bearer".to_string()).into())
             });
         };
 
-        let jwt_service = if let Some(data) = req.app_data::<web::Data<AppState>>() {
-            data.jwt_service.clone()
+        let app_state = if let Some(data) = req.app_data::<web::Data<AppState>>() {
+            data.clone()
         } else {
             return Box::pin(
                 async move { Err(error::AppError::InternalError(String::new()).into()) },
             );
         };
 
-        let Some(claims) = jwt_service.verify_token(&token) else {
-            return Box::pin(async move {
-                Err(error::AppError::Unauthorized("invalid credentials".to_string()).into())
-            });
-        };
-
-        req.extensions_mut().insert(claims);
-
-        let handler_service = self.service.call(req);
+        let service = self.service.clone();
+        Box::pin(async move {
+            let Some(claims) = app_state.authenticate(&token).await else {
+                return Err(
+                    error::AppError::Unauthorized("invalid credentials".to_string()).into(),
+                );
+            };
 
-        Box::pin(async move { handler_service.await })
+            req.extensions_mut().insert(claims);
+            service.call(req).await
+        })
     }
 }
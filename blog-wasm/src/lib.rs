@@ -8,28 +8,30 @@ use jsonwebtoken::dangerous;
 use serde_json::json;
 
 const TOKEN_KEY: &str = "blog_token";
+const REFRESH_TOKEN_KEY: &str = "blog_refresh_token";
 
 #[wasm_bindgen]
 #[derive(Serialize, Deserialize)]
 pub struct BlogApp {
     server_addr: String,
     token: Option<String>,
+    refresh_token: Option<String>,
+    /// Challenge of a login waiting for the second factor.
+    challenge_token: Option<String>,
 }
 
 #[wasm_bindgen]
 impl BlogApp {
     #[wasm_bindgen(constructor)]
     pub fn new(server_addr: String) -> Result<Self, JsValue> {
-        let token = match Self::load_token() {
-            Ok(val) => Some(val),
-            Err(_) => {
-                None
-            }
-        };
+        let token = Self::load_token(TOKEN_KEY).ok();
+        let refresh_token = Self::load_token(REFRESH_TOKEN_KEY).ok();
 
         let blog_app = BlogApp {
             server_addr: format!("{server_addr}/api"),
             token,
+            refresh_token,
+            challenge_token: None,
         };
         Ok(blog_app)
     }
@@ -45,14 +47,14 @@ impl BlogApp {
         Ok(storage)
     }
 
-    fn save_token_to_storage(token: &str) -> Result<(), JsValue> {
+    fn save_token_to_storage(key: &str, token: &str) -> Result<(), JsValue> {
         let storage = Self::get_local_storage()?;
-        storage.set_item(TOKEN_KEY, token)
+        storage.set_item(key, token)
     }
 
-    fn load_token() -> Result<String, JsValue> {
+    fn load_token(key: &str) -> Result<String, JsValue> {
         let storage = Self::get_local_storage()?;
-        let Some(token) = storage.get_item(TOKEN_KEY)? else {
+        let Some(token) = storage.get_item(key)? else {
             return Err(JsValue::from_str("Can't get token from storage"));
         };
         Ok(token)
@@ -60,11 +62,20 @@ impl BlogApp {
 
     fn remove_token() -> Result<(), JsValue> {
         let storage = Self::get_local_storage()?;
-        storage.remove_item(TOKEN_KEY)
+        storage.remove_item(TOKEN_KEY)?;
+        storage.remove_item(REFRESH_TOKEN_KEY)
+    }
+
+    fn store_reg_user(&mut self, reg_user: pod::RegisteredUser) -> Result<(), JsValue> {
+        Self::save_token_to_storage(TOKEN_KEY, &reg_user.token)?;
+        Self::save_token_to_storage(REFRESH_TOKEN_KEY, &reg_user.refresh_token)?;
+        self.token = Some(reg_user.token);
+        self.refresh_token = Some(reg_user.refresh_token);
+        Ok(())
     }
 
     fn make_js_result(json_str: &str) -> Result<JsValue, JsValue> {
-        js_sys::JSON::parse(&json_str)
+        js_sys::JSON::parse(json_str)
     }
 
     fn make_success_response() -> Result<JsValue, JsValue> {
@@ -110,8 +121,7 @@ impl BlogApp {
             .map_err(|e| e.to_string())?;
 
         let reg_user = resp.json::<pod::RegisteredUser>().await.map_err(|e| e.to_string())?;
-        Self::save_token_to_storage(&reg_user.token)?;
-        self.token = Some(reg_user.token);
+        self.store_reg_user(reg_user)?;
 
         Self::make_success_response()
     }
@@ -131,17 +141,100 @@ impl BlogApp {
             .await
             .map_err(|e| e.to_string())?;
 
+        let login_resp = resp.json::<pod::LoginResp>().await.map_err(|e| e.to_string())?;
+        match login_resp {
+            pod::LoginResp::Authenticated(reg_user) => {
+                self.store_reg_user(reg_user)?;
+                Self::make_success_response()
+            }
+            pod::LoginResp::TwoFactorRequired(challenge) => {
+                self.challenge_token = Some(challenge.challenge_token);
+                Self::make_js_result(&json!({"two_factor_required": true}).to_string())
+            }
+        }
+    }
+
+    /// Finishes a login that answered `two_factor_required` with a code from the
+    /// authenticator app or a recovery code.
+    #[wasm_bindgen]
+    pub async fn verify_two_factor(&mut self, code: String) -> Result<JsValue, JsValue> {
+        let Some(challenge_token) = self.challenge_token.clone() else {
+            return Err(JsValue::from_str("No login is waiting for a two-factor code"));
+        };
+
+        let url = format!("{}/auth/2fa/verify", self.server_addr);
+        let req = pod::VerifyTwoFactorReq {
+            challenge_token,
+            code,
+        };
+
+        let resp = Request::post(&url)
+            .json(&req)
+            .map_err(|e| e.to_string())?
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !resp.ok() {
+            return Err(JsValue::from_str("Invalid two-factor code"));
+        }
+
+        let reg_user = resp.json::<pod::RegisteredUser>().await.map_err(|e| e.to_string())?;
+        self.challenge_token = None;
+        self.store_reg_user(reg_user)?;
+
+        Self::make_success_response()
+    }
+
+    #[wasm_bindgen]
+    pub async fn refresh(&mut self) -> Result<JsValue, JsValue> {
+        let Some(refresh_token) = self.refresh_token.clone() else {
+            return Err(JsValue::from_str("Auth needed"));
+        };
+
+        let url = format!("{}/auth/refresh", self.server_addr);
+        let req = pod::RefreshTokenReq { refresh_token };
+
+        let resp = Request::post(&url)
+            .json(&req)
+            .map_err(|e| e.to_string())?
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !resp.ok() {
+            Self::remove_token()?;
+            self.token = None;
+            self.refresh_token = None;
+            return Err(JsValue::from_str("Session expired"));
+        }
+
         let reg_user = resp.json::<pod::RegisteredUser>().await.map_err(|e| e.to_string())?;
-        Self::save_token_to_storage(&reg_user.token)?;
-        self.token = Some(reg_user.token);
+        self.store_reg_user(reg_user)?;
 
         Self::make_success_response()
     }
 
     #[wasm_bindgen]
     pub async fn logout(&mut self) -> Result<JsValue, JsValue> {
+        if let Some(token) = self.token.as_ref() {
+            let url = format!("{}/auth/logout", self.server_addr);
+            let req = pod::LogoutReq {
+                refresh_token: self.refresh_token.clone(),
+            };
+
+            // The local session is dropped even if the server can't be reached.
+            let _resp = Request::post(&url)
+                .header("Authorization", &format!("Bearer {}", token))
+                .json(&req)
+                .map_err(|e| e.to_string())?
+                .send()
+                .await;
+        }
+
         Self::remove_token()?;
         self.token = None;
+        self.refresh_token = None;
         Self::make_success_response()
     }
 
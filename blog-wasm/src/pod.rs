@@ -13,9 +13,40 @@ pub struct LoginUserReq {
     pub password: String,
 }
 
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RefreshTokenReq {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct LogoutReq {
+    pub refresh_token: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct RegisteredUser {
     pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct TwoFactorChallenge {
+    pub challenge_token: String,
+    pub expires_in: i64,
+}
+
+/// Login answer: tokens, or a challenge for accounts with two-factor authentication.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum LoginResp {
+    Authenticated(RegisteredUser),
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct VerifyTwoFactorReq {
+    pub challenge_token: String,
+    pub code: String,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -46,6 +77,8 @@ pub struct PostInfo {
     pub id: i64,
     pub title: String,
     pub content: String,
+    #[serde(default)]
+    pub content_html: String,
     pub author_id: i64,
     pub created_at: String,
     pub updated_at: String,